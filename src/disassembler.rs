@@ -0,0 +1,681 @@
+//! Follows the control flow from the entry point to find out which code is ARM and which Thumb.
+//!
+//! Only direct branches, `BX`/`BLX` to a register, and `MOV`, `ADD`, `SUB`, `ORR` and literal
+//! loads writing the PC are followed, the value has to be known from a literal load, `ADR` or a
+//! move of an immediate. Returns through the stack and `LDM` are not followed. Code that isn't
+//! reached is decoded in the state of the code before it.
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Display;
+use std::ops::Bound;
+
+use crate::instructions::arm::adresssing::{Indexing, ShifterOperand};
+use crate::instructions::arm::branch::BranchInstruction;
+use crate::instructions::arm::dataprosessing::DataProssessingInstruction;
+use crate::instructions::arm::loadandstore::{LoadAndStoreInstruction, LoadAndStoreMultiple};
+use crate::instructions::arm::unconditional::UnconditionalInstruction;
+use crate::instructions::arm::{ArmInstruction, PartialArmInstruction};
+use crate::instructions::thumb::branch::BranchInstruction as ThumbBranchInstruction;
+use crate::instructions::thumb::dataprosessing::{
+    DataProssessingInstruction as ThumbDataProssessingInstruction, Operands,
+};
+use crate::instructions::thumb::loadandstore::LoadAndStoreInstruction as ThumbLoadAndStoreInstruction;
+use crate::instructions::thumb::ThumbInstruction;
+use crate::instructions::{Adress, Architecture, Decoded, Register, RegisterList};
+
+///The instruction set state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Arm,
+    Thumb,
+}
+impl Mode {
+    ///The state a `BX` to `adress` switches to.
+    pub const fn of_interworking_adress(adress: u32) -> Self {
+        match adress & 1 {
+            0 => Self::Arm,
+            _ => Self::Thumb,
+        }
+    }
+}
+impl Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mode::Arm => write!(f, "ARM"),
+            Mode::Thumb => write!(f, "Thumb"),
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Arm(Decoded<ArmInstruction>),
+    Thumb(Decoded<ThumbInstruction>),
+}
+impl Instruction {
+    pub fn mode(&self) -> Mode {
+        match self {
+            Self::Arm(_) => Mode::Arm,
+            Self::Thumb(_) => Mode::Thumb,
+        }
+    }
+    ///The size in bytes.
+    pub fn size(&self) -> u32 {
+        match self {
+            Self::Arm(_) => 4,
+            Self::Thumb(decoded) => decoded.instruction.size(),
+        }
+    }
+}
+///One instruction of the listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line {
+    pub adress: Adress,
+    ///The encoding, two halfwords for the joined BL and BLX (1) with the first one in the low
+    ///half.
+    pub raw: u32,
+    pub instruction: Instruction,
+    ///Whether the control flow from the entry point reaches the instruction.
+    pub reached: bool,
+}
+///A binary loaded at `base`.
+#[derive(Debug, Clone, Copy)]
+pub struct Image<'a> {
+    pub bytes: &'a [u8],
+    pub base: Adress,
+    pub architecture: Architecture,
+}
+impl Image<'_> {
+    fn offset(&self, adress: Adress, size: u32) -> Option<usize> {
+        let offset = adress.value().checked_sub(self.base.value())? as usize;
+        (offset + size as usize <= self.bytes.len()).then_some(offset)
+    }
+    pub fn word(&self, adress: Adress) -> Option<u32> {
+        let offset = self.offset(adress, 4)?;
+        let bytes = self.bytes[offset..offset + 4].try_into().ok()?;
+        Some(u32::from_le_bytes(bytes))
+    }
+    pub fn halfword(&self, adress: Adress) -> Option<u16> {
+        let offset = self.offset(adress, 2)?;
+        let bytes = self.bytes[offset..offset + 2].try_into().ok()?;
+        Some(u16::from_le_bytes(bytes))
+    }
+    ///Decodes the instruction at `adress` in `mode`, ARM instructions have to be word aligned.
+    pub fn decode(&self, adress: Adress, mode: Mode) -> Option<(u32, Instruction)> {
+        match mode {
+            Mode::Arm if adress.value().is_multiple_of(4) => {
+                let word = self.word(adress)?;
+                let decoded = ArmInstruction::decode(word, self.architecture);
+                Some((word, Instruction::Arm(decoded)))
+            }
+            Mode::Arm => None,
+            Mode::Thumb => {
+                let first = self.halfword(adress)?;
+                let second = self.halfword(Adress::new(adress.value().wrapping_add(2)));
+                let decoded = ThumbInstruction::decode_pair(first, second, self.architecture);
+                let raw = match decoded.instruction.size() {
+                    4 => (second.unwrap_or(0) as u32) << 16 | first as u32,
+                    _ => first as u32,
+                };
+                Some((raw, Instruction::Thumb(decoded)))
+            }
+        }
+    }
+}
+///Where the control flow goes after an instruction.
+struct Flow {
+    falls_through: bool,
+    target: Option<(Adress, Mode)>,
+}
+///The register values known while following the code in a straight line.
+#[derive(Default)]
+struct KnownRegisters([Option<u32>; 16]);
+impl KnownRegisters {
+    fn get(&self, register: Register) -> Option<u32> {
+        self.0[register as usize]
+    }
+    fn set(&mut self, register: Register, value: Option<u32>) {
+        self.0[register as usize] = value;
+    }
+    fn forget(&mut self, registers: RegisterList) {
+        for register in registers.registers() {
+            self.set(register, None);
+        }
+    }
+    fn forget_all(&mut self) {
+        self.0 = Default::default();
+    }
+}
+///Decodes the image, following the control flow from `entry` which is in `mode`.
+pub fn disassemble(image: Image, entry: Adress, mode: Mode) -> Vec<Line> {
+    let reached = trace(image, entry, mode);
+    let end = image.base.value() as u64 + image.bytes.len() as u64;
+    let mut lines = Vec::new();
+    let mut adress = image.base;
+    let mut current = mode;
+    while (adress.value() as u64) < end {
+        let state = reached.get(&adress).copied();
+        if let Some(state) = state {
+            current = state;
+        }
+        // a misaligned or trailing halfword can only be Thumb
+        let mut decoded = image
+            .decode(adress, current)
+            .or_else(|| image.decode(adress, Mode::Thumb));
+        // an instruction running into reached code is cut down to the halfword before it
+        let next = reached
+            .range((Bound::Excluded(adress), Bound::Unbounded))
+            .next()
+            .map(|(next, _)| next.value() - adress.value());
+        if let (Some((_, instruction)), Some(next)) = (decoded, next) {
+            if next < instruction.size() {
+                decoded = image.halfword(adress).map(|half| {
+                    let decoded = ThumbInstruction::decode(half, image.architecture);
+                    (half as u32, Instruction::Thumb(decoded))
+                });
+            }
+        }
+        let Some((raw, instruction)) = decoded else {
+            break;
+        };
+        lines.push(Line {
+            adress,
+            raw,
+            instruction,
+            reached: state.is_some(),
+        });
+        adress = Adress::new(adress.value().wrapping_add(instruction.size()));
+    }
+    lines
+}
+///Finds the state of every instruction the control flow reaches.
+fn trace(image: Image, entry: Adress, mode: Mode) -> BTreeMap<Adress, Mode> {
+    let mut reached = BTreeMap::new();
+    let mut queue = VecDeque::from([(entry, mode)]);
+    while let Some((start, mode)) = queue.pop_front() {
+        let mut known = KnownRegisters::default();
+        let mut adress = start;
+        while !reached.contains_key(&adress) {
+            let Some((_, instruction)) = image.decode(adress, mode) else {
+                break;
+            };
+            reached.insert(adress, mode);
+            let flow = match instruction {
+                Instruction::Arm(decoded) => {
+                    arm_flow(&decoded.instruction, adress, image, &mut known)
+                }
+                Instruction::Thumb(decoded) => {
+                    thumb_flow(&decoded.instruction, adress, image, &mut known)
+                }
+            };
+            if let Some(target) = flow.target {
+                queue.push_back(target);
+            }
+            if !flow.falls_through {
+                break;
+            }
+            adress = Adress::new(adress.value().wrapping_add(instruction.size()));
+        }
+    }
+    reached
+}
+///The target of a `BX` or `BLX` to `register`.
+fn exchange_target(known: &KnownRegisters, register: Register) -> Option<(Adress, Mode)> {
+    known.get(register).map(interworking_target)
+}
+///The target of a branch to `value` that switches to the state in bit 0.
+fn interworking_target(value: u32) -> (Adress, Mode) {
+    write_target(value, Mode::of_interworking_adress(value))
+}
+///The target of writing `value` to the PC in `mode` without interworking.
+fn write_target(value: u32, mode: Mode) -> (Adress, Mode) {
+    let adress = match mode {
+        Mode::Arm => value & !0b11,
+        Mode::Thumb => value & !1,
+    };
+    (Adress::new(adress), mode)
+}
+fn arm_flow(
+    instruction: &ArmInstruction,
+    adress: Adress,
+    image: Image,
+    known: &mut KnownRegisters,
+) -> Flow {
+    let conditional = !instruction.cond.is_always()
+        && !matches!(instruction.op, PartialArmInstruction::Unconditional(_));
+    let pc = adress.arm_pc().value();
+    let mut flow = Flow {
+        falls_through: true,
+        target: None,
+    };
+    match instruction.op {
+        PartialArmInstruction::Undefined(_) => flow.falls_through = false,
+        PartialArmInstruction::Branch(branch) => {
+            match branch {
+                BranchInstruction::B(_) => {
+                    flow.falls_through = conditional;
+                    flow.target = branch.target(adress).map(|target| (target, Mode::Arm));
+                }
+                BranchInstruction::BL(_) => {
+                    flow.target = branch.target(adress).map(|target| (target, Mode::Arm));
+                }
+                BranchInstruction::BX(register) => {
+                    flow.falls_through = conditional;
+                    flow.target = exchange_target(known, register);
+                }
+                BranchInstruction::BLX(register) => {
+                    flow.target = exchange_target(known, register);
+                }
+            }
+            if !matches!(branch, BranchInstruction::B(_) | BranchInstruction::BX(_)) {
+                known.forget_all();
+            }
+        }
+        PartialArmInstruction::Unconditional(UnconditionalInstruction::BLX(offset)) => {
+            flow.target = Some((offset.resolve(adress.arm_pc()), Mode::Thumb));
+            known.forget_all();
+        }
+        PartialArmInstruction::DataProssessing(data) => {
+            let (destination, s, value) = match data {
+                DataProssessingInstruction::MOV(inst) => (
+                    Some(inst.destination),
+                    inst.s,
+                    match inst.shifter {
+                        ShifterOperand::Immediate(immediate) => Some(immediate.value()),
+                        ShifterOperand::Register(register) => known.get(register),
+                        _ => None,
+                    },
+                ),
+                DataProssessingInstruction::ADD(inst)
+                | DataProssessingInstruction::SUB(inst)
+                | DataProssessingInstruction::ORR(inst) => {
+                    let first = match inst.first_operand {
+                        Register::R15 => Some(pc),
+                        register => known.get(register),
+                    };
+                    let value = match (first, inst.shifter) {
+                        (Some(first), ShifterOperand::Immediate(immediate)) => match data {
+                            DataProssessingInstruction::ADD(_) => {
+                                Some(first.wrapping_add(immediate.value()))
+                            }
+                            DataProssessingInstruction::SUB(_) => {
+                                Some(first.wrapping_sub(immediate.value()))
+                            }
+                            _ => Some(first | immediate.value()),
+                        },
+                        _ => None,
+                    };
+                    (Some(inst.destination), inst.s, value)
+                }
+                DataProssessingInstruction::CMN(_)
+                | DataProssessingInstruction::CMP(_)
+                | DataProssessingInstruction::TEQ(_)
+                | DataProssessingInstruction::TST(_) => (None, true, None),
+                DataProssessingInstruction::ADC(inst)
+                | DataProssessingInstruction::AND(inst)
+                | DataProssessingInstruction::BIC(inst)
+                | DataProssessingInstruction::EOR(inst)
+                | DataProssessingInstruction::RSB(inst)
+                | DataProssessingInstruction::RSC(inst)
+                | DataProssessingInstruction::SBC(inst) => (Some(inst.destination), inst.s, None),
+                DataProssessingInstruction::MVN(inst) => (Some(inst.destination), inst.s, None),
+            };
+            if let Some(destination) = destination {
+                // a conditional instruction might not have set it
+                known.set(destination, value.filter(|_| !conditional));
+                if destination == Register::R15 {
+                    flow.falls_through = conditional;
+                    // with S set the CPSR is restored from the SPSR, which can change the state
+                    if !s {
+                        flow.target = value.map(|value| write_target(value, Mode::Arm));
+                    }
+                }
+            }
+        }
+        PartialArmInstruction::LoadAndStore(load_and_store) => {
+            use LoadAndStoreInstruction::*;
+            match load_and_store {
+                LDR(inst) => {
+                    let literal = instruction
+                        .literal(adress)
+                        .and_then(|literal| image.word(literal));
+                    if inst.adressing_mode.indexing != Indexing::Offset {
+                        known.set(inst.adressing_mode.base, None);
+                    }
+                    known.set(inst.destination, literal.filter(|_| !conditional));
+                    if inst.destination == Register::R15 {
+                        flow.falls_through = conditional;
+                        // loading the PC only interworks from ARMv5 on
+                        flow.target = literal.map(|value| match image.architecture {
+                            Architecture::ARMv4T => write_target(value, Mode::Arm),
+                            _ => interworking_target(value),
+                        });
+                    }
+                }
+                STR(inst) | STRB(inst) => {
+                    if inst.adressing_mode.indexing != Indexing::Offset {
+                        known.set(inst.adressing_mode.base, None);
+                    }
+                }
+                LDRB(inst) => {
+                    if inst.adressing_mode.indexing != Indexing::Offset {
+                        known.set(inst.adressing_mode.base, None);
+                    }
+                    known.set(inst.destination, None);
+                }
+                LDRH(inst) | LDRSB(inst) | LDRSH(inst) => {
+                    if inst.adressing_mode.indexing != Indexing::Offset {
+                        known.set(inst.adressing_mode.base, None);
+                    }
+                    known.set(inst.destination, None);
+                }
+                STRH(inst) => {
+                    if inst.adressing_mode.indexing != Indexing::Offset {
+                        known.set(inst.adressing_mode.base, None);
+                    }
+                }
+                Multiple(multiple) => {
+                    use LoadAndStoreMultiple::*;
+                    match multiple {
+                        LDM(inst) | LDMC(inst) => {
+                            known.forget(inst.registers);
+                            if inst.write {
+                                known.set(inst.base, None);
+                            }
+                            if inst.registers.contains(RegisterList::REGISTER15) {
+                                flow.falls_through = conditional;
+                            }
+                        }
+                        STM(inst) => {
+                            if inst.write {
+                                known.set(inst.base, None);
+                            }
+                        }
+                        LDMR(inst) => known.forget(inst.registers),
+                        STM2(_) => {}
+                    }
+                }
+                _ => known.forget_all(),
+            }
+        }
+        PartialArmInstruction::Exceptiongenerating(_) => {}
+        _ => known.forget_all(),
+    }
+    flow
+}
+fn thumb_flow(
+    instruction: &ThumbInstruction,
+    adress: Adress,
+    image: Image,
+    known: &mut KnownRegisters,
+) -> Flow {
+    let pc = adress.thumb_pc();
+    let mut flow = Flow {
+        falls_through: true,
+        target: None,
+    };
+    match *instruction {
+        ThumbInstruction::Undefined(_) => flow.falls_through = false,
+        ThumbInstruction::Branch(branch) => match branch {
+            ThumbBranchInstruction::BCond { .. } => {
+                flow.target = branch.target(adress).map(|target| (target, Mode::Thumb));
+            }
+            ThumbBranchInstruction::B(_) => {
+                flow.falls_through = false;
+                flow.target = branch.target(adress).map(|target| (target, Mode::Thumb));
+            }
+            ThumbBranchInstruction::BL(_) => {
+                flow.target = branch.target(adress).map(|target| (target, Mode::Thumb));
+                known.forget_all();
+            }
+            ThumbBranchInstruction::BLXImmediate(_) => {
+                flow.target = branch.target(adress).map(|target| (target, Mode::Arm));
+                known.forget_all();
+            }
+            ThumbBranchInstruction::BX(register) => {
+                flow.falls_through = false;
+                flow.target = exchange_target(known, register);
+            }
+            ThumbBranchInstruction::BLX(register) => {
+                flow.target = exchange_target(known, register);
+                known.forget_all();
+            }
+            ThumbBranchInstruction::BLPrefix(_)
+            | ThumbBranchInstruction::BLSuffix(_)
+            | ThumbBranchInstruction::BLXSuffix(_) => known.forget_all(),
+        },
+        ThumbInstruction::DataProssessing(data) => {
+            use ThumbDataProssessingInstruction::*;
+            let (destination, value) = match data {
+                MOV(Operands::Immediate {
+                    destination,
+                    immediate,
+                }) => (Some(destination), Some(immediate as u32)),
+                MOV(Operands::Register {
+                    destination,
+                    source,
+                }) => (Some(destination), known.get(source)),
+                ADD(Operands::RegisterImmediate {
+                    destination,
+                    first_operand: Register::R15,
+                    immediate,
+                }) => (
+                    Some(destination),
+                    Some(pc.word_aligned().value().wrapping_add(immediate as u32)),
+                ),
+                ADD(Operands::Immediate {
+                    destination,
+                    immediate,
+                }) => (
+                    Some(destination),
+                    known
+                        .get(destination)
+                        .map(|value| value.wrapping_add(immediate as u32)),
+                ),
+                ADD(Operands::RegisterImmediate {
+                    destination,
+                    first_operand,
+                    immediate,
+                }) => (
+                    Some(destination),
+                    known
+                        .get(first_operand)
+                        .map(|value| value.wrapping_add(immediate as u32)),
+                ),
+                CMN { .. } | CMP { .. } | TST { .. } => (None, None),
+                ADC(operands) | ADD(operands) | AND(operands) | ASR(operands) | BIC(operands)
+                | EOR(operands) | LSL(operands) | LSR(operands) | MOV(operands) | MUL(operands)
+                | MVN(operands) | NEG(operands) | ORR(operands) | ROR(operands) | SBC(operands)
+                | SUB(operands) => {
+                    let destination = match operands {
+                        Operands::Register { destination, .. }
+                        | Operands::Registers { destination, .. }
+                        | Operands::RegisterImmediate { destination, .. }
+                        | Operands::Immediate { destination, .. } => destination,
+                    };
+                    (Some(destination), None)
+                }
+            };
+            if let Some(destination) = destination {
+                known.set(destination, value);
+                // only MOV and ADD with a high register can write the PC, neither interworks
+                if destination == Register::R15 {
+                    flow.falls_through = false;
+                    flow.target = value.map(|value| write_target(value, Mode::Thumb));
+                }
+            }
+        }
+        ThumbInstruction::LoadAndStore(load_and_store) => {
+            use ThumbLoadAndStoreInstruction::*;
+            match load_and_store {
+                LDR(inst) => {
+                    let literal = instruction
+                        .literal(adress)
+                        .and_then(|literal| image.word(literal));
+                    known.set(inst.destination, literal);
+                }
+                LDRB(inst) | LDRH(inst) | LDRSB(inst) | LDRSH(inst) => {
+                    known.set(inst.destination, None)
+                }
+                STR(_) | STRB(_) | STRH(_) => {}
+                LDMIA { base, registers } => {
+                    known.forget(registers);
+                    known.set(base, None);
+                }
+                STMIA { base, .. } => known.set(base, None),
+                PUSH(_) => known.set(Register::R13, None),
+                POP(registers) => {
+                    known.forget(registers);
+                    known.set(Register::R13, None);
+                    if registers.contains(RegisterList::REGISTER15) {
+                        flow.falls_through = false;
+                    }
+                }
+            }
+        }
+        ThumbInstruction::Exceptiongenerating(_) => {}
+    }
+    flow
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///Builds an image out of ARM words and Thumb halfwords.
+    #[derive(Default)]
+    struct Assembler(Vec<u8>);
+    impl Assembler {
+        fn arm(mut self, word: u32) -> Self {
+            self.0.extend(word.to_le_bytes());
+            self
+        }
+        fn thumb(mut self, half: u16) -> Self {
+            self.0.extend(half.to_le_bytes());
+            self
+        }
+    }
+    fn image(bytes: &[u8], architecture: Architecture) -> Image<'_> {
+        Image {
+            bytes,
+            base: Adress::new(0),
+            architecture,
+        }
+    }
+    ///The address, state and whether it's reached of every line.
+    fn states(lines: &[Line]) -> Vec<(u32, Mode, bool)> {
+        lines
+            .iter()
+            .map(|line| (line.adress.value(), line.instruction.mode(), line.reached))
+            .collect()
+    }
+
+    #[test]
+    fn exchanging_states() {
+        let bytes = Assembler::default()
+            // add r0, pc, #1 and bx r0
+            .arm(0xe28f_0001)
+            .arm(0xe12f_ff10)
+            // add r1, pc, #4 and bx r1
+            .thumb(0xa101)
+            .thumb(0x4708)
+            .thumb(0x0000)
+            .thumb(0x0000)
+            // b .
+            .arm(0xeaff_fffe)
+            .0;
+        let image = image(&bytes, Architecture::ARMv5TE);
+        let reached = trace(image, Adress::new(0), Mode::Arm);
+        assert_eq!(
+            reached.into_iter().collect::<Vec<_>>(),
+            [
+                (Adress::new(0x0), Mode::Arm),
+                (Adress::new(0x4), Mode::Arm),
+                (Adress::new(0x8), Mode::Thumb),
+                (Adress::new(0xa), Mode::Thumb),
+                (Adress::new(0x10), Mode::Arm),
+            ]
+        );
+        let lines = disassemble(image, Adress::new(0), Mode::Arm);
+        assert_eq!(
+            states(&lines),
+            [
+                (0x0, Mode::Arm, true),
+                (0x4, Mode::Arm, true),
+                (0x8, Mode::Thumb, true),
+                (0xa, Mode::Thumb, true),
+                (0xc, Mode::Thumb, false),
+                (0xe, Mode::Thumb, false),
+                (0x10, Mode::Arm, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn resynchronizing() {
+        let bytes = Assembler::default()
+            // add r0, pc, #3 and bx r0 to the Thumb code at 0xa
+            .arm(0xe28f_0003)
+            .arm(0xe12f_ff10)
+            // a halfword of padding and b .
+            .thumb(0x0000)
+            .thumb(0xe7fe)
+            .0;
+        let lines = disassemble(
+            image(&bytes, Architecture::ARMv5TE),
+            Adress::new(0),
+            Mode::Arm,
+        );
+        assert_eq!(
+            states(&lines),
+            [
+                (0x0, Mode::Arm, true),
+                (0x4, Mode::Arm, true),
+                (0x8, Mode::Thumb, false),
+                (0xa, Mode::Thumb, true),
+            ]
+        );
+        assert_eq!(lines[2].raw, 0x0000);
+        assert_eq!(lines[3].raw, 0xe7fe);
+    }
+
+    #[test]
+    fn writing_the_pc() {
+        let bytes = Assembler::default()
+            // add r0, pc, #4 and mov pc, r0
+            .arm(0xe28f_0004)
+            .arm(0xe1a0_f000)
+            .arm(0xffff_ffff)
+            // ldr pc, [pc] to the Thumb code at 0x18
+            .arm(0xe59f_f000)
+            .arm(0x0000_0000)
+            .arm(0x0000_0019)
+            // add r0, pc, #0 and mov pc, r0
+            .thumb(0xa000)
+            .thumb(0x4687)
+            // b .
+            .thumb(0xe7fe)
+            .thumb(0x0000)
+            .0;
+        let reached = trace(
+            image(&bytes, Architecture::ARMv5TE),
+            Adress::new(0),
+            Mode::Arm,
+        );
+        assert_eq!(
+            reached.into_iter().collect::<Vec<_>>(),
+            [
+                (Adress::new(0x0), Mode::Arm),
+                (Adress::new(0x4), Mode::Arm),
+                (Adress::new(0xc), Mode::Arm),
+                (Adress::new(0x18), Mode::Thumb),
+                (Adress::new(0x1a), Mode::Thumb),
+                (Adress::new(0x1c), Mode::Thumb),
+            ]
+        );
+        // loading the PC doesn't interwork on ARMv4T
+        let reached = trace(
+            image(&bytes, Architecture::ARMv4T),
+            Adress::new(0),
+            Mode::Arm,
+        );
+        assert_eq!(reached.get(&Adress::new(0x18)), Some(&Mode::Arm));
+    }
+}
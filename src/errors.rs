@@ -1,3 +1,4 @@
+use crate::instructions::Condition;
 use std::io::Error as IoError;
 use thiserror::Error as ThisError;
 #[derive(ThisError, Debug)]
@@ -6,6 +7,19 @@ pub enum ParseError {
     InvalidMask { invalid_set_bytes: u32 },
     #[error("Should have been zero but was {0:2}")]
     ShouldBeZero(u32),
+    #[error("Should have been one but was zero {0:2}")]
+    ShouldBeOne(u32),
+    #[error("Undefined instruction {0:#034b}")]
+    Undefined(u32),
+}
+#[derive(ThisError, Debug)]
+pub enum EncodeError {
+    #[error("{value} is out of range for the {field}")]
+    OutOfRange { field: &'static str, value: i64 },
+    #[error("There is no encoding for {0}")]
+    Unencodable(&'static str),
+    #[error("The instruction can't have the condition {0}")]
+    Condition(Condition),
 }
 #[derive(ThisError, Debug)]
 pub enum DisasemblerError {
@@ -15,6 +29,10 @@ pub enum DisasemblerError {
     Parse(ParseError),
     #[error("Failed to read file: {0}")]
     FileError(IoError),
+    #[error("Failed to write the listing: {0}")]
+    Output(IoError),
+    #[error("Source files can only be written in the GNU as syntaxes")]
+    ArmasmSource,
 }
 impl From<ParseError> for DisasemblerError {
     fn from(value: ParseError) -> Self {
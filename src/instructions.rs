@@ -1,18 +1,53 @@
 #![allow(unused)]
 //! using https://documentation-service.arm.com/static/5f8dacc8f86e16515cdb865a
 pub mod arm;
+pub mod thumb;
 
-use crate::errors::{DisasemblerError, ParseError};
+use crate::errors::{DisasemblerError, EncodeError, ParseError};
 use bitflags::bitflags;
+use std::fmt::{self, Display};
 use std::mem;
 use std::ops::{BitOr, RangeInclusive};
 use ux::u4;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PSRFlags {
-    c: bool,
-    x: bool,
-    s: bool,
-    f: bool,
+    pub c: bool,
+    pub x: bool,
+    pub s: bool,
+    pub f: bool,
+}
+impl PSRFlags {
+    ///Decodes the `field_mask` from bits 16 to 19.
+    pub fn new(value: u32) -> Self {
+        Self {
+            c: check_bit(value, 16),
+            x: check_bit(value, 17),
+            s: check_bit(value, 18),
+            f: check_bit(value, 19),
+        }
+    }
+    ///Whether no field is written.
+    pub fn is_empty(&self) -> bool {
+        !(self.c || self.x || self.s || self.f)
+    }
+    ///The `field_mask` in bits 16 to 19.
+    pub fn encode(&self) -> u32 {
+        (self.c as u32) << 16
+            | (self.x as u32) << 17
+            | (self.s as u32) << 18
+            | (self.f as u32) << 19
+    }
+}
+///The `<fields>` of MSR, like `fc`.
+impl Display for PSRFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (set, field) in [(self.f, 'f'), (self.s, 's'), (self.x, 'x'), (self.c, 'c')] {
+            if set {
+                write!(f, "{field}")?;
+            }
+        }
+        Ok(())
+    }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RegisterList(u16);
@@ -28,6 +63,7 @@ bitflags! {
         const REGISTER7 = 0b1<<7;
         const REGISTER8 = 0b1<<8;
         const REGISTER9 = 0b1<<9;
+        const REGISTER10 = 0b1<<10;
         const REGISTER11 = 0b1<<11;
         const REGISTER12 = 0b1<<12;
         const REGISTER13 = 0b1<<13;
@@ -35,6 +71,347 @@ bitflags! {
         const REGISTER15 = 0b1<<15;
     }
 }
+impl RegisterList {
+    ///Keeps every bit, all 16 of them are registers.
+    pub const fn new(bits: u16) -> Self {
+        Self::from_bits_retain(bits)
+    }
+    ///The registers in the list in ascending order.
+    pub fn registers(self) -> impl Iterator<Item = Register> {
+        (0..16u8)
+            .filter(move |i| self.bits() & (1 << i) != 0)
+            .filter_map(|i| Register::try_from(i).ok())
+    }
+}
+///Runs of three or more of R0 to R12 are written as a range, like `{r0-r3, lr}`.
+impl Display for RegisterList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let registers: Vec<Register> = self.registers().collect();
+        let mut parts = Vec::new();
+        let mut start = 0;
+        while start < registers.len() {
+            let mut end = start;
+            while registers.get(end + 1).is_some_and(|&next| {
+                next as u8 == registers[end] as u8 + 1 && next as u8 <= Register::R12 as u8
+            }) {
+                end += 1;
+            }
+            match end - start {
+                0 | 1 => {
+                    parts.push(registers[start].to_string());
+                    end = start;
+                }
+                _ => parts.push(format!("{}-{}", registers[start], registers[end])),
+            }
+            start = end + 1;
+        }
+        write!(f, "{{{}}}", parts.join(", "))
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violations(u8);
+bitflags! {
+    impl Violations:u8{
+        ///The operands make the instruction unpredictable.
+        const UNPREDICTABLE = 0b1<<0;
+        ///A should be zero field had bits set, they were ignored.
+        const SHOULD_BE_ZERO = 0b1<<1;
+        ///A should be one field had bits clear, they were ignored.
+        const SHOULD_BE_ONE = 0b1<<2;
+    }
+}
+///An instruction together with the ways its encoding breaks the architecture rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoded<T> {
+    pub instruction: T,
+    pub violations: Violations,
+}
+///An instruction in assembler syntax, split into the mnemonic and the operands so the condition
+///can be put into the mnemonic and listings can line up the operands.
+pub trait Assembly {
+    ///The mnemonic with `cond` where the syntax puts it, AL is left out.
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String;
+    ///The operands separated by commas, empty if there are none.
+    fn operands(&self, syntax: Syntax) -> String;
+}
+///The mnemonic and the operands of `instruction` with `cond` in `syntax`.
+pub fn assembly(instruction: &impl Assembly, cond: Condition, syntax: Syntax) -> (String, String) {
+    let operands = instruction.operands(syntax);
+    (
+        instruction.mnemonic(cond, syntax),
+        syntax.register_names(&operands),
+    )
+}
+///Writes `instruction` with `cond` the way [`Display`] does for instructions, which is in the
+///GNU syntax.
+pub fn fmt_assembly(
+    instruction: &impl Assembly,
+    cond: Condition,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let (mnemonic, operands) = assembly(instruction, cond, Syntax::Gnu);
+    match operands.is_empty() {
+        true => write!(f, "{mnemonic}"),
+        false => write!(f, "{mnemonic} {operands}"),
+    }
+}
+///The assembler the text is written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash, clap::ValueEnum)]
+pub enum Syntax {
+    ///The divided syntax of GNU `as` as used by devkitARM, with the condition before the
+    ///suffixes, `sl`, `fp` and `ip` for R10 to R12 and `@` comments.
+    #[default]
+    Gnu,
+    ///The Unified Assembler Language, with the condition after the suffixes, `svc`, shifted
+    ///moves written as shifts and the S of the Thumb instructions that set the flags.
+    #[value(name = "ual")]
+    Unified,
+    ///armasm of ADS and RVCT as used by the Nitro SDK, with the condition before the suffixes,
+    ///`DCD` and `DCW` for data and `;` comments.
+    Armasm,
+}
+impl Syntax {
+    ///Puts the condition of `base` relative to its suffixes, like `ldr` `eq` `b` in the
+    ///divided syntax and `ldr` `b` `eq` in UAL.
+    pub fn conditional(self, base: &str, cond: Condition, suffix: &str) -> String {
+        match self {
+            Self::Unified => format!("{base}{suffix}{}", cond.suffix()),
+            Self::Gnu | Self::Armasm => format!("{base}{}{suffix}", cond.suffix()),
+        }
+    }
+    ///The directive for a word of data.
+    pub const fn word(self) -> &'static str {
+        match self {
+            Self::Gnu | Self::Unified => ".word",
+            Self::Armasm => "DCD",
+        }
+    }
+    ///The directive for a halfword of data.
+    pub const fn halfword(self) -> &'static str {
+        match self {
+            Self::Gnu | Self::Unified => ".short",
+            Self::Armasm => "DCW",
+        }
+    }
+    ///What starts a comment that runs to the end of the line.
+    pub const fn comment(self) -> &'static str {
+        match self {
+            Self::Gnu | Self::Unified => "@",
+            Self::Armasm => ";",
+        }
+    }
+    ///The immediate of SWI and BKPT, which is written without a `#` outside of UAL.
+    pub fn comment_field(self, value: u32) -> String {
+        match self {
+            Self::Unified => format!("#{value:#x}"),
+            Self::Gnu | Self::Armasm => format!("{value:#x}"),
+        }
+    }
+    ///Renames the registers in `operands`, which are written with the names of [`Register`],
+    ///to the ones used by the syntax.
+    pub fn register_names(self, operands: &str) -> String {
+        let Self::Gnu = self else {
+            return operands.to_string();
+        };
+        let mut renamed = String::with_capacity(operands.len());
+        let mut rest = operands;
+        while !rest.is_empty() {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len())
+                .max(1);
+            let (token, remainder) = rest.split_at(end);
+            renamed.push_str(match token {
+                "r10" => "sl",
+                "r11" => "fp",
+                "r12" => "ip",
+                token => token,
+            });
+            rest = remainder;
+        }
+        renamed
+    }
+}
+///An immediate as `#value`, in hexadecimal once decimal gets hard to read.
+pub fn immediate(value: u32) -> String {
+    match value {
+        0..=255 => format!("#{value}"),
+        _ => format!("#{value:#x}"),
+    }
+}
+///The architecture version of the core the code runs on, later versions are supersets of the
+///earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, clap::ValueEnum)]
+pub enum Architecture {
+    ///ARM7TDMI, the DS ARM7.
+    #[value(name = "armv4t")]
+    ARMv4T,
+    ///ARM946E-S, the DS ARM9.
+    #[value(name = "armv5te")]
+    ARMv5TE,
+    ///ARMv5TE plus the LDREX, STREX, CPS, MCRR2 and MRRC2 of ARMv6 and its relaxed register
+    ///restrictions, the other ARMv6 instructions are undefined.
+    #[value(name = "armv6")]
+    ARMv6,
+}
+impl Architecture {
+    ///The architecture of the DS ARM7 or ARM9.
+    pub const fn for_core(arm7: bool) -> Self {
+        match arm7 {
+            true => Self::ARMv4T,
+            false => Self::ARMv5TE,
+        }
+    }
+}
+///The condition flags of the CPSR, in the order of bits 28 to 31.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConditionFlags(u8);
+bitflags! {
+    impl ConditionFlags:u8{
+        ///Overflow
+        const V = 0b1<<0;
+        ///Carry
+        const C = 0b1<<1;
+        ///Zero
+        const Z = 0b1<<2;
+        ///Negative
+        const N = 0b1<<3;
+    }
+}
+impl ConditionFlags {
+    ///Takes the flags from bits 28 to 31 of a CPSR value.
+    pub const fn from_psr(psr: u32) -> Self {
+        Self::from_bits_retain((psr >> 28) as u8)
+    }
+}
+///The condition field, see A3.2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Condition {
+    /// 0000 EQ
+    Equal,
+    /// 0001 NE
+    NotEqual,
+    /// 0010 CS/HS
+    CarrySet,
+    /// 0011 CC/LO
+    CarryClear,
+    /// 0100 MI
+    Minus,
+    /// 0101 PL
+    Plus,
+    /// 0110 VS
+    Overflow,
+    /// 0111 VC
+    NoOverflow,
+    /// 1000 HI
+    Higher,
+    /// 1001 LS
+    LowerOrSame,
+    /// 1010 GE
+    SignedGreaterOrEqual,
+    /// 1011 LT
+    SignedLessThan,
+    /// 1100 GT
+    SignedGreaterThan,
+    /// 1101 LE
+    SignedLessThanOrEqual,
+    /// 1110 AL
+    Allways,
+    /// 1111 NV, since ARMv5 this selects the unconditional instructions instead.
+    Never,
+}
+impl Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Allways => write!(f, "al"),
+            cond => write!(f, "{}", cond.suffix()),
+        }
+    }
+}
+impl Condition {
+    ///Takes the four bit condition field.
+    pub const fn new(cond: u32) -> Self {
+        use Condition::*;
+        match cond & 0b1111 {
+            0b0000 => Equal,
+            0b0001 => NotEqual,
+            0b0010 => CarrySet,
+            0b0011 => CarryClear,
+            0b0100 => Minus,
+            0b0101 => Plus,
+            0b0110 => Overflow,
+            0b0111 => NoOverflow,
+            0b1000 => Higher,
+            0b1001 => LowerOrSame,
+            0b1010 => SignedGreaterOrEqual,
+            0b1011 => SignedLessThan,
+            0b1100 => SignedGreaterThan,
+            0b1101 => SignedLessThanOrEqual,
+            0b1110 => Allways,
+            _ => Never,
+        }
+    }
+    ///The opposite condition, conditions come in pairs that only differ in the lowest bit.
+    pub const fn invert(self) -> Self {
+        Self::new(self as u32 ^ 1)
+    }
+    ///Whether the condition is AL, which always passes.
+    pub const fn is_always(self) -> bool {
+        matches!(self, Self::Allways)
+    }
+    ///Whether the condition is `1111`, which selects the unconditional instructions in ARM state.
+    pub const fn is_never(self) -> bool {
+        matches!(self, Self::Never)
+    }
+    ///The suffix added to mnemonics, empty for AL.
+    pub const fn suffix(self) -> &'static str {
+        use Condition::*;
+        match self {
+            Equal => "eq",
+            NotEqual => "ne",
+            CarrySet => "cs",
+            CarryClear => "cc",
+            Minus => "mi",
+            Plus => "pl",
+            Overflow => "vs",
+            NoOverflow => "vc",
+            Higher => "hi",
+            LowerOrSame => "ls",
+            SignedGreaterOrEqual => "ge",
+            SignedLessThan => "lt",
+            SignedGreaterThan => "gt",
+            SignedLessThanOrEqual => "le",
+            Allways => "",
+            Never => "nv",
+        }
+    }
+    ///Whether an instruction with this condition executes with `flags` set.
+    pub const fn evaluate(self, flags: ConditionFlags) -> bool {
+        let n = flags.contains(ConditionFlags::N);
+        let z = flags.contains(ConditionFlags::Z);
+        let c = flags.contains(ConditionFlags::C);
+        let v = flags.contains(ConditionFlags::V);
+        use Condition::*;
+        match self {
+            Equal => z,
+            NotEqual => !z,
+            CarrySet => c,
+            CarryClear => !c,
+            Minus => n,
+            Plus => !n,
+            Overflow => v,
+            NoOverflow => !v,
+            Higher => c && !z,
+            LowerOrSame => !c || z,
+            SignedGreaterOrEqual => n == v,
+            SignedLessThan => n != v,
+            SignedGreaterThan => !z && n == v,
+            SignedLessThanOrEqual => z || n != v,
+            Allways => true,
+            Never => false,
+        }
+    }
+}
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Coprocessor {
     P0,
@@ -83,6 +460,11 @@ impl TryFrom<u8> for Coprocessor {
         })
     }
 }
+impl Display for Coprocessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "p{}", *self as u8)
+    }
+}
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Register {
     R0,
@@ -131,6 +513,17 @@ impl TryFrom<u8> for Register {
         })
     }
 }
+///The general purpose names, with the SP, LR and PC by their role.
+impl Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::R13 => write!(f, "sp"),
+            Self::R14 => write!(f, "lr"),
+            Self::R15 => write!(f, "pc"),
+            register => write!(f, "r{}", *register as u8),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CRegister {
@@ -180,15 +573,44 @@ impl TryFrom<u8> for CRegister {
         })
     }
 }
+impl Display for CRegister {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "c{}", *self as u8)
+    }
+}
 pub const fn check_rest_null_mask(value: u32, mask: u32) -> Result<u32, ParseError> {
     if value & !mask > 0 {
-        return Err(ParseError::InvalidMask {
+        Err(ParseError::InvalidMask {
             invalid_set_bytes: value & !mask,
-        });
+        })
     } else {
         Ok(value & mask)
     }
 }
+///Checks that the bits in `mask` are all zero.
+pub const fn should_be_zero(value: u32, mask: u32) -> Result<(), ParseError> {
+    match value & mask {
+        0 => Ok(()),
+        set => Err(ParseError::ShouldBeZero(set)),
+    }
+}
+///Checks that the bits in `mask` are all one.
+pub const fn should_be_one(value: u32, mask: u32) -> Result<(), ParseError> {
+    match !value & mask {
+        0 => Ok(()),
+        cleared => Err(ParseError::ShouldBeOne(cleared)),
+    }
+}
+///Checks that `value` fits into a field that is `bits` wide, `field` names it in the error.
+pub fn encode_field(value: u32, bits: u32, field: &'static str) -> Result<u32, EncodeError> {
+    match value.checked_shr(bits).unwrap_or(0) {
+        0 => Ok(value),
+        _ => Err(EncodeError::OutOfRange {
+            field,
+            value: value as i64,
+        }),
+    }
+}
 #[inline(always)]
 pub const fn check_bit(value: u32, bit: u32) -> bool {
     ((value >> bit) & 1) == 1
@@ -222,22 +644,191 @@ pub const fn split_with_range(value: u32, mask: RangeInclusive<u32>) -> (u32, u3
 }
 pub mod consts {
     pub const COND_MASK: u32 = 0b1111 << 28;
+    ///How far ahead of the executing instruction the PC reads in ARM state.
+    pub const ARM_PC_OFFSET: u32 = 8;
+    ///How far ahead of the executing instruction the PC reads in Thumb state.
+    pub const THUMB_PC_OFFSET: u32 = 4;
 }
+///Signed byte offset relative to the PC.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RelativeAdress(i32);
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl RelativeAdress {
+    pub const fn new(offset: i32) -> Self {
+        Self(offset)
+    }
+    ///Sign extends a word offset that is `bits` wide and converts it to bytes.
+    pub const fn from_word_offset(offset: u32, bits: u32) -> Self {
+        let shift = 32 - bits;
+        Self(((offset << shift) as i32 >> shift) << 2)
+    }
+    ///Sign extends a halfword offset that is `bits` wide and converts it to bytes.
+    pub const fn from_halfword_offset(offset: u32, bits: u32) -> Self {
+        let shift = 32 - bits;
+        Self(((offset << shift) as i32 >> shift) << 1)
+    }
+    pub const fn offset(self) -> i32 {
+        self.0
+    }
+    ///The word offset that is `bits` wide, the inverse of [`Self::from_word_offset`].
+    pub fn to_word_offset(self, bits: u32) -> Result<u32, EncodeError> {
+        let words = self.0 >> 2;
+        let limit = 1 << (bits - 1);
+        match self.0 % 4 == 0 && (-limit..limit).contains(&words) {
+            true => Ok(words as u32 & ((1 << bits) - 1)),
+            false => Err(EncodeError::OutOfRange {
+                field: "branch offset",
+                value: self.0 as i64,
+            }),
+        }
+    }
+    ///Resolves the offset against the value the PC reads as.
+    pub const fn resolve(self, pc: Adress) -> Adress {
+        Adress(pc.0.wrapping_add_signed(self.0))
+    }
+}
+///The offset as `#offset`, relative to the value the PC reads as.
+impl Display for RelativeAdress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 < 0 {
+            true => write!(f, "#-{:#x}", self.0.unsigned_abs()),
+            false => write!(f, "#{:#x}", self.0),
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Adress(u32);
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ShifterOperand {
-    Immediate(u32),
-    Register(Register),
-    ShiftLeft(u32),
+impl Adress {
+    pub const fn new(adress: u32) -> Self {
+        Self(adress)
+    }
+    pub const fn value(self) -> u32 {
+        self.0
+    }
+    ///The value the PC reads as while executing the ARM instruction at this address.
+    pub const fn arm_pc(self) -> Adress {
+        Adress(self.0.wrapping_add(consts::ARM_PC_OFFSET))
+    }
+    ///The value the PC reads as while executing the Thumb instruction at this address.
+    pub const fn thumb_pc(self) -> Adress {
+        Adress(self.0.wrapping_add(consts::THUMB_PC_OFFSET))
+    }
+    ///Clears the low bits, as done to the PC for PC relative loads in Thumb state.
+    pub const fn word_aligned(self) -> Adress {
+        Adress(self.0 & !0b11)
+    }
 }
-impl TryFrom<u32> for ShifterOperand {
-    type Error = ParseError;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::arm::ArmInstruction;
+    use crate::instructions::thumb::ThumbInstruction;
 
-    fn try_from(value: u32) -> Result<Self, Self::Error> {
-        let (i, rest) = split_with_mask(value, 1 << 24);
-        Ok(Self::Immediate(value))
+    ///The mnemonic and operands of `word` in every syntax, in the order GNU, UAL and armasm.
+    fn arm(word: u32) -> Vec<(String, String)> {
+        let instruction = ArmInstruction::try_from(word).unwrap();
+        [Syntax::Gnu, Syntax::Unified, Syntax::Armasm]
+            .map(|syntax| assembly(&instruction, instruction.cond, syntax))
+            .to_vec()
+    }
+    fn pair(mnemonic: &str, operands: &str) -> (String, String) {
+        (mnemonic.to_string(), operands.to_string())
+    }
+
+    #[test]
+    fn syntaxes() {
+        assert_eq!(Syntax::Gnu.comment(), "@");
+        assert_eq!(Syntax::Unified.comment(), "@");
+        assert_eq!(Syntax::Armasm.comment(), ";");
+        // addseq r10, r11, r12
+        assert_eq!(
+            arm(0x009b_a00c),
+            [
+                pair("addeqs", "sl, fp, ip"),
+                pair("addseq", "r10, r11, r12"),
+                pair("addeqs", "r10, r11, r12"),
+            ]
+        );
+        // ldmia r0, {r1, r2}
+        assert_eq!(
+            arm(0xe890_0006),
+            [
+                pair("ldmia", "r0, {r1, r2}"),
+                pair("ldm", "r0, {r1, r2}"),
+                pair("ldmia", "r0, {r1, r2}"),
+            ]
+        );
+        // ldmdb r0, {r1, r2} keeps its suffix
+        assert_eq!(arm(0xe910_0006)[1], pair("ldmdb", "r0, {r1, r2}"));
+        // ldrd r0, r1, [r2]
+        assert_eq!(
+            arm(0xe1c2_00d0),
+            [
+                pair("ldrd", "r0, [r2]"),
+                pair("ldrd", "r0, r1, [r2]"),
+                pair("ldrd", "r0, [r2]"),
+            ]
+        );
+        // swi 0x10
+        assert_eq!(
+            arm(0xef00_0010),
+            [
+                pair("swi", "0x10"),
+                pair("svc", "#0x10"),
+                pair("swi", "0x10")
+            ]
+        );
+        // ldmia r3!, {r4} in Thumb
+        let instruction = ThumbInstruction::try_from(0xcb10).unwrap();
+        assert_eq!(
+            assembly(&instruction, Condition::Allways, Syntax::Unified),
+            pair("ldm", "r3!, {r4}")
+        );
+    }
+
+    #[test]
+    fn conditions() {
+        // the NZCV flags and the conditions that pass with them, bit n is condition n
+        let table = [
+            (0b0000, 0b0101_0110_1010_1010),
+            (0b0001, 0b0110_1010_0110_1010),
+            (0b0010, 0b0101_0101_1010_0110),
+            (0b0011, 0b0110_1001_0110_0110),
+            (0b0100, 0b0110_0110_1010_1001),
+            (0b0101, 0b0110_1010_0110_1001),
+            (0b0110, 0b0110_0110_1010_0101),
+            (0b0111, 0b0110_1010_0110_0101),
+            (0b1000, 0b0110_1010_1001_1010),
+            (0b1001, 0b0101_0110_0101_1010),
+            (0b1010, 0b0110_1001_1001_0110),
+            (0b1011, 0b0101_0101_0101_0110),
+            (0b1100, 0b0110_1010_1001_1001),
+            (0b1101, 0b0110_0110_0101_1001),
+            (0b1110, 0b0110_1010_1001_0101),
+            (0b1111, 0b0110_0110_0101_0101),
+        ];
+        for (nzcv, passing) in table {
+            let flags = ConditionFlags::from_psr(nzcv << 28 | 0x1f);
+            assert_eq!(flags.bits(), nzcv as u8);
+            for cond in 0..16 {
+                let condition = Condition::new(cond);
+                assert_eq!(
+                    condition.evaluate(flags),
+                    passing >> cond & 1 == 1,
+                    "{condition:?} with {nzcv:#06b}"
+                );
+                if cond < 0b1110 {
+                    assert_ne!(
+                        condition.evaluate(flags),
+                        condition.invert().evaluate(flags)
+                    );
+                }
+            }
+        }
+        for cond in 0..16 {
+            let condition = Condition::new(cond);
+            assert_eq!(condition.invert().invert(), condition);
+            assert_eq!(condition.invert() as u32, cond ^ 1);
+        }
+        assert_eq!(Condition::Allways.invert(), Condition::Never);
     }
 }
@@ -1,16 +1,22 @@
+use std::fmt::{self, Display};
+
+use self::adresssing::{Indexing, LoadAndStoreOffset};
 use self::register_access_instructions::RegisterAccessInstruction;
-use crate::errors::ParseError;
-use crate::instructions::{check_bit, split_with_mask};
+use crate::errors::{EncodeError, ParseError};
+use crate::instructions::{
+    check_bit, fmt_assembly, split_with_mask, split_with_range, Adress, Architecture, Assembly,
+    Condition, Decoded, Register, Syntax, Violations,
+};
 use arithmetic::AritmeticInstruction;
 use branch::BranchInstruction;
 use coprocessor::CoprocessorInstruction;
 use dataprosessing::DataProssessingInstruction;
 use exception::ExceptiongeneratingInstruction;
-use loadandstore::LoadAndStoreInstruction;
+use loadandstore::{LoadAndStoreInstruction, LoadAndStoreMultiple};
 use multiply::MultiplyInstruction;
 use semaphore::SemaphoreInstruction;
 use unconditional::UnconditionalInstruction;
-mod adresssing;
+pub mod adresssing;
 pub mod arithmetic;
 pub mod branch;
 pub mod coprocessor;
@@ -18,47 +24,14 @@ pub mod dataprosessing;
 pub mod exception;
 pub mod loadandstore;
 pub mod multiply;
-mod register_access_instructions;
+pub mod register_access_instructions;
 pub mod semaphore;
 pub mod unconditional;
+///An ARM instruction, the operation is only executed if the condition passes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct PostIndexedAddressingMode {}
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct AddressingMode {}
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ArmInstruction {
-    /// 0000 EQ
-    Equal(PartialArmInstruction),
-    /// 0001 NE
-    NotEqual(PartialArmInstruction),
-    /// 0010 CS/HS
-    CarrySet(PartialArmInstruction),
-    /// 0011 CC/LO
-    CarryClear(PartialArmInstruction),
-    /// 0100 MI
-    Minus(PartialArmInstruction),
-    /// 0101 PL
-    Plus(PartialArmInstruction),
-    /// 0110 VS
-    Overflow(PartialArmInstruction),
-    /// 0111 VC
-    NoOverflow(PartialArmInstruction),
-    /// 1000 HI
-    Higher(PartialArmInstruction),
-    /// 1001 LS
-    LowerOrSame(PartialArmInstruction),
-    /// 1010 GE
-    SignedGreaterOrEqual(PartialArmInstruction),
-    /// 1011 LT
-    SignedLessThan(PartialArmInstruction),
-    /// 1100 GT
-    SignedGreaterThan(PartialArmInstruction),
-    /// 1101 LE
-    SignedLessThanOrEqual(PartialArmInstruction),
-    /// 1110 AL
-    Allways(PartialArmInstruction),
-    /// 1111 UC
-    Unconditional(UnconditionalInstruction),
+pub struct ArmInstruction {
+    pub cond: Condition,
+    pub op: PartialArmInstruction,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -73,83 +46,248 @@ pub enum PartialArmInstruction {
     Exceptiongenerating(ExceptiongeneratingInstruction),
     Coprocessor(CoprocessorInstruction),
     RegisterAccess(RegisterAccessInstruction),
+    ///The instructions of the `1111` condition space, the condition is always `Never`.
+    Unconditional(UnconditionalInstruction),
+    ///A word that doesn't encode an instruction.
+    Undefined(u32),
+}
+impl ArmInstruction {
+    ///The instruction for a word that doesn't encode one.
+    pub const fn undefined(value: u32) -> Self {
+        Self {
+            cond: Condition::new(value >> 28),
+            op: PartialArmInstruction::Undefined(value),
+        }
+    }
+    ///Encodes the instruction into the word it was decoded from, words that aren't an
+    ///instruction are kept as they are.
+    ///
+    ///Values that have no encoding, like out of range offsets or forms that decode as another
+    ///instruction, are an error.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        use PartialArmInstruction::*;
+        // NV selects the unconditional space, which is the only one with CPS
+        let condition_fits = match self.op {
+            Unconditional(_) => self.cond.is_never(),
+            RegisterAccess(RegisterAccessInstruction::CPS { .. }) => false,
+            Undefined(_) => true,
+            _ => !self.cond.is_never(),
+        };
+        if !condition_fits {
+            return Err(EncodeError::Condition(self.cond));
+        }
+        let op = match self.op {
+            Branch(branch) => branch.encode()?,
+            DataProssessing(data) => data.encode()?,
+            Multiply(multiply) => multiply.encode(),
+            Aritmetic(aritmetic) => aritmetic.encode()?,
+            LoadAndStore(load_and_store) => load_and_store.encode()?,
+            Semaphore(semaphore) => semaphore.encode(),
+            Exceptiongenerating(exception) => exception.encode()?,
+            Coprocessor(coprocessor) => coprocessor.encode()?,
+            RegisterAccess(register_access) => register_access.encode()?,
+            Unconditional(unconditional) => unconditional.encode()?,
+            Undefined(value) => return Ok(value),
+        };
+        Ok((self.cond as u32) << 28 | op)
+    }
+    ///Whether the operands make the instruction unpredictable on `architecture`.
+    pub fn is_unpredictable(&self, architecture: Architecture) -> bool {
+        // BKPT has to be unconditional
+        let conditional_breakpoint = !self.cond.is_always()
+            && matches!(
+                self.op,
+                PartialArmInstruction::Exceptiongenerating(ExceptiongeneratingInstruction::BKPT(_))
+            );
+        conditional_breakpoint || self.op.is_unpredictable(architecture)
+    }
+    ///The first architecture version that has the instruction.
+    pub fn architecture(&self) -> Architecture {
+        self.op.architecture()
+    }
+    ///Resolves the target of B, BL and BLX (1) at `adress`.
+    pub fn target(&self, adress: Adress) -> Option<Adress> {
+        match self.op {
+            PartialArmInstruction::Branch(branch) => branch.target(adress),
+            PartialArmInstruction::Unconditional(unconditional) => unconditional.target(adress),
+            _ => None,
+        }
+    }
+    ///The address of the word loaded by an LDR at `adress` from the PC with an immediate
+    ///offset and without writeback.
+    pub fn literal(&self, adress: Adress) -> Option<Adress> {
+        let PartialArmInstruction::LoadAndStore(LoadAndStoreInstruction::LDR(inst)) = self.op
+        else {
+            return None;
+        };
+        let mode = inst.adressing_mode;
+        match (mode.base, mode.offset, mode.indexing) {
+            (Register::R15, LoadAndStoreOffset::Immediate(offset), Indexing::Offset) => {
+                let pc = adress.arm_pc().value();
+                Some(Adress::new(match mode.add {
+                    true => pc.wrapping_add(offset as u32),
+                    false => pc.wrapping_sub(offset as u32),
+                }))
+            }
+            _ => None,
+        }
+    }
+    ///The word of an instruction that can't be written in assembly.
+    fn unwritable(&self) -> Option<u32> {
+        match self.op {
+            PartialArmInstruction::RegisterAccess(RegisterAccessInstruction::MSR {
+                flags, ..
+            }) if flags.is_empty() => self.encode().ok(),
+            _ => None,
+        }
+    }
+}
+impl PartialArmInstruction {
+    ///Whether the operands make the instruction unpredictable on `architecture`.
+    pub fn is_unpredictable(&self, architecture: Architecture) -> bool {
+        match self {
+            Self::Branch(branch) => branch.is_unpredictable(),
+            Self::DataProssessing(data) => data.is_unpredictable(),
+            Self::Multiply(multiply) => multiply.is_unpredictable(architecture),
+            Self::Aritmetic(aritmetic) => aritmetic.is_unpredictable(),
+            Self::Coprocessor(coprocessor) => coprocessor.is_unpredictable(),
+            Self::LoadAndStore(load_and_store) => load_and_store.is_unpredictable(),
+            Self::Semaphore(semaphore) => semaphore.is_unpredictable(),
+            Self::RegisterAccess(register_access) => register_access.is_unpredictable(),
+            Self::Unconditional(unconditional) => unconditional.is_unpredictable(),
+            _ => false,
+        }
+    }
+    ///The first architecture version that has the instruction.
+    pub fn architecture(&self) -> Architecture {
+        match self {
+            Self::Branch(branch) => branch.architecture(),
+            Self::Multiply(multiply) => multiply.architecture(),
+            Self::Aritmetic(aritmetic) => aritmetic.architecture(),
+            Self::LoadAndStore(load_and_store) => load_and_store.architecture(),
+            Self::Exceptiongenerating(exception) => exception.architecture(),
+            Self::Coprocessor(coprocessor) => coprocessor.architecture(),
+            Self::RegisterAccess(register_access) => register_access.architecture(),
+            Self::Unconditional(unconditional) => unconditional.architecture(),
+            Self::DataProssessing(_) | Self::Semaphore(_) | Self::Undefined(_) => {
+                Architecture::ARMv4T
+            }
+        }
+    }
+}
+///Words that aren't an instruction are written as data, ignoring the condition, and so is MSR
+///without fields, which has no syntax.
+impl Assembly for ArmInstruction {
+    fn mnemonic(&self, _cond: Condition, syntax: Syntax) -> String {
+        match self.unwritable() {
+            Some(_) => syntax.word().to_string(),
+            None => self.op.mnemonic(self.cond, syntax),
+        }
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        match self.unwritable() {
+            Some(word) => format!("{word:#010x}"),
+            None => self.op.operands(syntax),
+        }
+    }
+}
+impl Display for ArmInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, self.cond, f)
+    }
+}
+impl Assembly for PartialArmInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        match self {
+            Self::Branch(branch) => branch.mnemonic(cond, syntax),
+            Self::DataProssessing(data) => data.mnemonic(cond, syntax),
+            Self::Multiply(multiply) => multiply.mnemonic(cond, syntax),
+            Self::Aritmetic(aritmetic) => aritmetic.mnemonic(cond, syntax),
+            Self::LoadAndStore(load_and_store) => load_and_store.mnemonic(cond, syntax),
+            Self::Semaphore(semaphore) => semaphore.mnemonic(cond, syntax),
+            Self::Exceptiongenerating(exception) => exception.mnemonic(cond, syntax),
+            Self::Coprocessor(coprocessor) => coprocessor.mnemonic(cond, syntax),
+            Self::RegisterAccess(register_access) => register_access.mnemonic(cond, syntax),
+            Self::Unconditional(unconditional) => unconditional.mnemonic(cond, syntax),
+            Self::Undefined(_) => syntax.word().to_string(),
+        }
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        match self {
+            Self::Branch(branch) => branch.operands(syntax),
+            Self::DataProssessing(data) => data.operands(syntax),
+            Self::Multiply(multiply) => multiply.operands(syntax),
+            Self::Aritmetic(aritmetic) => aritmetic.operands(syntax),
+            Self::LoadAndStore(load_and_store) => load_and_store.operands(syntax),
+            Self::Semaphore(semaphore) => semaphore.operands(syntax),
+            Self::Exceptiongenerating(exception) => exception.operands(syntax),
+            Self::Coprocessor(coprocessor) => coprocessor.operands(syntax),
+            Self::RegisterAccess(register_access) => register_access.operands(syntax),
+            Self::Unconditional(unconditional) => unconditional.operands(syntax),
+            Self::Undefined(value) => format!("{value:#010x}"),
+        }
+    }
+}
+impl Display for PartialArmInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
+impl ArmInstruction {
+    ///Decodes any word without failing.
+    ///
+    ///Encodings with should be zero or should be one fields set wrong are decoded as if they
+    ///were right and tagged, words that aren't an instruction on `architecture` become
+    ///`Undefined`.
+    pub fn decode(value: u32, architecture: Architecture) -> Decoded<ArmInstruction> {
+        let mut violations = Violations::empty();
+        let mut word = value;
+        loop {
+            match ArmInstruction::try_from(word) {
+                Ok(instruction) if instruction.architecture() > architecture => {
+                    return Decoded {
+                        instruction: ArmInstruction::undefined(value),
+                        violations: Violations::empty(),
+                    }
+                }
+                Ok(instruction) => {
+                    if instruction.is_unpredictable(architecture) {
+                        violations |= Violations::UNPREDICTABLE;
+                    }
+                    return Decoded {
+                        instruction,
+                        violations,
+                    };
+                }
+                Err(ParseError::ShouldBeZero(bits)) if word & bits != 0 => {
+                    word &= !bits;
+                    violations |= Violations::SHOULD_BE_ZERO;
+                }
+                Err(ParseError::ShouldBeOne(bits)) if !word & bits != 0 => {
+                    word |= bits;
+                    violations |= Violations::SHOULD_BE_ONE;
+                }
+                Err(_) => {
+                    return Decoded {
+                        instruction: ArmInstruction::undefined(value),
+                        violations: Violations::empty(),
+                    }
+                }
+            }
+        }
+    }
 }
 impl TryFrom<u32> for ArmInstruction {
     type Error = ParseError;
 
     fn try_from(value: u32) -> Result<Self, Self::Error> {
-        let (cond, rest) = split_with_mask(value, 0b1111 << 28);
-        use ArmInstruction::*;
-        Ok(match cond >> 28 {
-            (0) => {
-                let inst: PartialArmInstruction = rest.try_into()?;
-                Equal(inst)
-            }
-
-            (1) => {
-                let inst = rest.try_into()?;
-                NotEqual(inst)
-            }
-            (2) => {
-                let inst = rest.try_into()?;
-                CarrySet(inst)
-            }
-            (3) => {
-                let inst = rest.try_into()?;
-                CarryClear(inst)
-            }
-            (4) => {
-                let inst = rest.try_into()?;
-                Minus(inst)
-            }
-            (5) => {
-                let inst = rest.try_into()?;
-                Plus(inst)
-            }
-            (6) => {
-                let inst = rest.try_into()?;
-                Overflow(inst)
-            }
-            (7) => {
-                let inst = rest.try_into()?;
-                NoOverflow(inst)
-            }
-            (8) => {
-                let inst = rest.try_into()?;
-                Higher(inst)
-            }
-            (9) => {
-                let inst = rest.try_into()?;
-                LowerOrSame(inst)
-            }
-            (10) => {
-                let inst = rest.try_into()?;
-                SignedGreaterOrEqual(inst)
-            }
-            (11) => {
-                let inst = rest.try_into()?;
-                SignedLessThan(inst)
-            }
-            (12) => {
-                let inst = rest.try_into()?;
-                SignedGreaterThan(inst)
-            }
-            (13) => {
-                let inst = rest.try_into()?;
-                SignedLessThanOrEqual(inst)
-            }
-            (14) => {
-                let inst = rest.try_into()?;
-                Allways(inst)
-            }
-            (15) => {
-                let inst = rest.try_into()?;
-                Unconditional(inst)
-            }
-            _ => {
-                unreachable!("Masking in cond is wrong, got {} expected < 16", cond)
-            }
-        })
+        let (cond, rest) = split_with_range(value, 28..=31);
+        let cond = Condition::new(cond);
+        let op = match cond {
+            Condition::Never => PartialArmInstruction::Unconditional(rest.try_into()?),
+            _ => rest.try_into()?,
+        };
+        Ok(Self { cond, op })
     }
 }
 impl TryFrom<u32> for PartialArmInstruction {
@@ -165,38 +303,90 @@ impl TryFrom<u32> for PartialArmInstruction {
             0b100 => parse_0b100(rest),
             0b101 => parse_0b101(rest),
             0b110 => parse_0b110(rest),
-            0b111 => parse_0b111(rest),
-            e => todo!("invalid code {e:b}"),
+            _ => parse_0b111(rest),
         }
     }
 }
+///The comparison opcodes without the S bit set, which encode other instructions.
+fn is_miscellaneous(rest: u32) -> bool {
+    let (opcode, _) = split_with_range(rest, 23..=24);
+    opcode == 0b10 && !check_bit(rest, 20)
+}
 fn parse_0b000(rest: u32) -> Result<PartialArmInstruction, ParseError> {
+    if check_bit(rest, 7) && check_bit(rest, 4) {
+        return parse_multiply_extension(rest);
+    }
+    if is_miscellaneous(rest) {
+        return parse_miscellaneous(rest);
+    }
     DataProssessingInstruction::new(rest, false).map(PartialArmInstruction::DataProssessing)
 }
 fn parse_0b001(rest: u32) -> Result<PartialArmInstruction, ParseError> {
+    if is_miscellaneous(rest) {
+        // only the MSR immediate form exists, the rest is undefined
+        if !check_bit(rest, 21) {
+            return Err(ParseError::Undefined(rest));
+        }
+        return RegisterAccessInstruction::new(rest, true)
+            .map(PartialArmInstruction::RegisterAccess);
+    }
     DataProssessingInstruction::new(rest, true).map(PartialArmInstruction::DataProssessing)
 }
+///Multiplies and extra load/store instructions, see Figure A3-3.
+fn parse_multiply_extension(rest: u32) -> Result<PartialArmInstruction, ParseError> {
+    let (op, _) = split_with_range(rest, 5..=6);
+    match (op, check_bit(rest, 24)) {
+        (0b00, false) => MultiplyInstruction::new(rest).map(PartialArmInstruction::Multiply),
+        (0b00, true) if check_bit(rest, 23) => {
+            LoadAndStoreInstruction::new_exclusive(rest).map(PartialArmInstruction::LoadAndStore)
+        }
+        (0b00, true) => SemaphoreInstruction::new(rest).map(PartialArmInstruction::Semaphore),
+        _ => LoadAndStoreInstruction::new_misc(rest).map(PartialArmInstruction::LoadAndStore),
+    }
+}
+///Miscellaneous instructions, see Figure A3-4.
+fn parse_miscellaneous(rest: u32) -> Result<PartialArmInstruction, ParseError> {
+    use PartialArmInstruction::*;
+    let (op, _) = split_with_range(rest, 21..=22);
+    let (switch, _) = split_with_range(rest, 4..=7);
+    match (switch, op) {
+        (0b0000, _) => RegisterAccessInstruction::new(rest, false).map(RegisterAccess),
+        (0b0001, 0b01) | (0b0011, 0b01) => BranchInstruction::new_exchange(rest).map(Branch),
+        (0b0001, 0b11) => AritmeticInstruction::new_clz(rest).map(Aritmetic),
+        (0b0101, _) => AritmeticInstruction::new(rest).map(Aritmetic),
+        (0b0111, 0b01) => Ok(Exceptiongenerating(
+            ExceptiongeneratingInstruction::new_breakpoint(rest),
+        )),
+        (0b1000..=0b1111, _) if !check_bit(rest, 4) => {
+            MultiplyInstruction::new_halfword(rest).map(Multiply)
+        }
+        _ => Err(ParseError::Undefined(rest)),
+    }
+}
 fn parse_0b010(rest: u32) -> Result<PartialArmInstruction, ParseError> {
-    todo!("got code b010")
+    LoadAndStoreInstruction::new_word_or_byte(rest, false).map(PartialArmInstruction::LoadAndStore)
 }
 fn parse_0b011(rest: u32) -> Result<PartialArmInstruction, ParseError> {
-    todo!("got code b011")
+    if check_bit(rest, 4) {
+        return Err(ParseError::Undefined(rest));
+    }
+    LoadAndStoreInstruction::new_word_or_byte(rest, true).map(PartialArmInstruction::LoadAndStore)
 }
 fn parse_0b100(rest: u32) -> Result<PartialArmInstruction, ParseError> {
-    LoadAndStoreInstruction::new(rest).map(PartialArmInstruction::LoadAndStore)
+    LoadAndStoreMultiple::new(rest)
+        .map(LoadAndStoreInstruction::from)
+        .map(PartialArmInstruction::LoadAndStore)
 }
 fn parse_0b101(rest: u32) -> Result<PartialArmInstruction, ParseError> {
-    todo!("got code b101")
+    Ok(PartialArmInstruction::Branch(BranchInstruction::new(rest)))
 }
 fn parse_0b110(rest: u32) -> Result<PartialArmInstruction, ParseError> {
-    todo!("got code b110")
+    CoprocessorInstruction::new_load_store(rest).map(PartialArmInstruction::Coprocessor)
 }
 fn parse_0b111(rest: u32) -> Result<PartialArmInstruction, ParseError> {
-    let (switch, rest) = split_with_mask(rest, 0b1 << 24);
-    match switch {
-        0 => parse_0b1110(rest),
-        1 => parse_0b1111(rest),
-        e => unreachable!("Invalid masking expected <2, got {}", e),
+    match check_bit(rest, 24) {
+        false => parse_0b1110(rest),
+        true => parse_0b1111(rest),
     }
 }
 fn parse_0b1110(rest: u32) -> Result<PartialArmInstruction, ParseError> {
@@ -204,5 +394,862 @@ fn parse_0b1110(rest: u32) -> Result<PartialArmInstruction, ParseError> {
     CoprocessorInstruction::new(rest, is_transfer).map(PartialArmInstruction::Coprocessor)
 }
 fn parse_0b1111(rest: u32) -> Result<PartialArmInstruction, ParseError> {
-    ExceptiongeneratingInstruction::new(rest).map(PartialArmInstruction::Exceptiongenerating)
+    Ok(PartialArmInstruction::Exceptiongenerating(
+        ExceptiongeneratingInstruction::new(rest),
+    ))
+}
+#[cfg(test)]
+pub(crate) mod tests {
+    use proptest::prelude::*;
+    use ux::u5;
+
+    use super::adresssing::*;
+    use super::coprocessor::{CDP, LDC, MCR, MCRR, MRC, MRRC, STC};
+    use super::dataprosessing::{
+        GenericDataInstruction, MOVLikeDataInstruction, NoDestinationDataInstruction,
+    };
+    use super::loadandstore::{
+        LoadAndStoreGenericInsturction, LoadAndStoreMiscInstruction, LoadAndStoreMultipleGeneric,
+        LoadAndStoreMultipleWriteGeneric, LoadAndStorePostIndexInstruction,
+    };
+    use super::register_access_instructions::IFlags;
+    use super::*;
+    use crate::instructions::{CRegister, Coprocessor, PSRFlags, RegisterList, RelativeAdress};
+
+    fn register() -> impl Strategy<Value = Register> {
+        (0..16u8).prop_map(|register| Register::try_from(register).unwrap())
+    }
+    fn c_register() -> impl Strategy<Value = CRegister> {
+        (0..16u8).prop_map(|register| CRegister::try_from(register).unwrap())
+    }
+    fn coprocessor() -> impl Strategy<Value = Coprocessor> {
+        (0..16u8).prop_map(|coprocessor| Coprocessor::try_from(coprocessor).unwrap())
+    }
+    ///Any condition but NV, which selects the unconditional instructions.
+    fn condition() -> impl Strategy<Value = Condition> {
+        (0..15u32).prop_map(Condition::new)
+    }
+    ///Mostly aligned offsets in range, with some that aren't.
+    fn relative_adress() -> impl Strategy<Value = RelativeAdress> {
+        prop_oneof![
+            4 => (-(1 << 23)..(1 << 23)).prop_map(|words: i32| RelativeAdress::new(words * 4)),
+            1 => any::<i32>().prop_map(RelativeAdress::new),
+        ]
+    }
+    fn shift_type() -> impl Strategy<Value = ShiftType> {
+        (0..4u32).prop_map(ShiftType::new)
+    }
+    ///Shifts with amounts a bit past the ones that can be encoded.
+    fn shift() -> impl Strategy<Value = Shift> {
+        prop_oneof![
+            (0..34u8).prop_map(Shift::LSL),
+            (0..34u8).prop_map(Shift::LSR),
+            (0..34u8).prop_map(Shift::ASR),
+            (0..34u8).prop_map(Shift::ROR),
+            Just(Shift::RRX),
+        ]
+    }
+    fn shifter_operand() -> impl Strategy<Value = ShifterOperand> {
+        prop_oneof![
+            (any::<u8>(), 0..17u8).prop_map(|(immediate, rotate)| ShifterOperand::Immediate(
+                RotatedImmediate { immediate, rotate }
+            )),
+            register().prop_map(ShifterOperand::Register),
+            (register(), shift())
+                .prop_map(|(register, shift)| ShifterOperand::ImmediateShift { register, shift }),
+            (register(), shift_type(), register()).prop_map(|(register, shift, amount)| {
+                ShifterOperand::RegisterShift {
+                    register,
+                    shift,
+                    amount,
+                }
+            }),
+        ]
+    }
+    fn indexing() -> impl Strategy<Value = Indexing> {
+        prop_oneof![
+            Just(Indexing::Offset),
+            Just(Indexing::PreIndexed),
+            Just(Indexing::PostIndexed),
+        ]
+    }
+    fn load_and_store_offset() -> impl Strategy<Value = LoadAndStoreOffset> {
+        prop_oneof![
+            (0..5000u16).prop_map(LoadAndStoreOffset::Immediate),
+            register().prop_map(LoadAndStoreOffset::Register),
+            (register(), shift()).prop_map(|(register, shift)| {
+                LoadAndStoreOffset::ScaledRegister { register, shift }
+            }),
+        ]
+    }
+    fn load_and_store_adressing_mode() -> impl Strategy<Value = LoadAndStoreAddressingMode> {
+        (
+            register(),
+            load_and_store_offset(),
+            any::<bool>(),
+            indexing(),
+        )
+            .prop_map(|(base, offset, add, indexing)| LoadAndStoreAddressingMode {
+                base,
+                offset,
+                add,
+                indexing,
+            })
+    }
+    fn misc_adressing_mode() -> impl Strategy<Value = MiscLoadAndStoreAddressingMode> {
+        let offset = prop_oneof![
+            any::<u8>().prop_map(MiscLoadAndStoreOffset::Immediate),
+            register().prop_map(MiscLoadAndStoreOffset::Register),
+        ];
+        (register(), offset, any::<bool>(), indexing()).prop_map(|(base, offset, add, indexing)| {
+            MiscLoadAndStoreAddressingMode {
+                base,
+                offset,
+                add,
+                indexing,
+            }
+        })
+    }
+    fn multiple_adressing_mode() -> impl Strategy<Value = LoadAndStoreMultipleAddressingMode> {
+        (0..4u32).prop_map(|mode| LoadAndStoreMultipleAddressingMode::new(mode << 23))
+    }
+    fn coprocessor_adressing_mode() -> impl Strategy<Value = CoprocessorAddressingMode> {
+        prop_oneof![
+            (register(), any::<u8>(), any::<bool>(), indexing()).prop_map(
+                |(base, offset, add, indexing)| CoprocessorAddressingMode::Indexed {
+                    base,
+                    offset,
+                    add,
+                    indexing,
+                }
+            ),
+            (register(), any::<u8>())
+                .prop_map(|(base, option)| CoprocessorAddressingMode::Unindexed { base, option }),
+        ]
+    }
+    fn branch() -> impl Strategy<Value = BranchInstruction> {
+        prop_oneof![
+            relative_adress().prop_map(BranchInstruction::B),
+            relative_adress().prop_map(BranchInstruction::BL),
+            register().prop_map(BranchInstruction::BLX),
+            register().prop_map(BranchInstruction::BX),
+        ]
+    }
+    fn data_prossessing() -> impl Strategy<Value = DataProssessingInstruction> {
+        use DataProssessingInstruction::*;
+        (
+            0..16u8,
+            register(),
+            register(),
+            any::<bool>(),
+            shifter_operand(),
+        )
+            .prop_map(|(opcode, destination, first_operand, s, shifter)| {
+                let generic = GenericDataInstruction {
+                    destination,
+                    first_operand,
+                    s,
+                    shifter,
+                };
+                let compare = NoDestinationDataInstruction {
+                    first_operand,
+                    s,
+                    shifter,
+                };
+                let moving = MOVLikeDataInstruction {
+                    destination,
+                    s,
+                    shifter,
+                };
+                match opcode {
+                    0 => AND(generic),
+                    1 => EOR(generic),
+                    2 => SUB(generic),
+                    3 => RSB(generic),
+                    4 => ADD(generic),
+                    5 => ADC(generic),
+                    6 => SBC(generic),
+                    7 => RSC(generic),
+                    8 => TST(compare),
+                    9 => TEQ(compare),
+                    10 => CMP(compare),
+                    11 => CMN(compare),
+                    12 => ORR(generic),
+                    13 => MOV(moving),
+                    14 => BIC(generic),
+                    _ => MVN(moving),
+                }
+            })
+    }
+    fn multiply() -> impl Strategy<Value = MultiplyInstruction> {
+        use MultiplyInstruction::*;
+        (
+            0..11u8,
+            any::<[bool; 3]>(),
+            [register(), register(), register(), register()],
+        )
+            .prop_map(|(kind, [s, x, y], [rd, rn, rs, rm])| match kind {
+                0 => MLA {
+                    destination: rd,
+                    s,
+                    first_operand: rm,
+                    second_operand: rs,
+                    add_operand: rn,
+                },
+                1 => MUL {
+                    destination: rd,
+                    s,
+                    first_operand: rm,
+                    second_operand: rs,
+                },
+                2 => SMLA {
+                    x,
+                    y,
+                    destination: rd,
+                    first_operand: rm,
+                    second_operand: rs,
+                    add_operand: rn,
+                },
+                3 => SMLAL {
+                    s,
+                    high: rd,
+                    low: rn,
+                    first_operand: rm,
+                    second_operand: rs,
+                },
+                4 => SMLAL2 {
+                    x,
+                    y,
+                    high: rd,
+                    low: rn,
+                    first_operand: rm,
+                    second_operand: rs,
+                },
+                5 => SMLAW {
+                    y,
+                    destination: rd,
+                    first_operand: rm,
+                    second_operand: rs,
+                    add_operand: rn,
+                },
+                6 => SMUL {
+                    x,
+                    y,
+                    destination: rd,
+                    first_operand: rm,
+                    second_operand: rs,
+                },
+                7 => SMULL {
+                    s,
+                    high: rd,
+                    low: rn,
+                    first_operand: rm,
+                    second_operand: rs,
+                },
+                8 => SMULW {
+                    y,
+                    destination: rd,
+                    first_operand: rm,
+                    second_operand: rs,
+                },
+                9 => UMLAL {
+                    s,
+                    high: rd,
+                    low: rn,
+                    first_operand: rm,
+                    second_operand: rs,
+                },
+                _ => UMULL {
+                    s,
+                    high: rd,
+                    low: rn,
+                    first_operand: rm,
+                    second_operand: rs,
+                },
+            })
+    }
+    fn aritmetic() -> impl Strategy<Value = AritmeticInstruction> {
+        use AritmeticInstruction::*;
+        (0..5u8, register(), register(), register()).prop_map(
+            |(kind, destination, first_operand, second_operand)| match kind {
+                0 => QADD {
+                    destination,
+                    first_operand,
+                    second_operand,
+                },
+                1 => QDADD {
+                    destination,
+                    first_operand,
+                    second_operand,
+                },
+                2 => QDSUB {
+                    destination,
+                    first_operand,
+                    second_operand,
+                },
+                3 => QSUB {
+                    destination,
+                    first_operand,
+                    second_operand,
+                },
+                _ => CLZ {
+                    destination,
+                    source: first_operand,
+                },
+            },
+        )
+    }
+    fn load_and_store() -> impl Strategy<Value = LoadAndStoreInstruction> {
+        use LoadAndStoreInstruction::*;
+        let generic = (register(), load_and_store_adressing_mode()).prop_map(
+            |(destination, adressing_mode)| LoadAndStoreGenericInsturction {
+                destination,
+                adressing_mode,
+            },
+        );
+        let post_indexed = (
+            register(),
+            register(),
+            load_and_store_offset(),
+            any::<bool>(),
+        )
+            .prop_map(|(destination, base, offset, add)| {
+                LoadAndStorePostIndexInstruction {
+                    destination,
+                    adressing_mode: PostIndexedAddressingMode { base, offset, add },
+                }
+            });
+        let misc = (register(), misc_adressing_mode()).prop_map(|(destination, adressing_mode)| {
+            LoadAndStoreMiscInstruction {
+                destination,
+                adressing_mode,
+            }
+        });
+        let multiple = (
+            0..5u8,
+            multiple_adressing_mode(),
+            register(),
+            any::<bool>(),
+            any::<u16>(),
+        )
+            .prop_map(|(kind, adressing_mode, base, write, registers)| {
+                let registers = RegisterList::new(registers);
+                let with_write = LoadAndStoreMultipleWriteGeneric {
+                    adressing_mode,
+                    base,
+                    write,
+                    registers,
+                };
+                let user = LoadAndStoreMultipleGeneric {
+                    adressing_mode,
+                    base,
+                    registers,
+                };
+                match kind {
+                    0 => LoadAndStoreMultiple::LDM(with_write),
+                    1 => LoadAndStoreMultiple::LDMR(user),
+                    2 => LoadAndStoreMultiple::LDMC(with_write),
+                    3 => LoadAndStoreMultiple::STM(with_write),
+                    _ => LoadAndStoreMultiple::STM2(user),
+                }
+            });
+        prop_oneof![
+            (0..4u8, generic).prop_map(|(kind, inst)| match kind {
+                0 => LDR(inst),
+                1 => LDRB(inst),
+                2 => STR(inst),
+                _ => STRB(inst),
+            }),
+            (0..4u8, post_indexed).prop_map(|(kind, inst)| match kind {
+                0 => LDRT(inst),
+                1 => LDRBT(inst),
+                2 => STRT(inst),
+                _ => STRBT(inst),
+            }),
+            (0..6u8, misc).prop_map(|(kind, inst)| match kind {
+                0 => LDRD(inst),
+                1 => LDRH(inst),
+                2 => LDRSB(inst),
+                3 => LDRSH(inst),
+                4 => STRD(inst),
+                _ => STRH(inst),
+            }),
+            (register(), register()).prop_map(|(destination, adress)| LDREX(
+                super::loadandstore::LDREX {
+                    destination,
+                    adress
+                }
+            )),
+            (register(), register(), register()).prop_map(|(destination, source, adress)| {
+                STREX(super::loadandstore::STREX {
+                    destination,
+                    source,
+                    adress,
+                })
+            }),
+            multiple.prop_map(Multiple),
+        ]
+    }
+    fn semaphore() -> impl Strategy<Value = SemaphoreInstruction> {
+        (any::<bool>(), register(), register(), register()).prop_map(
+            |(byte, destination, value, mem)| match byte {
+                true => SemaphoreInstruction::SWPB {
+                    destination,
+                    value,
+                    mem,
+                },
+                false => SemaphoreInstruction::SWP {
+                    destination,
+                    value,
+                    mem,
+                },
+            },
+        )
+    }
+    fn exception() -> impl Strategy<Value = ExceptiongeneratingInstruction> {
+        prop_oneof![
+            any::<u16>().prop_map(ExceptiongeneratingInstruction::BKPT),
+            (0..1u32 << 25).prop_map(ExceptiongeneratingInstruction::SWI),
+        ]
+    }
+    ///Opcodes a bit past the widest field.
+    fn coprocessor_instruction() -> impl Strategy<Value = CoprocessorInstruction> {
+        (
+            0..7u8,
+            coprocessor(),
+            [register(), register()],
+            [c_register(), c_register(), c_register()],
+            [0..18u8, 0..10u8],
+            any::<bool>(),
+            coprocessor_adressing_mode(),
+        )
+            .prop_map(
+                |(
+                    kind,
+                    coprocessor,
+                    [rd, rn],
+                    [crd, crn, crm],
+                    [opcode_1, opcode_2],
+                    long,
+                    addressing_mode,
+                )| {
+                    match kind {
+                        0 => CoprocessorInstruction::CDP(CDP {
+                            coprocessor,
+                            destination: crd,
+                            first_operand: crn,
+                            second_operand: crm,
+                            opcode_1,
+                            opcode_2,
+                        }),
+                        1 => CoprocessorInstruction::LDC(LDC {
+                            coprocessor,
+                            long_load: long,
+                            destination: crd,
+                            addressing_mode,
+                        }),
+                        2 => CoprocessorInstruction::STC(STC {
+                            long_store: long,
+                            coprocessor,
+                            source: crd,
+                            addressing_mode,
+                        }),
+                        3 => CoprocessorInstruction::MCR(MCR {
+                            coprocessor,
+                            value: rd,
+                            destination: crn,
+                            additional_destination: crm,
+                            opcode_1,
+                            opcode_2,
+                        }),
+                        4 => CoprocessorInstruction::MRC(MRC {
+                            coprocessor,
+                            value: rd,
+                            destination: crn,
+                            additional_destination: crm,
+                            opcode_1,
+                            opcode_2,
+                        }),
+                        5 => CoprocessorInstruction::MCRR(MCRR {
+                            coprocessor,
+                            opcode: opcode_1,
+                            first_register: rd,
+                            second_register: rn,
+                            destination: crm,
+                        }),
+                        _ => CoprocessorInstruction::MRRC(MRRC {
+                            coprocessor,
+                            opcode: opcode_1,
+                            first_register: rd,
+                            second_register: rn,
+                            destination: crm,
+                        }),
+                    }
+                },
+            )
+    }
+    fn register_access() -> impl Strategy<Value = RegisterAccessInstruction> {
+        let flags = (0..16u32).prop_map(|mask| PSRFlags::new(mask << 16));
+        let i_flags = any::<[bool; 4]>().prop_map(|[enable, a, i, f]| IFlags { enable, a, i, f });
+        prop_oneof![
+            (register(), any::<bool>()).prop_map(|(destination, is_spsr)| {
+                RegisterAccessInstruction::MRS {
+                    destination,
+                    is_spsr,
+                }
+            }),
+            (flags, shifter_operand(), any::<bool>()).prop_map(
+                |(flags, shifter_operand, is_spsr)| RegisterAccessInstruction::MSR {
+                    flags,
+                    shifter_operand,
+                    is_spsr,
+                }
+            ),
+            (
+                proptest::option::of(i_flags),
+                proptest::option::of((0..32u8).prop_map(u5::new))
+            )
+                .prop_map(|(flags, mode)| RegisterAccessInstruction::CPS { flags, mode }),
+        ]
+    }
+    fn unconditional() -> impl Strategy<Value = UnconditionalInstruction> {
+        prop_oneof![
+            relative_adress().prop_map(|offset| {
+                UnconditionalInstruction::BLX(RelativeAdress::new(offset.offset() & !0b1))
+            }),
+            coprocessor_instruction().prop_map(UnconditionalInstruction::Coprocessor),
+            load_and_store_adressing_mode()
+                .prop_map(|addressing_mode| UnconditionalInstruction::PLD { addressing_mode }),
+            register_access().prop_map(UnconditionalInstruction::RegisterAccess),
+        ]
+    }
+    ///Instructions with their fields in range and a bit past it, with conditions that may not
+    ///fit them.
+    fn instruction() -> impl Strategy<Value = ArmInstruction> {
+        let op = prop_oneof![
+            branch().prop_map(PartialArmInstruction::Branch),
+            data_prossessing().prop_map(PartialArmInstruction::DataProssessing),
+            multiply().prop_map(PartialArmInstruction::Multiply),
+            aritmetic().prop_map(PartialArmInstruction::Aritmetic),
+            load_and_store().prop_map(PartialArmInstruction::LoadAndStore),
+            semaphore().prop_map(PartialArmInstruction::Semaphore),
+            exception().prop_map(PartialArmInstruction::Exceptiongenerating),
+            coprocessor_instruction().prop_map(PartialArmInstruction::Coprocessor),
+            register_access().prop_map(PartialArmInstruction::RegisterAccess),
+        ];
+        prop_oneof![
+            (condition(), op).prop_map(|(cond, op)| ArmInstruction { cond, op }),
+            unconditional().prop_map(|op| ArmInstruction {
+                cond: Condition::Never,
+                op: PartialArmInstruction::Unconditional(op),
+            }),
+            // the wrong side of the unconditional space
+            (0..16u32, register_access()).prop_map(|(cond, op)| ArmInstruction {
+                cond: Condition::new(cond),
+                op: PartialArmInstruction::RegisterAccess(op),
+            }),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig {
+            cases: 20_000,
+            // about a third of the instructions can't be encoded
+            max_global_rejects: 20_000,
+            ..ProptestConfig::default()
+        })]
+
+        #[test]
+        fn decode_encode_round_trip(instruction in instruction()) {
+            // values past a field and conditions that don't fit are rejected, which proptest counts
+            let word = instruction.encode();
+            prop_assume!(word.is_ok(), "{:?} has no encoding", instruction);
+            prop_assert_eq!(ArmInstruction::try_from(word.unwrap()).ok(), Some(instruction));
+        }
+
+        #[test]
+        fn encode_decode_round_trip(word in any::<u32>()) {
+            if let Ok(instruction) = ArmInstruction::try_from(word) {
+                prop_assert_eq!(instruction.encode().ok(), Some(word));
+            }
+        }
+    }
+
+    ///The operation `word` decodes to, for the tests of the instruction classes.
+    pub(crate) fn op(word: u32) -> Option<PartialArmInstruction> {
+        ArmInstruction::try_from(word)
+            .ok()
+            .map(|instruction| instruction.op)
+    }
+    ///The violations of `word` on ARMv5TE.
+    pub(crate) fn violations(word: u32) -> Violations {
+        ArmInstruction::decode(word, Architecture::ARMv5TE).violations
+    }
+
+    #[test]
+    fn miscellaneous_instructions() {
+        use register_access_instructions::RegisterAccessInstruction::*;
+        use AritmeticInstruction::*;
+        use PartialArmInstruction::*;
+        use Register::*;
+        // bx r2 and blx r4
+        assert_eq!(op(0xe12f_ff12), Some(Branch(BranchInstruction::BX(R2))));
+        assert_eq!(op(0xe12f_ff34), Some(Branch(BranchInstruction::BLX(R4))));
+        // clz r1, r2
+        assert_eq!(
+            op(0xe16f_1f12),
+            Some(Aritmetic(CLZ {
+                destination: R1,
+                source: R2
+            }))
+        );
+        // qadd, qsub, qdadd and qdsub r0, r1, r2
+        let (destination, first_operand, second_operand) = (R0, R1, R2);
+        assert_eq!(
+            op(0xe102_0051),
+            Some(Aritmetic(QADD {
+                destination,
+                first_operand,
+                second_operand
+            }))
+        );
+        assert_eq!(
+            op(0xe122_0051),
+            Some(Aritmetic(QSUB {
+                destination,
+                first_operand,
+                second_operand
+            }))
+        );
+        assert_eq!(
+            op(0xe142_0051),
+            Some(Aritmetic(QDADD {
+                destination,
+                first_operand,
+                second_operand
+            }))
+        );
+        assert_eq!(
+            op(0xe162_0051),
+            Some(Aritmetic(QDSUB {
+                destination,
+                first_operand,
+                second_operand
+            }))
+        );
+        // mrs r3, cpsr and mrs r3, spsr
+        assert_eq!(
+            op(0xe10f_3000),
+            Some(RegisterAccess(MRS {
+                destination: R3,
+                is_spsr: false
+            }))
+        );
+        assert_eq!(
+            op(0xe14f_3000),
+            Some(RegisterAccess(MRS {
+                destination: R3,
+                is_spsr: true
+            }))
+        );
+        // msr cpsr_fc, r1
+        assert_eq!(
+            op(0xe129_f001),
+            Some(RegisterAccess(MSR {
+                flags: PSRFlags {
+                    c: true,
+                    x: false,
+                    s: false,
+                    f: true
+                },
+                shifter_operand: ShifterOperand::Register(R1),
+                is_spsr: false
+            }))
+        );
+        // msr spsr_f, #0xf0000000
+        assert_eq!(
+            op(0xe368_f20f),
+            Some(RegisterAccess(MSR {
+                flags: PSRFlags {
+                    c: false,
+                    x: false,
+                    s: false,
+                    f: true
+                },
+                shifter_operand: ShifterOperand::Immediate(RotatedImmediate {
+                    immediate: 0xf,
+                    rotate: 2
+                }),
+                is_spsr: true
+            }))
+        );
+        // bkpt #0x1234
+        assert_eq!(
+            op(0xe121_2374),
+            Some(Exceptiongenerating(ExceptiongeneratingInstruction::BKPT(
+                0x1234
+            )))
+        );
+        // smlabb r0, r1, r2, r3
+        assert!(matches!(op(0xe100_3281), Some(Multiply(_))));
+        // bxj and the unallocated encodings
+        for word in [0xe12f_ff20, 0xe100_0070] {
+            assert!(matches!(
+                ArmInstruction::try_from(word),
+                Err(ParseError::Undefined(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn miscellaneous_should_be_fields() {
+        // mrs r3, cpsr with an SBZ bit set and with an SBO bit cleared
+        assert_eq!(violations(0xe10f_3001), Violations::SHOULD_BE_ZERO);
+        assert_eq!(violations(0xe10e_3000), Violations::SHOULD_BE_ONE);
+        // msr cpsr_fc, r1 with an SBZ bit set and with an SBO bit cleared
+        assert_eq!(violations(0xe129_f101), Violations::SHOULD_BE_ZERO);
+        assert_eq!(violations(0xe129_7001), Violations::SHOULD_BE_ONE);
+        // bx r2 with an SBO bit cleared
+        assert_eq!(violations(0xe12f_7f12), Violations::SHOULD_BE_ONE);
+        // clz r1, r2 with SBO bits cleared in both fields
+        assert_eq!(violations(0xe16e_1e12), Violations::SHOULD_BE_ONE);
+        // qadd r0, r1, r2 with an SBZ bit set
+        assert_eq!(violations(0xe102_0851), Violations::SHOULD_BE_ZERO);
+        // mrs pc, cpsr and blx pc
+        assert_eq!(violations(0xe10f_f000), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0xe12f_ff3f), Violations::UNPREDICTABLE);
+        // the decoded instruction is the one with the fields fixed
+        assert_eq!(
+            ArmInstruction::decode(0xe16e_1e12, Architecture::ARMv5TE).instruction,
+            ArmInstruction::try_from(0xe16f_1f12).unwrap()
+        );
+    }
+
+    #[test]
+    fn architectures() {
+        use Architecture::*;
+        let is_undefined = |word, architecture| {
+            let decoded = ArmInstruction::decode(word, architecture);
+            matches!(decoded.instruction.op, PartialArmInstruction::Undefined(_))
+        };
+        let words = [
+            // bx r2, swp r0, r1, [r2] and mul r0, r1, r2
+            (0xe12f_ff12, ARMv4T),
+            (0xe102_0091, ARMv4T),
+            (0xe000_0291, ARMv4T),
+            // clz r1, r2, blx r4, blx 0x100, ldrd r2, r3, [r4, #8] and strd r4, r5, [r6], #-16
+            (0xe16f_1f12, ARMv5TE),
+            (0xe12f_ff34, ARMv5TE),
+            (0xfa00_003e, ARMv5TE),
+            (0xe1c4_20d8, ARMv5TE),
+            (0xe046_41f0, ARMv5TE),
+            // qadd r0, r1, r2, bkpt 0x1234, smlabb r0, r1, r2, r3, pld [r0, #4] and
+            // mcrr p5, 2, r0, r1, c6
+            (0xe102_0051, ARMv5TE),
+            (0xe121_2374, ARMv5TE),
+            (0xe100_3281, ARMv5TE),
+            (0xf5d0_f004, ARMv5TE),
+            (0xec41_0526, ARMv5TE),
+            // ldrex r0, [r1], strex r0, r1, [r2], cpsie i and mcrr2 p5, 2, r0, r1, c6
+            (0xe191_0f9f, ARMv6),
+            (0xe182_0f91, ARMv6),
+            (0xf108_0080, ARMv6),
+            (0xfc41_0526, ARMv6),
+        ];
+        for (word, architecture) in words {
+            for tested in [ARMv4T, ARMv5TE, ARMv6] {
+                assert_eq!(
+                    is_undefined(word, tested),
+                    tested < architecture,
+                    "{word:#010x} on {tested:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rendering() {
+        let text = |word: u32| ArmInstruction::try_from(word).unwrap().to_string();
+        // Shifter operands
+        assert_eq!(text(0xe081_0102), "add r0, r1, r2, lsl #2");
+        assert_eq!(text(0xe1a0_0061), "mov r0, r1, rrx");
+        assert_eq!(text(0xe181_0332), "orr r0, r1, r2, lsr r3");
+        // Negative offsets
+        assert_eq!(text(0xe532_1004), "ldr r1, [r2, #-4]!");
+        assert_eq!(text(0xe002_10b3), "strh r1, [r2], -r3");
+        assert_eq!(text(0xe711_01c2), "ldr r0, [r1, -r2, asr #3]");
+        // Register lists
+        assert_eq!(text(0xe92d_400f), "stmdb sp!, {r0-r3, lr}");
+        assert_eq!(text(0xe8bd_0003), "ldmia sp!, {r0, r1}");
+        // The ^ forms
+        assert_eq!(text(0xe8d0_0006), "ldmia r0, {r1, r2}^");
+        assert_eq!(text(0xe8fd_800f), "ldmia sp!, {r0-r3, pc}^");
+        assert_eq!(text(0xe940_00f0), "stmdb r0, {r4-r7}^");
+    }
+
+    #[test]
+    fn encodings() {
+        use dataprosessing::DataProssessingInstruction::*;
+        use loadandstore::LoadAndStoreMultiple::LDM;
+        use PartialArmInstruction::*;
+        use Register::*;
+        let encode =
+            |cond: Condition, op: PartialArmInstruction| ArmInstruction { cond, op }.encode().ok();
+        // add r0, r1, r2, lsl #2
+        let add = ADD(GenericDataInstruction {
+            destination: R0,
+            first_operand: R1,
+            s: false,
+            shifter: ShifterOperand::ImmediateShift {
+                register: R2,
+                shift: Shift::LSL(2),
+            },
+        });
+        assert_eq!(
+            encode(Condition::Allways, DataProssessing(add)),
+            Some(0xe081_0102)
+        );
+        // movseq r0, #4, 4 keeps the rotation
+        let mov = MOV(MOVLikeDataInstruction {
+            destination: R0,
+            s: true,
+            shifter: ShifterOperand::Immediate(RotatedImmediate {
+                immediate: 4,
+                rotate: 2,
+            }),
+        });
+        assert_eq!(
+            encode(Condition::Equal, DataProssessing(mov)),
+            Some(0x03b0_0204)
+        );
+        // bx r2
+        assert_eq!(
+            encode(Condition::Allways, Branch(BranchInstruction::BX(R2))),
+            Some(0xe12f_ff12)
+        );
+        // ldmia sp!, {r0-r3, pc}
+        let ldm = LDM(LoadAndStoreMultipleWriteGeneric {
+            adressing_mode: LoadAndStoreMultipleAddressingMode::IncrementAfter,
+            base: R13,
+            write: true,
+            registers: RegisterList::new(0x800f),
+        });
+        assert_eq!(
+            encode(
+                Condition::Allways,
+                LoadAndStore(LoadAndStoreInstruction::Multiple(ldm))
+            ),
+            Some(0xe8bd_800f)
+        );
+        // lsl #0 is written as a plain register
+        let mov = MOV(MOVLikeDataInstruction {
+            destination: R0,
+            s: false,
+            shifter: ShifterOperand::ImmediateShift {
+                register: R1,
+                shift: Shift::LSL(0),
+            },
+        });
+        assert_eq!(encode(Condition::Allways, DataProssessing(mov)), None);
+    }
 }
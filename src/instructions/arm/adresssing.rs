@@ -1,7 +1,904 @@
+//! The addressing modes of chapter A5.
+use std::fmt::{self, Display};
 
+use crate::errors::{EncodeError, ParseError};
+use crate::instructions::{
+    check_bit, encode_field, immediate, should_be_zero, split_with_range, Register, Syntax,
+};
+
+///Addressing Mode 1 - Data-processing operands. See A5.1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShifterOperand {
+    ///32-bit immediate. See A5.1.3.
+    Immediate(RotatedImmediate),
+    ///Register. See A5.1.4.
+    Register(Register),
+    ///Register shifted by an immediate, including RRX. See A5.1.5 to A5.1.13.
+    ImmediateShift { register: Register, shift: Shift },
+    ///Register shifted by the least significant byte of a register. See A5.1.5 to A5.1.12.
+    RegisterShift {
+        register: Register,
+        shift: ShiftType,
+        amount: Register,
+    },
+}
+impl ShifterOperand {
+    ///Decodes bits 0 to 11, `immediate` is the I bit.
+    pub fn new(value: u32, immediate: bool) -> Result<Self, ParseError> {
+        if immediate {
+            return Ok(Self::Immediate(RotatedImmediate::new(value)));
+        }
+        let (rm, value) = split_with_range(value, 0..=3);
+        let (shift, value) = split_with_range(value, 5..=6);
+        let register = Register::try_from(rm as u8)?;
+        if check_bit(value, 4) {
+            // bit 7 set here is the multiply and extra load/store space
+            if check_bit(value, 7) {
+                return Err(ParseError::InvalidMask {
+                    invalid_set_bytes: 1 << 7,
+                });
+            }
+            let (rs, _) = split_with_range(value, 8..=11);
+            return Ok(Self::RegisterShift {
+                register,
+                shift: ShiftType::new(shift),
+                amount: Register::try_from(rs as u8)?,
+            });
+        }
+        let (shift_imm, _) = split_with_range(value, 7..=11);
+        Ok(match Shift::new(shift, shift_imm) {
+            Shift::LSL(0) => Self::Register(register),
+            shift => Self::ImmediateShift { register, shift },
+        })
+    }
+    ///Encodes bits 0 to 11, the I bit is left to the instruction.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        Ok(match *self {
+            Self::Immediate(immediate) => immediate.encode()?,
+            Self::Register(register) => register as u32,
+            Self::ImmediateShift {
+                shift: Shift::LSL(0),
+                ..
+            } => return Err(EncodeError::Unencodable("a shift by LSL #0")),
+            Self::ImmediateShift { register, shift } => shift.encode()? | register as u32,
+            Self::RegisterShift {
+                register,
+                shift,
+                amount,
+            } => (amount as u32) << 8 | shift.encode() | 1 << 4 | register as u32,
+        })
+    }
+    ///The operand in `syntax`, which only differs for rotated immediates.
+    pub fn operand(self, syntax: Syntax) -> String {
+        match self {
+            Self::Immediate(immediate) => immediate.operand(syntax),
+            _ => self.to_string(),
+        }
+    }
+}
+impl Display for ShifterOperand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Immediate(immediate) => write!(f, "{immediate}"),
+            Self::Register(register) => write!(f, "{register}"),
+            Self::ImmediateShift { register, shift } => write!(f, "{register}, {shift}"),
+            Self::RegisterShift {
+                register,
+                shift,
+                amount,
+            } => write!(f, "{register}, {shift} {amount}"),
+        }
+    }
+}
+///8 bit immediate rotated right by twice the 4 bit rotation.
+///
+///The encoding is kept as is since the same value can have several encodings which differ in the
+///shifter carry out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotatedImmediate {
+    pub immediate: u8,
+    pub rotate: u8,
+}
+impl RotatedImmediate {
+    ///Decodes `rotate_imm` from bits 8 to 11 and `immed_8` from bits 0 to 7.
+    pub fn new(value: u32) -> Self {
+        let (rotate, value) = split_with_range(value, 8..=11);
+        let (immediate, _) = split_with_range(value, 0..=7);
+        Self {
+            immediate: immediate as u8,
+            rotate: rotate as u8,
+        }
+    }
+    ///The encoding of `value` with the smallest rotation, `None` if it can't be encoded.
+    pub fn from_value(value: u32) -> Option<Self> {
+        (0..16u8).find_map(|rotate| {
+            let immediate = value.rotate_left(2 * rotate as u32);
+            (immediate <= 0xff).then_some(Self {
+                immediate: immediate as u8,
+                rotate,
+            })
+        })
+    }
+    pub const fn value(self) -> u32 {
+        (self.immediate as u32).rotate_right(2 * self.rotate as u32)
+    }
+    ///Encodes `rotate_imm` into bits 8 to 11 and `immed_8` into bits 0 to 7.
+    pub fn encode(self) -> Result<u32, EncodeError> {
+        let rotate = encode_field(self.rotate as u32, 4, "rotation")?;
+        Ok(rotate << 8 | self.immediate as u32)
+    }
+    ///The value, or the 8 bit immediate and the rotation when an assembler would pick another
+    ///encoding for the value. Only UAL writes the rotation with a `#`.
+    pub fn operand(self, syntax: Syntax) -> String {
+        match (Self::from_value(self.value()) == Some(self), syntax) {
+            (true, _) => immediate(self.value()),
+            (false, Syntax::Unified) => format!("#{}, #{}", self.immediate, 2 * self.rotate),
+            (false, Syntax::Gnu | Syntax::Armasm) => {
+                format!("#{}, {}", self.immediate, 2 * self.rotate)
+            }
+        }
+    }
+    ///The shifter carry out, which is the C flag when the rotation is zero and bit 31 of the
+    ///value otherwise.
+    pub const fn carry_out(self, carry: bool) -> bool {
+        match self.rotate {
+            0 => carry,
+            _ => check_bit(self.value(), 31),
+        }
+    }
+}
+impl Display for RotatedImmediate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.operand(Syntax::Gnu))
+    }
+}
+///Shift by a register amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShiftType {
+    LSL,
+    LSR,
+    ASR,
+    ROR,
+}
+impl ShiftType {
+    pub const fn mnemonic(self) -> &'static str {
+        match self {
+            Self::LSL => "lsl",
+            Self::LSR => "lsr",
+            Self::ASR => "asr",
+            Self::ROR => "ror",
+        }
+    }
+    ///Takes the two bit shift type.
+    pub fn new(shift: u32) -> Self {
+        use ShiftType::*;
+        match shift & 0b11 {
+            0b00 => LSL,
+            0b01 => LSR,
+            0b10 => ASR,
+            _ => ROR,
+        }
+    }
+    ///The shift type in bits 5 and 6.
+    pub const fn encode(self) -> u32 {
+        (self as u32) << 5
+    }
+}
+impl Display for ShiftType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.mnemonic())
+    }
+}
+///Shift by an immediate amount, as used by scaled register offsets and shifter operands.
+///
+///The amounts are the architectural ones, so `LSR(32)` and `ASR(32)` are the encodings with a
+///`shift_imm` of zero, and `ROR` with a `shift_imm` of zero is `RRX`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shift {
+    ///Logical shift left by 0 to 31.
+    LSL(u8),
+    ///Logical shift right by 1 to 32.
+    LSR(u8),
+    ///Arithmetic shift right by 1 to 32.
+    ASR(u8),
+    ///Rotate right by 1 to 31.
+    ROR(u8),
+    ///Rotate right by one bit with extend.
+    RRX,
+}
+impl Shift {
+    ///Takes the two bit shift type and the five bit `shift_imm` field.
+    pub fn new(shift: u32, shift_imm: u32) -> Self {
+        let shift_imm = (shift_imm & 0b11111) as u8;
+        use Shift::*;
+        match (shift & 0b11, shift_imm) {
+            (0b00, n) => LSL(n),
+            (0b01, 0) => LSR(32),
+            (0b01, n) => LSR(n),
+            (0b10, 0) => ASR(32),
+            (0b10, n) => ASR(n),
+            (_, 0) => RRX,
+            (_, n) => ROR(n),
+        }
+    }
+    pub const fn mnemonic(self) -> &'static str {
+        match self {
+            Self::LSL(_) => "lsl",
+            Self::LSR(_) => "lsr",
+            Self::ASR(_) => "asr",
+            Self::ROR(_) => "ror",
+            Self::RRX => "rrx",
+        }
+    }
+    ///Encodes the shift type into bits 5 and 6 and `shift_imm` into bits 7 to 11.
+    pub fn encode(self) -> Result<u32, EncodeError> {
+        let (shift, shift_imm) = match self {
+            Self::LSL(amount @ 0..=31) => (ShiftType::LSL, amount),
+            Self::LSR(amount @ 1..=32) => (ShiftType::LSR, amount % 32),
+            Self::ASR(amount @ 1..=32) => (ShiftType::ASR, amount % 32),
+            Self::ROR(amount @ 1..=31) => (ShiftType::ROR, amount),
+            Self::RRX => (ShiftType::ROR, 0),
+            Self::LSL(amount) | Self::LSR(amount) | Self::ASR(amount) | Self::ROR(amount) => {
+                return Err(EncodeError::OutOfRange {
+                    field: "shift amount",
+                    value: amount as i64,
+                })
+            }
+        };
+        Ok((shift_imm as u32) << 7 | shift.encode())
+    }
+    ///The amount, `None` for RRX which always shifts by one bit.
+    pub const fn amount(self) -> Option<u8> {
+        match self {
+            Self::LSL(amount) | Self::LSR(amount) | Self::ASR(amount) | Self::ROR(amount) => {
+                Some(amount)
+            }
+            Self::RRX => None,
+        }
+    }
+}
+impl Display for Shift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.amount() {
+            Some(amount) => write!(f, "{} #{amount}", self.mnemonic()),
+            None => write!(f, "{}", self.mnemonic()),
+        }
+    }
+}
+///How the base register is updated by a load or store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indexing {
+    ///The address is base plus offset, the base is left unchanged.
+    Offset,
+    ///The address is base plus offset, which is then written back to the base.
+    PreIndexed,
+    ///The address is the base, base plus offset is then written back to the base.
+    PostIndexed,
+}
+impl Indexing {
+    ///Writes the address of `base` and `offset`, an offset of `None` is zero.
+    fn fmt_adress(
+        self,
+        f: &mut fmt::Formatter<'_>,
+        base: Register,
+        offset: Option<String>,
+    ) -> fmt::Result {
+        match (self, offset) {
+            (Self::Offset, None) => write!(f, "[{base}]"),
+            (Self::Offset, Some(offset)) => write!(f, "[{base}, {offset}]"),
+            (Self::PreIndexed, None) => write!(f, "[{base}, #0]!"),
+            (Self::PreIndexed, Some(offset)) => write!(f, "[{base}, {offset}]!"),
+            (Self::PostIndexed, None) => write!(f, "[{base}], #0"),
+            (Self::PostIndexed, Some(offset)) => write!(f, "[{base}], {offset}"),
+        }
+    }
+    ///Encodes the P and W bits.
+    pub const fn encode(self) -> u32 {
+        match self {
+            Indexing::Offset => 1 << 24,
+            Indexing::PreIndexed => 1 << 24 | 1 << 21,
+            Indexing::PostIndexed => 0,
+        }
+    }
+    ///Decodes the P and W bits.
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        Ok(match (check_bit(value, 24), check_bit(value, 21)) {
+            (true, false) => Indexing::Offset,
+            (true, true) => Indexing::PreIndexed,
+            (false, false) => Indexing::PostIndexed,
+            // P == 0 and W == 1 is either a different instruction or unpredictable
+            (false, true) => {
+                return Err(ParseError::InvalidMask {
+                    invalid_set_bytes: 1 << 21,
+                })
+            }
+        })
+    }
+}
+///Offset of a word or unsigned byte load or store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadAndStoreOffset {
+    ///12 bit immediate offset.
+    Immediate(u16),
+    ///Register offset, the same as a scaled register offset with `LSL #0`.
+    Register(Register),
+    ///Register offset shifted by an immediate.
+    ScaledRegister { register: Register, shift: Shift },
+}
+impl LoadAndStoreOffset {
+    pub fn new(value: u32, register_offset: bool) -> Result<Self, ParseError> {
+        if !register_offset {
+            let (offset, _) = split_with_range(value, 0..=11);
+            return Ok(Self::Immediate(offset as u16));
+        }
+        let (shift_imm, value) = split_with_range(value, 7..=11);
+        let (shift, value) = split_with_range(value, 5..=6);
+        let (rm, _) = split_with_range(value, 0..=3);
+        let register = Register::try_from(rm as u8)?;
+        Ok(match Shift::new(shift, shift_imm) {
+            Shift::LSL(0) => Self::Register(register),
+            shift => Self::ScaledRegister { register, shift },
+        })
+    }
+    ///Encodes bits 0 to 11, the I bit that selects a register offset is left to the
+    ///instruction.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        Ok(match *self {
+            Self::Immediate(offset) => encode_field(offset as u32, 12, "offset")?,
+            Self::Register(register) => register as u32,
+            Self::ScaledRegister {
+                shift: Shift::LSL(0),
+                ..
+            } => {
+                return Err(EncodeError::Unencodable(
+                    "a scaled register offset of LSL #0",
+                ))
+            }
+            Self::ScaledRegister { register, shift } => shift.encode()? | register as u32,
+        })
+    }
+    ///Whether the offset is a register, which is the I bit.
+    pub fn is_register(&self) -> bool {
+        !matches!(self, Self::Immediate(_))
+    }
+}
+impl LoadAndStoreOffset {
+    ///The offset register, `None` for an immediate offset.
+    pub fn register(self) -> Option<Register> {
+        match self {
+            Self::Immediate(_) => None,
+            Self::Register(register) | Self::ScaledRegister { register, .. } => Some(register),
+        }
+    }
+    ///The offset with its sign, `None` for adding an immediate of zero.
+    fn signed(self, add: bool) -> Option<String> {
+        let sign = if add { "" } else { "-" };
+        match self {
+            Self::Immediate(0) if add => None,
+            Self::Immediate(offset) => Some(format!("#{sign}{offset}")),
+            Self::Register(register) => Some(format!("{sign}{register}")),
+            Self::ScaledRegister { register, shift } => Some(format!("{sign}{register}, {shift}")),
+        }
+    }
+}
+///Addressing Mode 2 - Load and Store Word or Unsigned Byte. See A5.2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadAndStoreAddressingMode {
+    pub base: Register,
+    pub offset: LoadAndStoreOffset,
+    pub add: bool,
+    pub indexing: Indexing,
+}
+impl LoadAndStoreAddressingMode {
+    pub fn new(value: u32, register_offset: bool) -> Result<Self, ParseError> {
+        let (rn, _) = split_with_range(value, 16..=19);
+        Ok(Self {
+            base: Register::try_from(rn as u8)?,
+            offset: LoadAndStoreOffset::new(value, register_offset)?,
+            add: check_bit(value, 23),
+            indexing: Indexing::new(value)?,
+        })
+    }
+    ///Encodes the I, P, U and W bits, Rn and the offset.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        Ok((self.offset.is_register() as u32) << 25
+            | self.indexing.encode()
+            | (self.add as u32) << 23
+            | (self.base as u32) << 16
+            | self.offset.encode()?)
+    }
+}
+impl Display for LoadAndStoreAddressingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.indexing
+            .fmt_adress(f, self.base, self.offset.signed(self.add))
+    }
+}
+///Addressing Mode 2 restricted to post-indexing, used by the user mode privilege loads and
+///stores where the W bit selects the instruction instead of writeback.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DataProcessingAddressingMode{
-    Immediate(),
-    Register(),
-    LogicalShiftLeftImm()
+pub struct PostIndexedAddressingMode {
+    pub base: Register,
+    pub offset: LoadAndStoreOffset,
+    pub add: bool,
+}
+impl PostIndexedAddressingMode {
+    pub fn new(value: u32, register_offset: bool) -> Result<Self, ParseError> {
+        let (rn, _) = split_with_range(value, 16..=19);
+        Ok(Self {
+            base: Register::try_from(rn as u8)?,
+            offset: LoadAndStoreOffset::new(value, register_offset)?,
+            add: check_bit(value, 23),
+        })
+    }
+    ///Encodes the I and U bits, Rn and the offset. P is clear, the instruction sets W.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        Ok((self.offset.is_register() as u32) << 25
+            | (self.add as u32) << 23
+            | (self.base as u32) << 16
+            | self.offset.encode()?)
+    }
+}
+impl Display for PostIndexedAddressingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Indexing::PostIndexed.fmt_adress(f, self.base, self.offset.signed(self.add))
+    }
+}
+///Offset of a halfword, signed byte or doubleword load or store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MiscLoadAndStoreOffset {
+    ///8 bit immediate offset, split over `immedH` and `immedL` in the encoding.
+    Immediate(u8),
+    Register(Register),
+}
+impl MiscLoadAndStoreOffset {
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        let (immed_h, value) = split_with_range(value, 8..=11);
+        let (immed_l, _) = split_with_range(value, 0..=3);
+        Ok(match check_bit(value, 22) {
+            true => Self::Immediate((immed_h << 4 | immed_l) as u8),
+            false => {
+                if immed_h > 0 {
+                    return Err(ParseError::ShouldBeZero(immed_h << 8));
+                }
+                Self::Register(Register::try_from(immed_l as u8)?)
+            }
+        })
+    }
+    ///Encodes the I bit in bit 22 and `immedH` and `immedL`, or Rm.
+    pub fn encode(&self) -> u32 {
+        match *self {
+            Self::Immediate(offset) => 1 << 22 | (offset as u32 >> 4) << 8 | offset as u32 & 0b1111,
+            Self::Register(register) => register as u32,
+        }
+    }
+}
+impl MiscLoadAndStoreOffset {
+    ///The offset register, `None` for an immediate offset.
+    pub fn register(self) -> Option<Register> {
+        match self {
+            Self::Immediate(_) => None,
+            Self::Register(register) => Some(register),
+        }
+    }
+    ///The offset with its sign, `None` for adding an immediate of zero.
+    fn signed(self, add: bool) -> Option<String> {
+        let sign = if add { "" } else { "-" };
+        match self {
+            Self::Immediate(0) if add => None,
+            Self::Immediate(offset) => Some(format!("#{sign}{offset}")),
+            Self::Register(register) => Some(format!("{sign}{register}")),
+        }
+    }
+}
+///Addressing Mode 3 - Miscellaneous Loads and Stores. See A5.3.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MiscLoadAndStoreAddressingMode {
+    pub base: Register,
+    pub offset: MiscLoadAndStoreOffset,
+    pub add: bool,
+    pub indexing: Indexing,
+}
+impl MiscLoadAndStoreAddressingMode {
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        // post-indexing with writeback is unpredictable
+        if !check_bit(value, 24) {
+            should_be_zero(value, 1 << 21)?;
+        }
+        let (rn, _) = split_with_range(value, 16..=19);
+        Ok(Self {
+            base: Register::try_from(rn as u8)?,
+            offset: MiscLoadAndStoreOffset::new(value)?,
+            add: check_bit(value, 23),
+            indexing: Indexing::new(value)?,
+        })
+    }
+    ///Encodes the P, U, I and W bits, Rn and the offset.
+    pub fn encode(&self) -> u32 {
+        self.indexing.encode()
+            | (self.add as u32) << 23
+            | (self.base as u32) << 16
+            | self.offset.encode()
+    }
+}
+impl Display for MiscLoadAndStoreAddressingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.indexing
+            .fmt_adress(f, self.base, self.offset.signed(self.add))
+    }
+}
+///Addressing Mode 4 - Load and Store Multiple. See A5.4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadAndStoreMultipleAddressingMode {
+    ///IA
+    IncrementAfter,
+    ///IB
+    IncrementBefore,
+    ///DA
+    DecrementAfter,
+    ///DB
+    DecrementBefore,
+}
+impl LoadAndStoreMultipleAddressingMode {
+    pub fn new(value: u32) -> Self {
+        use LoadAndStoreMultipleAddressingMode::*;
+        match (check_bit(value, 24), check_bit(value, 23)) {
+            (false, true) => IncrementAfter,
+            (true, true) => IncrementBefore,
+            (false, false) => DecrementAfter,
+            (true, false) => DecrementBefore,
+        }
+    }
+    ///Encodes the P and U bits.
+    pub const fn encode(self) -> u32 {
+        use LoadAndStoreMultipleAddressingMode::*;
+        match self {
+            IncrementAfter => 1 << 23,
+            IncrementBefore => 1 << 24 | 1 << 23,
+            DecrementAfter => 0,
+            DecrementBefore => 1 << 24,
+        }
+    }
+    ///The suffix in `syntax`, where UAL leaves out the default IA.
+    pub const fn suffix(self, syntax: Syntax) -> &'static str {
+        use LoadAndStoreMultipleAddressingMode::*;
+        match (self, syntax) {
+            (IncrementAfter, Syntax::Unified) => "",
+            (IncrementAfter, _) => "ia",
+            (IncrementBefore, _) => "ib",
+            (DecrementAfter, _) => "da",
+            (DecrementBefore, _) => "db",
+        }
+    }
+}
+///The suffix of LDM and STM.
+impl Display for LoadAndStoreMultipleAddressingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use LoadAndStoreMultipleAddressingMode::*;
+        match self {
+            IncrementAfter => write!(f, "ia"),
+            IncrementBefore => write!(f, "ib"),
+            DecrementAfter => write!(f, "da"),
+            DecrementBefore => write!(f, "db"),
+        }
+    }
+}
+///Addressing Mode 5 - Load and Store Coprocessor. See A5.5.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoprocessorAddressingMode {
+    ///Word offset from the base, the offset in bytes is four times `offset`.
+    Indexed {
+        base: Register,
+        offset: u8,
+        add: bool,
+        indexing: Indexing,
+    },
+    ///The base is used as is, `option` is passed on to the coprocessor.
+    Unindexed { base: Register, option: u8 },
+}
+impl CoprocessorAddressingMode {
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        let (rn, _) = split_with_range(value, 16..=19);
+        let (offset, _) = split_with_range(value, 0..=7);
+        let base = Register::try_from(rn as u8)?;
+        let add = check_bit(value, 23);
+        if !check_bit(value, 24) && !check_bit(value, 21) {
+            // P == 0, W == 0 and U == 0 is the MCRR and MRRC space
+            if !add {
+                return Err(ParseError::Undefined(value));
+            }
+            return Ok(Self::Unindexed {
+                base,
+                option: offset as u8,
+            });
+        }
+        // unlike the other modes post-indexing always has W set
+        let indexing = match (check_bit(value, 24), check_bit(value, 21)) {
+            (true, false) => Indexing::Offset,
+            (true, true) => Indexing::PreIndexed,
+            (false, _) => Indexing::PostIndexed,
+        };
+        Ok(Self::Indexed {
+            base,
+            offset: offset as u8,
+            add,
+            indexing,
+        })
+    }
+    ///Encodes the P, U and W bits, Rn and the offset or option.
+    pub fn encode(&self) -> u32 {
+        match *self {
+            Self::Indexed {
+                base,
+                offset,
+                add,
+                indexing,
+            } => {
+                // post-indexing sets W
+                let indexing = match indexing {
+                    Indexing::PostIndexed => 1 << 21,
+                    indexing => indexing.encode(),
+                };
+                indexing | (add as u32) << 23 | (base as u32) << 16 | offset as u32
+            }
+            Self::Unindexed { base, option } => 1 << 23 | (base as u32) << 16 | option as u32,
+        }
+    }
+}
+impl Display for CoprocessorAddressingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Indexed {
+                base,
+                offset,
+                add,
+                indexing,
+            } => {
+                let offset = (offset != 0 || !add).then(|| {
+                    let sign = if add { "" } else { "-" };
+                    format!("#{sign}{}", offset as u32 * 4)
+                });
+                indexing.fmt_adress(f, base, offset)
+            }
+            Self::Unindexed { base, option } => write!(f, "[{base}], {{{option}}}"),
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Register::*;
+
+    #[test]
+    fn load_and_store_addressing_mode() {
+        // ldr r1, [r2, #-4]!
+        assert_eq!(
+            LoadAndStoreAddressingMode::new(0xe532_1004, false).unwrap(),
+            LoadAndStoreAddressingMode {
+                base: R2,
+                offset: LoadAndStoreOffset::Immediate(4),
+                add: false,
+                indexing: Indexing::PreIndexed,
+            }
+        );
+        // ldr r1, [r2], r3, lsl #2
+        assert_eq!(
+            LoadAndStoreAddressingMode::new(0xe692_1103, true).unwrap(),
+            LoadAndStoreAddressingMode {
+                base: R2,
+                offset: LoadAndStoreOffset::ScaledRegister {
+                    register: R3,
+                    shift: Shift::LSL(2),
+                },
+                add: true,
+                indexing: Indexing::PostIndexed,
+            }
+        );
+        // ldrt r0, [r1], #-8
+        assert_eq!(
+            PostIndexedAddressingMode::new(0xe431_0008, false).unwrap(),
+            PostIndexedAddressingMode {
+                base: R1,
+                offset: LoadAndStoreOffset::Immediate(8),
+                add: false,
+            }
+        );
+    }
+
+    #[test]
+    fn indexing() {
+        assert_eq!(Indexing::new(1 << 24).unwrap(), Indexing::Offset);
+        assert_eq!(
+            Indexing::new(1 << 24 | 1 << 21).unwrap(),
+            Indexing::PreIndexed
+        );
+        assert_eq!(Indexing::new(0).unwrap(), Indexing::PostIndexed);
+        assert!(Indexing::new(1 << 21).is_err());
+    }
+
+    #[test]
+    fn misc_load_and_store_addressing_mode() {
+        // ldrh r0, [r1, #-18]!
+        assert_eq!(
+            MiscLoadAndStoreAddressingMode::new(0xe171_01b2).unwrap(),
+            MiscLoadAndStoreAddressingMode {
+                base: R1,
+                offset: MiscLoadAndStoreOffset::Immediate(0x12),
+                add: false,
+                indexing: Indexing::PreIndexed,
+            }
+        );
+        // strh r0, [r1], #52
+        assert_eq!(
+            MiscLoadAndStoreAddressingMode::new(0xe0c1_03b4).unwrap(),
+            MiscLoadAndStoreAddressingMode {
+                base: R1,
+                offset: MiscLoadAndStoreOffset::Immediate(0x34),
+                add: true,
+                indexing: Indexing::PostIndexed,
+            }
+        );
+        // ldrsb r2, [r3, -r4]
+        assert_eq!(
+            MiscLoadAndStoreAddressingMode::new(0xe113_20d4).unwrap(),
+            MiscLoadAndStoreAddressingMode {
+                base: R3,
+                offset: MiscLoadAndStoreOffset::Register(R4),
+                add: false,
+                indexing: Indexing::Offset,
+            }
+        );
+        // a register offset with bits 8 to 11 set
+        assert!(matches!(
+            MiscLoadAndStoreAddressingMode::new(0xe113_2fd4),
+            Err(ParseError::ShouldBeZero(0xf00))
+        ));
+        // post-indexing with W set
+        assert!(matches!(
+            MiscLoadAndStoreAddressingMode::new(0xe0b3_20f4),
+            Err(ParseError::ShouldBeZero(_))
+        ));
+    }
+
+    #[test]
+    fn load_and_store_multiple_addressing_mode() {
+        use LoadAndStoreMultipleAddressingMode::*;
+        // ldmia, ldmib, ldmda and ldmdb r0, {r1}
+        assert_eq!(
+            LoadAndStoreMultipleAddressingMode::new(0xe890_0002),
+            IncrementAfter
+        );
+        assert_eq!(
+            LoadAndStoreMultipleAddressingMode::new(0xe990_0002),
+            IncrementBefore
+        );
+        assert_eq!(
+            LoadAndStoreMultipleAddressingMode::new(0xe810_0002),
+            DecrementAfter
+        );
+        assert_eq!(
+            LoadAndStoreMultipleAddressingMode::new(0xe910_0002),
+            DecrementBefore
+        );
+    }
+
+    #[test]
+    fn coprocessor_addressing_mode() {
+        let indexed = |offset, add, indexing| CoprocessorAddressingMode::Indexed {
+            base: R3,
+            offset,
+            add,
+            indexing,
+        };
+        // ldc p1, c2, [r3, #-8]!
+        assert_eq!(
+            CoprocessorAddressingMode::new(0xed33_2102).unwrap(),
+            indexed(2, false, Indexing::PreIndexed)
+        );
+        // stc p1, c2, [r3, #-1020]
+        assert_eq!(
+            CoprocessorAddressingMode::new(0xed03_21ff).unwrap(),
+            indexed(0xff, false, Indexing::Offset)
+        );
+        // ldc p1, c2, [r3], #4
+        assert_eq!(
+            CoprocessorAddressingMode::new(0xecb3_2101).unwrap(),
+            indexed(1, true, Indexing::PostIndexed)
+        );
+        // ldc p1, c2, [r3], #-4
+        assert_eq!(
+            CoprocessorAddressingMode::new(0xec33_2101).unwrap(),
+            indexed(1, false, Indexing::PostIndexed)
+        );
+        // ldc p1, c2, [r3], {5}
+        assert_eq!(
+            CoprocessorAddressingMode::new(0xec93_2105).unwrap(),
+            CoprocessorAddressingMode::Unindexed {
+                base: R3,
+                option: 5
+            }
+        );
+        // P, U and W clear
+        assert!(CoprocessorAddressingMode::new(0xec13_2105).is_err());
+    }
+
+    #[test]
+    fn immediate_shifts() {
+        let shift = |word| match ShifterOperand::new(word, false).unwrap() {
+            ShifterOperand::ImmediateShift {
+                register: R1,
+                shift,
+            } => shift,
+            operand => panic!("{word:#010x} decoded as {operand:?}"),
+        };
+        // mov r0, r1, rrx and mov r0, r1, ror #1
+        assert_eq!(shift(0xe1a0_0061), Shift::RRX);
+        assert_eq!(shift(0xe1a0_00e1), Shift::ROR(1));
+        // mov r0, r1, lsr #32 and mov r0, r1, asr #32
+        assert_eq!(shift(0xe1a0_0021), Shift::LSR(32));
+        assert_eq!(shift(0xe1a0_0041), Shift::ASR(32));
+        // mov r0, r1, lsl #31
+        assert_eq!(shift(0xe1a0_0f81), Shift::LSL(31));
+        // mov r0, r1 is a shift by LSL #0
+        assert_eq!(
+            ShifterOperand::new(0xe1a0_0001, false).unwrap(),
+            ShifterOperand::Register(R1)
+        );
+    }
+
+    #[test]
+    fn register_shifts() {
+        // add r0, r1, r2, ror r3
+        assert_eq!(
+            ShifterOperand::new(0xe081_0372, false).unwrap(),
+            ShifterOperand::RegisterShift {
+                register: R2,
+                shift: ShiftType::ROR,
+                amount: R3,
+            }
+        );
+        // mov r0, r1, lsl r2
+        assert_eq!(
+            ShifterOperand::new(0xe1a0_0211, false).unwrap(),
+            ShifterOperand::RegisterShift {
+                register: R1,
+                shift: ShiftType::LSL,
+                amount: R2,
+            }
+        );
+        // bit 7 set is the multiply and extra load/store space
+        assert!(ShifterOperand::new(0xe000_0091, false).is_err());
+    }
+
+    #[test]
+    fn rotated_immediate() {
+        // mov r0, #0xff000000
+        let immediate = match ShifterOperand::new(0xe3a0_04ff, true).unwrap() {
+            ShifterOperand::Immediate(immediate) => immediate,
+            operand => panic!("decoded as {operand:?}"),
+        };
+        assert_eq!(
+            immediate,
+            RotatedImmediate {
+                immediate: 0xff,
+                rotate: 4
+            }
+        );
+        assert_eq!(immediate.value(), 0xff00_0000);
+        assert!(immediate.carry_out(false));
+        // without a rotation the carry is left as it is
+        let one = RotatedImmediate::new(0xe3a0_0001);
+        assert_eq!(one.value(), 1);
+        assert!(one.carry_out(true));
+        assert!(!one.carry_out(false));
+        // the same value with another rotation carries out bit 31
+        let rotated = RotatedImmediate {
+            immediate: 4,
+            rotate: 1,
+        };
+        assert_eq!(rotated.value(), 1);
+        assert!(!rotated.carry_out(true));
+        assert_eq!(RotatedImmediate::from_value(1), Some(one));
+        assert_eq!(RotatedImmediate::from_value(0x101), None);
+    }
 }
@@ -1,24 +1,32 @@
-use crate::instructions::Register;
+use std::fmt::{self, Display};
+
+use crate::errors::{EncodeError, ParseError};
+use crate::instructions::{
+    fmt_assembly, should_be_one, should_be_zero, split_with_range, Architecture, Assembly,
+    Condition, Register, Syntax,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AritmeticInstruction {
-    ///Dual 16-bit signed saturating addition. See QADD16 on page A4-94.
+    ///Saturating Add. See QADD on page A4-92.
     QADD {
         destination: Register,
         first_operand: Register,
         second_operand: Register,
     },
+    ///Saturating Double and Add. See QDADD on page A4-100.
     QDADD {
         destination: Register,
         first_operand: Register,
         second_operand: Register,
     },
+    ///Saturating Double and Subtract. See QDSUB on page A4-102.
     QDSUB {
         destination: Register,
         first_operand: Register,
         second_operand: Register,
     },
-    ///Dual 16-bit signed saturating addition. See QADD16 on page A4-94.
+    ///Saturating Subtract. See QSUB on page A4-104.
     QSUB {
         destination: Register,
         first_operand: Register,
@@ -30,3 +38,203 @@ pub enum AritmeticInstruction {
         source: Register,
     },
 }
+impl AritmeticInstruction {
+    ///Decodes the saturating instructions, the first operand is Rm and the second Rn.
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        should_be_zero(value, 0b1111 << 8)?;
+        let (op, value) = split_with_range(value, 21..=22);
+        let (rn, value) = split_with_range(value, 16..=19);
+        let (rd, value) = split_with_range(value, 12..=15);
+        let (rm, _) = split_with_range(value, 0..=3);
+        let destination = Register::try_from(rd as u8)?;
+        let first_operand = Register::try_from(rm as u8)?;
+        let second_operand = Register::try_from(rn as u8)?;
+        use AritmeticInstruction::*;
+        Ok(match op {
+            0b00 => QADD {
+                destination,
+                first_operand,
+                second_operand,
+            },
+            0b01 => QSUB {
+                destination,
+                first_operand,
+                second_operand,
+            },
+            0b10 => QDADD {
+                destination,
+                first_operand,
+                second_operand,
+            },
+            _ => QDSUB {
+                destination,
+                first_operand,
+                second_operand,
+            },
+        })
+    }
+    pub fn new_clz(value: u32) -> Result<Self, ParseError> {
+        should_be_one(value, 0b1111 << 16 | 0b1111 << 8)?;
+        let (rd, value) = split_with_range(value, 12..=15);
+        let (rm, _) = split_with_range(value, 0..=3);
+        Ok(Self::CLZ {
+            destination: Register::try_from(rd as u8)?,
+            source: Register::try_from(rm as u8)?,
+        })
+    }
+    ///Encodes the instruction without the condition.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        use AritmeticInstruction::*;
+        Ok(match *self {
+            QADD {
+                destination,
+                first_operand,
+                second_operand,
+            }
+            | QSUB {
+                destination,
+                first_operand,
+                second_operand,
+            }
+            | QDADD {
+                destination,
+                first_operand,
+                second_operand,
+            }
+            | QDSUB {
+                destination,
+                first_operand,
+                second_operand,
+            } => {
+                let op = match self {
+                    QADD { .. } => 0b00,
+                    QSUB { .. } => 0b01,
+                    QDADD { .. } => 0b10,
+                    _ => 0b11,
+                };
+                0b0001_0000 << 20
+                    | op << 21
+                    | (second_operand as u32) << 16
+                    | (destination as u32) << 12
+                    | 0b0101 << 4
+                    | first_operand as u32
+            }
+            CLZ {
+                destination,
+                source,
+            } => {
+                0b0001_0110 << 20
+                    | 0b1111 << 16
+                    | (destination as u32) << 12
+                    | 0b1111 << 8
+                    | 0b0001 << 4
+                    | source as u32
+            }
+        })
+    }
+    ///The PC as any of the registers is unpredictable.
+    pub fn is_unpredictable(&self) -> bool {
+        use AritmeticInstruction::*;
+        match *self {
+            QADD {
+                destination,
+                first_operand,
+                second_operand,
+            }
+            | QDADD {
+                destination,
+                first_operand,
+                second_operand,
+            }
+            | QDSUB {
+                destination,
+                first_operand,
+                second_operand,
+            }
+            | QSUB {
+                destination,
+                first_operand,
+                second_operand,
+            } => [destination, first_operand, second_operand].contains(&Register::R15),
+            CLZ {
+                destination,
+                source,
+            } => destination == Register::R15 || source == Register::R15,
+        }
+    }
+    ///CLZ is ARMv5 and the saturating instructions are part of the DSP extension.
+    pub fn architecture(&self) -> Architecture {
+        Architecture::ARMv5TE
+    }
+}
+impl Assembly for AritmeticInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        use AritmeticInstruction::*;
+        let base = match self {
+            QADD { .. } => "qadd",
+            QDADD { .. } => "qdadd",
+            QDSUB { .. } => "qdsub",
+            QSUB { .. } => "qsub",
+            CLZ { .. } => "clz",
+        };
+        syntax.conditional(base, cond, "")
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        use AritmeticInstruction::*;
+        match self {
+            QADD {
+                destination,
+                first_operand,
+                second_operand,
+            }
+            | QDADD {
+                destination,
+                first_operand,
+                second_operand,
+            }
+            | QDSUB {
+                destination,
+                first_operand,
+                second_operand,
+            }
+            | QSUB {
+                destination,
+                first_operand,
+                second_operand,
+            } => format!("{destination}, {first_operand}, {second_operand}"),
+            CLZ {
+                destination,
+                source,
+            } => format!("{destination}, {source}"),
+        }
+    }
+}
+impl Display for AritmeticInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::instructions::arm::tests::violations;
+    use crate::instructions::Violations;
+
+    #[test]
+    fn saturating_arithmetic_on_the_pc() {
+        // qadd r0, r1, r2
+        assert_eq!(violations(0xe102_0051), Violations::empty());
+        // qadd pc, r1, r2, qsub r0, pc, r2, qdadd r0, r1, pc and qdsub pc, r1, r2
+        for word in [0xe102_f051, 0xe122_005f, 0xe14f_0051, 0xe162_f051] {
+            assert_eq!(violations(word), Violations::UNPREDICTABLE, "{word:#010x}");
+        }
+    }
+
+    #[test]
+    fn count_leading_zeros_on_the_pc() {
+        // clz r0, r1
+        assert_eq!(violations(0xe16f_0f11), Violations::empty());
+        // clz pc, r1 and clz r0, pc
+        assert_eq!(violations(0xe16f_ff11), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0xe16f_0f1f), Violations::UNPREDICTABLE);
+    }
+}
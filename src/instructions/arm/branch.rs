@@ -1,5 +1,10 @@
-use crate::instructions::{Register, RelativeAdress};
+use std::fmt::{self, Display};
 
+use crate::errors::{EncodeError, ParseError};
+use crate::instructions::{
+    check_bit, fmt_assembly, should_be_one, split_with_range, Adress, Architecture, Assembly,
+    Condition, Register, RelativeAdress, Syntax,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BranchInstruction {
@@ -12,3 +17,133 @@ pub enum BranchInstruction {
     ///Branch and Exchange Instruction Set. See BX on page A4-20.
     BX(Register),
 }
+impl BranchInstruction {
+    ///Decodes B and BL, the 24 bit offset is sign extended and converted to bytes.
+    pub fn new(value: u32) -> Self {
+        let (offset, _) = split_with_range(value, 0..=23);
+        let offset = RelativeAdress::from_word_offset(offset, 24);
+        match check_bit(value, 24) {
+            true => Self::BL(offset),
+            false => Self::B(offset),
+        }
+    }
+    ///Decodes BX and BLX (2), bit 5 selects the link.
+    pub fn new_exchange(value: u32) -> Result<Self, ParseError> {
+        should_be_one(value, 0b1111_1111_1111 << 8)?;
+        let (rm, _) = split_with_range(value, 0..=3);
+        let rm = Register::try_from(rm as u8)?;
+        Ok(match check_bit(value, 5) {
+            true => Self::BLX(rm),
+            false => Self::BX(rm),
+        })
+    }
+    ///Encodes the instruction without the condition.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        Ok(match self {
+            Self::B(offset) => 0b101 << 25 | offset.to_word_offset(24)?,
+            Self::BL(offset) => 0b1011 << 24 | offset.to_word_offset(24)?,
+            Self::BLX(register) => 0x12f_ff30 | *register as u32,
+            Self::BX(register) => 0x12f_ff10 | *register as u32,
+        })
+    }
+    ///Resolves the target of B and BL for the instruction at `adress`, taking the PC being 8
+    ///bytes ahead into account. Register branches have no static target.
+    pub fn target(&self, adress: Adress) -> Option<Adress> {
+        match self {
+            Self::B(offset) | Self::BL(offset) => Some(offset.resolve(adress.arm_pc())),
+            Self::BLX(_) | Self::BX(_) => None,
+        }
+    }
+    ///BLX (2) can't branch to the PC.
+    pub fn is_unpredictable(&self) -> bool {
+        matches!(self, Self::BLX(Register::R15))
+    }
+    ///BX is ARMv4T, BLX ARMv5.
+    pub fn architecture(&self) -> Architecture {
+        match self {
+            Self::BLX(_) => Architecture::ARMv5TE,
+            _ => Architecture::ARMv4T,
+        }
+    }
+}
+impl Assembly for BranchInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        let base = match self {
+            Self::B(_) => "b",
+            Self::BL(_) => "bl",
+            Self::BLX(_) => "blx",
+            Self::BX(_) => "bx",
+        };
+        syntax.conditional(base, cond, "")
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        match self {
+            Self::B(offset) | Self::BL(offset) => offset.to_string(),
+            Self::BLX(register) | Self::BX(register) => register.to_string(),
+        }
+    }
+}
+impl Display for BranchInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::arm::tests::op;
+    use crate::instructions::arm::PartialArmInstruction::Branch;
+    use crate::instructions::arm::{ArmInstruction, PartialArmInstruction};
+    use crate::instructions::Violations;
+    use BranchInstruction::*;
+
+    fn target(word: u32, adress: u32) -> Option<Adress> {
+        ArmInstruction::try_from(word)
+            .unwrap()
+            .target(Adress::new(adress))
+    }
+
+    #[test]
+    fn branch_targets() {
+        // b . at 0x1000
+        assert_eq!(op(0xeaff_fffe), Some(Branch(B(RelativeAdress::new(-8)))));
+        assert_eq!(target(0xeaff_fffe, 0x1000), Some(Adress::new(0x1000)));
+        // bl 0x100 at 0
+        assert_eq!(op(0xeb00_003e), Some(Branch(BL(RelativeAdress::new(0xf8)))));
+        assert_eq!(target(0xeb00_003e, 0), Some(Adress::new(0x100)));
+        // b 0x1000 at 0x2000
+        assert_eq!(
+            op(0xeaff_fbfe),
+            Some(Branch(B(RelativeAdress::new(-0x1008))))
+        );
+        assert_eq!(target(0xeaff_fbfe, 0x2000), Some(Adress::new(0x1000)));
+        // b 0xfffffff8 at 0 wraps around
+        assert_eq!(target(0xeaff_fffc, 0), Some(Adress::new(0xffff_fff8)));
+        // the largest offsets
+        assert_eq!(
+            op(0xea7f_ffff),
+            Some(Branch(B(RelativeAdress::new(0x1ff_fffc))))
+        );
+        assert_eq!(
+            op(0xea80_0000),
+            Some(Branch(B(RelativeAdress::new(-0x200_0000))))
+        );
+    }
+
+    #[test]
+    fn exchange() {
+        // bx lr
+        assert_eq!(op(0xe12f_ff1e), Some(Branch(BX(Register::R14))));
+        assert_eq!(target(0xe12f_ff1e, 0), None);
+        // blx r3
+        assert_eq!(op(0xe12f_ff33), Some(Branch(BLX(Register::R3))));
+        // bx lr with a should be one bit cleared
+        assert!(ArmInstruction::try_from(0xe12f_0f1e).is_err());
+        let decoded = ArmInstruction::decode(0xe12f_0f1e, Architecture::ARMv5TE);
+        assert_eq!(
+            decoded.instruction.op,
+            PartialArmInstruction::Branch(BX(Register::R14))
+        );
+        assert_eq!(decoded.violations, Violations::SHOULD_BE_ONE);
+    }
+}
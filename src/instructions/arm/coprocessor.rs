@@ -1,9 +1,12 @@
-use crate::errors::ParseError;
+use std::fmt::{self, Display};
+
+use crate::errors::{EncodeError, ParseError};
 use crate::instructions::{
-    check_bit, split_with_mask, split_with_range, CRegister, Coprocessor, Register,
+    check_bit, encode_field, fmt_assembly, split_with_range, Architecture, Assembly, CRegister,
+    Condition, Coprocessor, Register, Syntax,
 };
 
-use super::AddressingMode;
+use super::adresssing::{CoprocessorAddressingMode, Indexing};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CoprocessorInstruction {
@@ -23,6 +26,7 @@ pub enum CoprocessorInstruction {
     STC(STC),
 }
 impl CoprocessorInstruction {
+    ///Decodes the data operations and register transfers, which have bits 24 to 27 set to `1110`.
     pub fn new(value: u32, is_transfer: bool) -> Result<CoprocessorInstruction, ParseError> {
         Ok(match is_transfer {
             // checks bit 4
@@ -33,56 +37,306 @@ impl CoprocessorInstruction {
                     false => Self::MCR(MCR::new(value)?),
                 }
             }
-            false => {
-                todo!("coprocessor {:032b}, {}", value, is_transfer);
+            false => Self::CDP(CDP::new(value)?),
+        })
+    }
+    ///Decodes the loads and stores and the two register transfers, which have bits 25 to 27 set
+    ///to `110`.
+    pub fn new_load_store(value: u32) -> Result<CoprocessorInstruction, ParseError> {
+        let (switch, _) = split_with_range(value, 21..=24);
+        // P, U and W clear with N set is the two register transfer space
+        if switch == 0b0010 {
+            return Ok(match check_bit(value, 20) {
+                true => Self::MRRC(MRRC::new(value)?),
+                false => Self::MCRR(MCRR::new(value)?),
+            });
+        }
+        Ok(match check_bit(value, 20) {
+            true => Self::LDC(LDC::new(value)?),
+            false => Self::STC(STC::new(value)?),
+        })
+    }
+    ///Encodes the instruction without the condition, which is the same for the unconditional
+    ///forms.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        Ok(match *self {
+            Self::CDP(inst) => {
+                0b1110 << 24
+                    | encode_field(inst.opcode_1 as u32, 4, "opcode_1")? << 20
+                    | (inst.first_operand as u32) << 16
+                    | (inst.destination as u32) << 12
+                    | (inst.coprocessor as u32) << 8
+                    | encode_field(inst.opcode_2 as u32, 3, "opcode_2")? << 5
+                    | inst.second_operand as u32
+            }
+            Self::LDC(LDC {
+                coprocessor,
+                long_load: long,
+                destination: register,
+                addressing_mode,
+            })
+            | Self::STC(STC {
+                coprocessor,
+                long_store: long,
+                source: register,
+                addressing_mode,
+            }) => {
+                let load = matches!(self, Self::LDC(_));
+                0b110 << 25
+                    | (long as u32) << 22
+                    | (load as u32) << 20
+                    | (register as u32) << 12
+                    | (coprocessor as u32) << 8
+                    | addressing_mode.encode()
+            }
+            Self::MCR(MCR {
+                coprocessor,
+                value,
+                destination,
+                additional_destination,
+                opcode_1,
+                opcode_2,
+            })
+            | Self::MRC(MRC {
+                coprocessor,
+                value,
+                destination,
+                additional_destination,
+                opcode_1,
+                opcode_2,
+            }) => {
+                let load = matches!(self, Self::MRC(_));
+                0b1110 << 24
+                    | encode_field(opcode_1 as u32, 3, "opcode_1")? << 21
+                    | (load as u32) << 20
+                    | (destination as u32) << 16
+                    | (value as u32) << 12
+                    | (coprocessor as u32) << 8
+                    | encode_field(opcode_2 as u32, 3, "opcode_2")? << 5
+                    | 1 << 4
+                    | additional_destination as u32
+            }
+            Self::MCRR(MCRR {
+                coprocessor,
+                opcode,
+                first_register,
+                second_register,
+                destination,
+            })
+            | Self::MRRC(MRRC {
+                coprocessor,
+                opcode,
+                first_register,
+                second_register,
+                destination,
+            }) => {
+                let load = matches!(self, Self::MRRC(_));
+                0b110_0010 << 21
+                    | (load as u32) << 20
+                    | (second_register as u32) << 16
+                    | (first_register as u32) << 12
+                    | (coprocessor as u32) << 8
+                    | encode_field(opcode as u32, 4, "opcode")? << 4
+                    | destination as u32
             }
         })
     }
+    ///The two register transfers can't use the PC, and MRRC can't load both values into the
+    ///same register. LDC and STC can't write back to the PC.
+    pub fn is_unpredictable(&self) -> bool {
+        match self {
+            Self::MCRR(MCRR {
+                first_register,
+                second_register,
+                ..
+            }) => *first_register == Register::R15 || *second_register == Register::R15,
+            Self::MRRC(MRRC {
+                first_register,
+                second_register,
+                ..
+            }) => {
+                *first_register == Register::R15
+                    || *second_register == Register::R15
+                    || first_register == second_register
+            }
+            Self::LDC(LDC {
+                addressing_mode, ..
+            })
+            | Self::STC(STC {
+                addressing_mode, ..
+            }) => matches!(
+                addressing_mode,
+                CoprocessorAddressingMode::Indexed {
+                    base: Register::R15,
+                    indexing: Indexing::PreIndexed | Indexing::PostIndexed,
+                    ..
+                }
+            ),
+            _ => false,
+        }
+    }
+    ///The two register transfers are part of the DSP extension.
+    pub fn architecture(&self) -> Architecture {
+        match self {
+            Self::MCRR(_) | Self::MRRC(_) => Architecture::ARMv5TE,
+            _ => Architecture::ARMv4T,
+        }
+    }
+    ///The mnemonic with `version` after the base, which is `2` for the unconditional forms
+    ///like `ldc2l`.
+    pub fn versioned_mnemonic(&self, cond: Condition, syntax: Syntax, version: &str) -> String {
+        let (base, suffix) = match self {
+            Self::CDP(_) => ("cdp", ""),
+            Self::LDC(inst) => ("ldc", if inst.long_load { "l" } else { "" }),
+            Self::MCR(_) => ("mcr", ""),
+            Self::MCRR(_) => ("mcrr", ""),
+            Self::MRC(_) => ("mrc", ""),
+            Self::MRRC(_) => ("mrrc", ""),
+            Self::STC(inst) => ("stc", if inst.long_store { "l" } else { "" }),
+        };
+        syntax.conditional(&format!("{base}{version}"), cond, suffix)
+    }
+}
+impl Assembly for CoprocessorInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        self.versioned_mnemonic(cond, syntax, "")
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        match self {
+            Self::CDP(inst) => format!(
+                "{}, {}, {}, {}, {}, {}",
+                inst.coprocessor,
+                inst.opcode_1,
+                inst.destination,
+                inst.first_operand,
+                inst.second_operand,
+                inst.opcode_2
+            ),
+            Self::LDC(inst) => format!(
+                "{}, {}, {}",
+                inst.coprocessor, inst.destination, inst.addressing_mode
+            ),
+            // MRC to the PC sets the flags, which UAL writes as the flags of the APSR
+            Self::MRC(MRC {
+                coprocessor,
+                value: Register::R15,
+                destination,
+                additional_destination,
+                opcode_1,
+                opcode_2,
+            }) if syntax == Syntax::Unified => format!(
+                "{coprocessor}, {opcode_1}, apsr_nzcv, {destination}, {additional_destination}, {opcode_2}"
+            ),
+            Self::MCR(MCR {
+                coprocessor,
+                value,
+                destination,
+                additional_destination,
+                opcode_1,
+                opcode_2,
+            })
+            | Self::MRC(MRC {
+                coprocessor,
+                value,
+                destination,
+                additional_destination,
+                opcode_1,
+                opcode_2,
+            }) => format!(
+                "{coprocessor}, {opcode_1}, {value}, {destination}, {additional_destination}, {opcode_2}"
+            ),
+            Self::MCRR(MCRR {
+                coprocessor,
+                opcode,
+                first_register,
+                second_register,
+                destination,
+            })
+            | Self::MRRC(MRRC {
+                coprocessor,
+                opcode,
+                first_register,
+                second_register,
+                destination,
+            }) => format!(
+                "{coprocessor}, {opcode}, {first_register}, {second_register}, {destination}"
+            ),
+            Self::STC(inst) => format!(
+                "{}, {}, {}",
+                inst.coprocessor, inst.source, inst.addressing_mode
+            ),
+        }
+    }
+}
+impl Display for CoprocessorInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CDP {
-    coprocessor: Coprocessor,
-    destination: CRegister,
-    first_operand: CRegister,
-    second_operand: CRegister,
-    opcode_1: u8,
-    opcode_2: u8,
+    pub coprocessor: Coprocessor,
+    pub destination: CRegister,
+    pub first_operand: CRegister,
+    pub second_operand: CRegister,
+    pub opcode_1: u8,
+    pub opcode_2: u8,
 }
 impl CDP {
-    fn new(value: u32) -> Result<Self, ParseError> {
-        todo!("CDP")
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        let (opcode_1, value) = split_with_range(value, 20..=23);
+        let (crn, value) = split_with_range(value, 16..=19);
+        let (crd, value) = split_with_range(value, 12..=15);
+        let (coproc, value) = split_with_range(value, 8..=11);
+        let (opcode_2, value) = split_with_range(value, 5..=7);
+        let (crm, _) = split_with_range(value, 0..=3);
+        Ok(CDP {
+            coprocessor: Coprocessor::try_from(coproc as u8)?,
+            destination: CRegister::try_from(crd as u8)?,
+            first_operand: CRegister::try_from(crn as u8)?,
+            second_operand: CRegister::try_from(crm as u8)?,
+            opcode_1: opcode_1 as u8,
+            opcode_2: opcode_2 as u8,
+        })
     }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LDC {
-    coprocessor: Coprocessor,
-    long_load: bool,
-    destination: CRegister,
-    addressing_mode: AddressingMode,
+    pub coprocessor: Coprocessor,
+    pub long_load: bool,
+    pub destination: CRegister,
+    pub addressing_mode: CoprocessorAddressingMode,
 }
 impl LDC {
-    fn new(value: u32) -> Result<Self, ParseError> {
-        todo!("LDC")
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        let (crd, _) = split_with_range(value, 12..=15);
+        let (coproc, _) = split_with_range(value, 8..=11);
+        Ok(LDC {
+            coprocessor: Coprocessor::try_from(coproc as u8)?,
+            long_load: check_bit(value, 22),
+            destination: CRegister::try_from(crd as u8)?,
+            addressing_mode: CoprocessorAddressingMode::new(value)?,
+        })
     }
 }
 ///Move to Coprocessor from ARM Register. See MCR on page A4-62.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MCR {
-    coprocessor: Coprocessor,
-    value: Register,
-    destination: CRegister,
-    additional_destination: CRegister,
-    opcode_1: u8,
-    opcode_2: u8,
+    pub coprocessor: Coprocessor,
+    pub value: Register,
+    pub destination: CRegister,
+    pub additional_destination: CRegister,
+    pub opcode_1: u8,
+    pub opcode_2: u8,
 }
 impl MCR {
-    fn new(value: u32) -> Result<Self, ParseError> {
+    pub fn new(value: u32) -> Result<Self, ParseError> {
         let (opcode_1, value) = split_with_range(value, 21..=23);
         let (opcode_2, value) = split_with_range(value, 5..=7);
         let (creg_dest, value) = split_with_range(value, 16..=19);
         let (val_reg, value) = split_with_range(value, 12..=15);
         let (coproc, value) = split_with_range(value, 8..=11);
-        let (creg_dest_extra, value) = split_with_range(value, 8..=11);
+        let (creg_dest_extra, value) = split_with_range(value, 0..=3);
         Ok(MCR {
             coprocessor: Coprocessor::try_from(coproc as u8)?,
             value: Register::try_from(val_reg as u8)?,
@@ -96,35 +350,46 @@ impl MCR {
 ///Move to Coprocessor from two ARM Registers. See MCRR on page A4-64.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MCRR {
-    coprocessor: Coprocessor,
-    opcode: u8,
-    first_register: Register,
-    second_register: Register,
-    destination: CRegister,
+    pub coprocessor: Coprocessor,
+    pub opcode: u8,
+    pub first_register: Register,
+    pub second_register: Register,
+    pub destination: CRegister,
 }
 impl MCRR {
-    fn new(value: u32) -> Result<Self, ParseError> {
-        todo!("MCRR")
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        let (rn, value) = split_with_range(value, 16..=19);
+        let (rd, value) = split_with_range(value, 12..=15);
+        let (coproc, value) = split_with_range(value, 8..=11);
+        let (opcode, value) = split_with_range(value, 4..=7);
+        let (crm, _) = split_with_range(value, 0..=3);
+        Ok(MCRR {
+            coprocessor: Coprocessor::try_from(coproc as u8)?,
+            opcode: opcode as u8,
+            first_register: Register::try_from(rd as u8)?,
+            second_register: Register::try_from(rn as u8)?,
+            destination: CRegister::try_from(crm as u8)?,
+        })
     }
 }
 ///Move to ARM Register from Coprocessor. See MRC on page A4-70.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MRC {
-    coprocessor: Coprocessor,
-    value: Register,
-    destination: CRegister,
-    additional_destination: CRegister,
-    opcode_1: u8,
-    opcode_2: u8,
+    pub coprocessor: Coprocessor,
+    pub value: Register,
+    pub destination: CRegister,
+    pub additional_destination: CRegister,
+    pub opcode_1: u8,
+    pub opcode_2: u8,
 }
 impl MRC {
-    fn new(value: u32) -> Result<Self, ParseError> {
+    pub fn new(value: u32) -> Result<Self, ParseError> {
         let (opcode_1, value) = split_with_range(value, 21..=23);
         let (opcode_2, value) = split_with_range(value, 5..=7);
         let (creg_dest, value) = split_with_range(value, 16..=19);
         let (val_reg, value) = split_with_range(value, 12..=15);
         let (coproc, value) = split_with_range(value, 8..=11);
-        let (creg_dest_extra, value) = split_with_range(value, 8..=11);
+        let (creg_dest_extra, value) = split_with_range(value, 0..=3);
         Ok(MRC {
             coprocessor: Coprocessor::try_from(coproc as u8)?,
             value: Register::try_from(val_reg as u8)?,
@@ -138,27 +403,204 @@ impl MRC {
 ///Move to two ARM Registers from Coprocessor. See MRRC on page A4-72.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MRRC {
-    coprocessor: Coprocessor,
-    opcode: u8,
-    first_register: Register,
-    second_register: Register,
-    destination: CRegister,
+    pub coprocessor: Coprocessor,
+    pub opcode: u8,
+    pub first_register: Register,
+    pub second_register: Register,
+    pub destination: CRegister,
 }
 impl MRRC {
-    fn new(value: u32) -> Result<Self, ParseError> {
-        todo!("MRRC")
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        let (rn, value) = split_with_range(value, 16..=19);
+        let (rd, value) = split_with_range(value, 12..=15);
+        let (coproc, value) = split_with_range(value, 8..=11);
+        let (opcode, value) = split_with_range(value, 4..=7);
+        let (crm, _) = split_with_range(value, 0..=3);
+        Ok(MRRC {
+            coprocessor: Coprocessor::try_from(coproc as u8)?,
+            opcode: opcode as u8,
+            first_register: Register::try_from(rd as u8)?,
+            second_register: Register::try_from(rn as u8)?,
+            destination: CRegister::try_from(crm as u8)?,
+        })
     }
 }
 ///Store Coprocessor Register. See STC on page A4-186.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct STC {
-    l: bool,
-    coprocessor: Coprocessor,
-    soruce: CRegister,
-    addressing_mode: AddressingMode,
+    pub long_store: bool,
+    pub coprocessor: Coprocessor,
+    pub source: CRegister,
+    pub addressing_mode: CoprocessorAddressingMode,
 }
 impl STC {
-    fn new(value: u32) -> Result<Self, ParseError> {
-        todo!("STC")
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        let (crd, _) = split_with_range(value, 12..=15);
+        let (coproc, _) = split_with_range(value, 8..=11);
+        Ok(STC {
+            long_store: check_bit(value, 22),
+            coprocessor: Coprocessor::try_from(coproc as u8)?,
+            source: CRegister::try_from(crd as u8)?,
+            addressing_mode: CoprocessorAddressingMode::new(value)?,
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::arm::tests::{op, violations};
+    use crate::instructions::arm::{ArmInstruction, PartialArmInstruction};
+    use crate::instructions::Violations;
+    use CRegister::*;
+    use Coprocessor::*;
+    use Register::*;
+
+    #[test]
+    fn register_transfers() {
+        // mcr p15, 0, r0, c7, c10, 4 keeps CRm apart from opcode_2
+        assert_eq!(
+            op(0xee07_0f9a),
+            Some(PartialArmInstruction::Coprocessor(
+                CoprocessorInstruction::MCR(MCR {
+                    coprocessor: P15,
+                    value: R0,
+                    destination: Cr7,
+                    additional_destination: Cr10,
+                    opcode_1: 0,
+                    opcode_2: 4
+                })
+            ))
+        );
+        // mrc p15, 1, r2, c3, c5, 7
+        assert_eq!(
+            op(0xee33_2ff5),
+            Some(PartialArmInstruction::Coprocessor(
+                CoprocessorInstruction::MRC(MRC {
+                    coprocessor: P15,
+                    value: R2,
+                    destination: Cr3,
+                    additional_destination: Cr5,
+                    opcode_1: 1,
+                    opcode_2: 7
+                })
+            ))
+        );
+        assert_eq!(
+            ArmInstruction::try_from(0xee07_0f9a).unwrap().to_string(),
+            "mcr p15, 0, r0, c7, c10, 4"
+        );
+        // mcrr p5, 2, r0, r1, c6
+        assert_eq!(
+            op(0xec41_0526),
+            Some(PartialArmInstruction::Coprocessor(
+                CoprocessorInstruction::MCRR(MCRR {
+                    coprocessor: P5,
+                    opcode: 2,
+                    first_register: R0,
+                    second_register: R1,
+                    destination: Cr6
+                })
+            ))
+        );
+        // mrrc p5, 3, r2, r3, c7
+        assert_eq!(
+            op(0xec53_2537),
+            Some(PartialArmInstruction::Coprocessor(
+                CoprocessorInstruction::MRRC(MRRC {
+                    coprocessor: P5,
+                    opcode: 3,
+                    first_register: R2,
+                    second_register: R3,
+                    destination: Cr7
+                })
+            ))
+        );
+    }
+
+    #[test]
+    fn data_operations_and_transfers() {
+        // cdp p2, 3, c4, c5, c6, 7
+        assert_eq!(
+            op(0xee35_42e6),
+            Some(PartialArmInstruction::Coprocessor(
+                CoprocessorInstruction::CDP(CDP {
+                    coprocessor: P2,
+                    destination: Cr4,
+                    first_operand: Cr5,
+                    second_operand: Cr6,
+                    opcode_1: 3,
+                    opcode_2: 7
+                })
+            ))
+        );
+        // ldc p1, c2, [r3, #-8]!
+        assert_eq!(
+            op(0xed33_2102),
+            Some(PartialArmInstruction::Coprocessor(
+                CoprocessorInstruction::LDC(LDC {
+                    coprocessor: P1,
+                    long_load: false,
+                    destination: Cr2,
+                    addressing_mode: CoprocessorAddressingMode::Indexed {
+                        base: R3,
+                        offset: 2,
+                        add: false,
+                        indexing: Indexing::PreIndexed
+                    }
+                })
+            ))
+        );
+        // stcl p14, c5, [r6], #16
+        assert_eq!(
+            op(0xece6_5e04),
+            Some(PartialArmInstruction::Coprocessor(
+                CoprocessorInstruction::STC(STC {
+                    long_store: true,
+                    coprocessor: P14,
+                    source: Cr5,
+                    addressing_mode: CoprocessorAddressingMode::Indexed {
+                        base: R6,
+                        offset: 4,
+                        add: true,
+                        indexing: Indexing::PostIndexed
+                    }
+                })
+            ))
+        );
+        // ldc p6, c0, [r1], {5}
+        assert_eq!(
+            op(0xec91_0605),
+            Some(PartialArmInstruction::Coprocessor(
+                CoprocessorInstruction::LDC(LDC {
+                    coprocessor: P6,
+                    long_load: false,
+                    destination: Cr0,
+                    addressing_mode: CoprocessorAddressingMode::Unindexed {
+                        base: R1,
+                        option: 5
+                    }
+                })
+            ))
+        );
+    }
+
+    #[test]
+    fn transfer_restrictions() {
+        assert_eq!(violations(0xec41_0526), Violations::empty());
+        // mcrr p5, 2, pc, r1, c6
+        assert_eq!(violations(0xec41_f526), Violations::UNPREDICTABLE);
+        // mrrc p5, 3, r2, r2, c7
+        assert_eq!(violations(0xec52_2537), Violations::UNPREDICTABLE);
+        // ldc p1, c2, [pc, #4] and with writeback, stc p1, c2, [pc], #4 and ldc2 p1, c2, [pc, #4]!
+        assert_eq!(violations(0xed9f_2101), Violations::empty());
+        assert_eq!(violations(0xedbf_2101), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0xecaf_2101), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0xfdbf_2101), Violations::UNPREDICTABLE);
+        // mrrc is part of the DSP extension
+        let decoded = ArmInstruction::decode(0xec53_2537, Architecture::ARMv4T);
+        assert!(matches!(
+            decoded.instruction.op,
+            PartialArmInstruction::Undefined(_)
+        ));
     }
 }
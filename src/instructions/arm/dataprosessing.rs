@@ -1,5 +1,10 @@
-use crate::errors::ParseError;
-use crate::instructions::{split_with_mask, Register, ShifterOperand};
+use std::fmt::{self, Display};
+
+use super::adresssing::ShifterOperand;
+use crate::errors::{EncodeError, ParseError};
+use crate::instructions::{
+    check_bit, fmt_assembly, split_with_range, Assembly, Condition, Register, Syntax,
+};
 pub enum Test {
     TEST,
     TEST2,
@@ -40,68 +45,438 @@ pub enum DataProssessingInstruction {
     TST(NoDestinationDataInstruction),
 }
 impl DataProssessingInstruction {
-    pub fn new(value: u32, s: bool) -> Result<Self, ParseError> {
-        let (opcode, rest) = split_with_mask(value, 0b1111 << 20);
-        let (rn, rest) = split_with_mask(rest, 0b1111 << 15);
-        let rn = ((rn >> 16) as u8).try_into()?;
+    ///Decodes a data-processing instruction, `immediate` is the I bit.
+    pub fn new(value: u32, immediate: bool) -> Result<Self, ParseError> {
+        let (opcode, rest) = split_with_range(value, 21..=24);
+        let s = check_bit(value, 20);
+        let shifter = ShifterOperand::new(value, immediate)?;
         use DataProssessingInstruction::*;
-        Ok(match opcode >> 20 {
-            0b0101 => ADC(GenericDataInstruction::new(rest, rn, s)?),
-            0b0100 => ADD(GenericDataInstruction::new(rest, rn, s)?),
-            0b0000 => AND(GenericDataInstruction::new(rest, rn, s)?),
-            0b1110 => BIC(GenericDataInstruction::new(rest, rn, s)?),
-            0b1010 => CMP(NoDestinationDataInstruction::new(rest, rn, s)?),
-            0b1110 => BIC(GenericDataInstruction::new(rest, rn, s)?),
-
-            bin => todo!("unimpemented dataprocessing opcode {:b}", bin),
+        Ok(match opcode {
+            0b0000 => AND(GenericDataInstruction::new(rest, s, shifter)?),
+            0b0001 => EOR(GenericDataInstruction::new(rest, s, shifter)?),
+            0b0010 => SUB(GenericDataInstruction::new(rest, s, shifter)?),
+            0b0011 => RSB(GenericDataInstruction::new(rest, s, shifter)?),
+            0b0100 => ADD(GenericDataInstruction::new(rest, s, shifter)?),
+            0b0101 => ADC(GenericDataInstruction::new(rest, s, shifter)?),
+            0b0110 => SBC(GenericDataInstruction::new(rest, s, shifter)?),
+            0b0111 => RSC(GenericDataInstruction::new(rest, s, shifter)?),
+            0b1000 => TST(NoDestinationDataInstruction::new(rest, s, shifter)?),
+            0b1001 => TEQ(NoDestinationDataInstruction::new(rest, s, shifter)?),
+            0b1010 => CMP(NoDestinationDataInstruction::new(rest, s, shifter)?),
+            0b1011 => CMN(NoDestinationDataInstruction::new(rest, s, shifter)?),
+            0b1100 => ORR(GenericDataInstruction::new(rest, s, shifter)?),
+            0b1101 => MOV(MOVLikeDataInstruction::new(rest, s, shifter)?),
+            0b1110 => BIC(GenericDataInstruction::new(rest, s, shifter)?),
+            _ => MVN(MOVLikeDataInstruction::new(rest, s, shifter)?),
         })
     }
+    ///Encodes the instruction without the condition.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        use DataProssessingInstruction::*;
+        let opcode: u32 = match self {
+            AND(_) => 0b0000,
+            EOR(_) => 0b0001,
+            SUB(_) => 0b0010,
+            RSB(_) => 0b0011,
+            ADD(_) => 0b0100,
+            ADC(_) => 0b0101,
+            SBC(_) => 0b0110,
+            RSC(_) => 0b0111,
+            TST(_) => 0b1000,
+            TEQ(_) => 0b1001,
+            CMP(_) => 0b1010,
+            CMN(_) => 0b1011,
+            ORR(_) => 0b1100,
+            MOV(_) => 0b1101,
+            BIC(_) => 0b1110,
+            MVN(_) => 0b1111,
+        };
+        let (s, rn, rd, shifter) = match self {
+            ADC(inst) | ADD(inst) | AND(inst) | BIC(inst) | EOR(inst) | ORR(inst) | RSB(inst)
+            | RSC(inst) | SBC(inst) | SUB(inst) => {
+                (inst.s, inst.first_operand, inst.destination, inst.shifter)
+            }
+            // without the S bit the comparisons are the miscellaneous instructions
+            CMN(inst) | CMP(inst) | TEQ(inst) | TST(inst) if !inst.s => {
+                return Err(EncodeError::Unencodable("a comparison without the S bit"))
+            }
+            CMN(inst) | CMP(inst) | TEQ(inst) | TST(inst) => {
+                (true, inst.first_operand, Register::R0, inst.shifter)
+            }
+            MOV(inst) | MVN(inst) => (inst.s, Register::R0, inst.destination, inst.shifter),
+        };
+        let immediate = matches!(shifter, ShifterOperand::Immediate(_));
+        Ok((immediate as u32) << 25
+            | opcode << 21
+            | (s as u32) << 20
+            | (rn as u32) << 16
+            | (rd as u32) << 12
+            | shifter.encode()?)
+    }
+    ///Shifting by a register is unpredictable if any of the registers is the PC.
+    pub fn is_unpredictable(&self) -> bool {
+        use DataProssessingInstruction::*;
+        let (registers, shifter) = match self {
+            ADC(inst) | ADD(inst) | AND(inst) | BIC(inst) | EOR(inst) | ORR(inst) | RSB(inst)
+            | RSC(inst) | SBC(inst) | SUB(inst) => {
+                ([inst.destination, inst.first_operand], inst.shifter)
+            }
+            CMN(inst) | CMP(inst) | TEQ(inst) | TST(inst) => {
+                ([inst.first_operand, inst.first_operand], inst.shifter)
+            }
+            MOV(inst) | MVN(inst) => ([inst.destination, inst.destination], inst.shifter),
+        };
+        match shifter {
+            ShifterOperand::RegisterShift {
+                register, amount, ..
+            } => [registers[0], registers[1], register, amount].contains(&Register::R15),
+            _ => false,
+        }
+    }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct GenericDataInstruction {
-    destination: Register,
-    first_operand: Register,
-    s: bool,
-    shifter: ShifterOperand,
+    pub destination: Register,
+    pub first_operand: Register,
+    pub s: bool,
+    pub shifter: ShifterOperand,
 }
 impl GenericDataInstruction {
-    fn new(value: u32, rn: Register, s: bool) -> Result<Self, ParseError> {
-        let (rd, rest) = split_with_mask(value, 0b1111 << 11);
-        let rd = ((rd >> 12) as u8).try_into()?;
-        let shifter = rest.try_into()?;
+    fn new(value: u32, s: bool, shifter: ShifterOperand) -> Result<Self, ParseError> {
+        let (rn, value) = split_with_range(value, 16..=19);
+        let (rd, _) = split_with_range(value, 12..=15);
         Ok(Self {
-            destination: rd,
-            first_operand: rn,
+            destination: Register::try_from(rd as u8)?,
+            first_operand: Register::try_from(rn as u8)?,
             s,
             shifter,
         })
     }
 }
 
+///The comparisons, which only update the flags.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct NoDestinationDataInstruction {
-    first_operand: Register,
-    s: bool,
-    shifter: ShifterOperand,
+    pub first_operand: Register,
+    pub s: bool,
+    pub shifter: ShifterOperand,
+}
+impl NoDestinationDataInstruction {
+    fn new(value: u32, s: bool, shifter: ShifterOperand) -> Result<Self, ParseError> {
+        let (rn, value) = split_with_range(value, 16..=19);
+        let (rd, _) = split_with_range(value, 12..=15);
+        if rd > 0 {
+            return Err(ParseError::ShouldBeZero(rd << 12));
+        }
+        Ok(Self {
+            first_operand: Register::try_from(rn as u8)?,
+            s,
+            shifter,
+        })
+    }
 }
+///MOV and MVN, which have no first operand.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MOVLikeDataInstruction {
-    destination: Register,
-    s: bool,
-    shifter: ShifterOperand,
+    pub destination: Register,
+    pub s: bool,
+    pub shifter: ShifterOperand,
 }
-impl NoDestinationDataInstruction {
-    fn new(value: u32, rn: Register, s: bool) -> Result<Self, ParseError> {
-        let (rd, rest) = split_with_mask(value, 0b1111 << 11);
-        if rd > 0 {
-            return Err(ParseError::ShouldBeZero(rd));
+impl MOVLikeDataInstruction {
+    fn new(value: u32, s: bool, shifter: ShifterOperand) -> Result<Self, ParseError> {
+        let (rn, value) = split_with_range(value, 16..=19);
+        let (rd, _) = split_with_range(value, 12..=15);
+        if rn > 0 {
+            return Err(ParseError::ShouldBeZero(rn << 16));
         }
-        let shifter = rest.try_into()?;
         Ok(Self {
-            first_operand: rn,
+            destination: Register::try_from(rd as u8)?,
             s,
             shifter,
         })
     }
 }
+impl Assembly for DataProssessingInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        use DataProssessingInstruction::*;
+        let (base, s) = match self {
+            ADC(inst) => ("adc", inst.s),
+            ADD(inst) => ("add", inst.s),
+            AND(inst) => ("and", inst.s),
+            BIC(inst) => ("bic", inst.s),
+            EOR(inst) => ("eor", inst.s),
+            ORR(inst) => ("orr", inst.s),
+            RSB(inst) => ("rsb", inst.s),
+            RSC(inst) => ("rsc", inst.s),
+            SBC(inst) => ("sbc", inst.s),
+            SUB(inst) => ("sub", inst.s),
+            // UAL writes the moves of a shifted register as the shift
+            MOV(inst) if syntax == Syntax::Unified => match inst.shifter {
+                ShifterOperand::ImmediateShift { shift, .. } => (shift.mnemonic(), inst.s),
+                ShifterOperand::RegisterShift { shift, .. } => (shift.mnemonic(), inst.s),
+                _ => ("mov", inst.s),
+            },
+            MOV(inst) => ("mov", inst.s),
+            MVN(inst) => ("mvn", inst.s),
+            // the comparisons always set the flags, so the S is implied
+            CMN(_) => ("cmn", false),
+            CMP(_) => ("cmp", false),
+            TEQ(_) => ("teq", false),
+            TST(_) => ("tst", false),
+        };
+        syntax.conditional(base, cond, if s { "s" } else { "" })
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        use DataProssessingInstruction::*;
+        match self {
+            ADC(inst) | ADD(inst) | AND(inst) | BIC(inst) | EOR(inst) | ORR(inst) | RSB(inst)
+            | RSC(inst) | SBC(inst) | SUB(inst) => format!(
+                "{}, {}, {}",
+                inst.destination,
+                inst.first_operand,
+                inst.shifter.operand(syntax)
+            ),
+            CMN(inst) | CMP(inst) | TEQ(inst) | TST(inst) => {
+                format!("{}, {}", inst.first_operand, inst.shifter.operand(syntax))
+            }
+            MOV(inst) if syntax == Syntax::Unified => match inst.shifter {
+                ShifterOperand::ImmediateShift { register, shift } => match shift.amount() {
+                    Some(amount) => format!("{}, {register}, #{amount}", inst.destination),
+                    None => format!("{}, {register}", inst.destination),
+                },
+                ShifterOperand::RegisterShift {
+                    register, amount, ..
+                } => format!("{}, {register}, {amount}", inst.destination),
+                _ => format!("{}, {}", inst.destination, inst.shifter.operand(syntax)),
+            },
+            MOV(inst) | MVN(inst) => {
+                format!("{}, {}", inst.destination, inst.shifter.operand(syntax))
+            }
+        }
+    }
+}
+impl Display for DataProssessingInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::arm::adresssing::{RotatedImmediate, Shift, ShiftType};
+    use crate::instructions::arm::tests::{op, violations};
+    use crate::instructions::arm::PartialArmInstruction::DataProssessing;
+    use crate::instructions::arm::{ArmInstruction, PartialArmInstruction};
+    use crate::instructions::{Architecture, Violations};
+    use DataProssessingInstruction::*;
+    use Register::*;
+
+    fn generic(
+        destination: Register,
+        first_operand: Register,
+        s: bool,
+        shifter: ShifterOperand,
+    ) -> GenericDataInstruction {
+        GenericDataInstruction {
+            destination,
+            first_operand,
+            s,
+            shifter,
+        }
+    }
+    fn compare(first_operand: Register, shifter: ShifterOperand) -> NoDestinationDataInstruction {
+        NoDestinationDataInstruction {
+            first_operand,
+            s: true,
+            shifter,
+        }
+    }
+    fn immediate(value: u8) -> ShifterOperand {
+        ShifterOperand::Immediate(RotatedImmediate {
+            immediate: value,
+            rotate: 0,
+        })
+    }
 
+    #[test]
+    fn opcodes() {
+        use ShifterOperand::Register as R;
+        // and r0, r1, r2
+        assert_eq!(
+            op(0xe001_0002),
+            Some(DataProssessing(AND(generic(R0, R1, false, R(R2)))))
+        );
+        // eor r3, r4, #1
+        assert_eq!(
+            op(0xe224_3001),
+            Some(DataProssessing(EOR(generic(R3, R4, false, immediate(1)))))
+        );
+        // sub r5, r6, r7, lsl #2
+        assert_eq!(
+            op(0xe046_5107),
+            Some(DataProssessing(SUB(generic(
+                R5,
+                R6,
+                false,
+                ShifterOperand::ImmediateShift {
+                    register: R7,
+                    shift: Shift::LSL(2)
+                }
+            ))))
+        );
+        // rsb r0, r1, #0
+        assert_eq!(
+            op(0xe261_0000),
+            Some(DataProssessing(RSB(generic(R0, R1, false, immediate(0)))))
+        );
+        // adds r0, r1, r2
+        assert_eq!(
+            op(0xe091_0002),
+            Some(DataProssessing(ADD(generic(R0, R1, true, R(R2)))))
+        );
+        // adc r0, r1, r2, lsr r3
+        assert_eq!(
+            op(0xe0a1_0332),
+            Some(DataProssessing(ADC(generic(
+                R0,
+                R1,
+                false,
+                ShifterOperand::RegisterShift {
+                    register: R2,
+                    shift: ShiftType::LSR,
+                    amount: R3
+                }
+            ))))
+        );
+        // sbc r8, r9, r10
+        assert_eq!(
+            op(0xe0c9_800a),
+            Some(DataProssessing(SBC(generic(R8, R9, false, R(R10)))))
+        );
+        // rsc r11, r12, sp
+        assert_eq!(
+            op(0xe0ec_b00d),
+            Some(DataProssessing(RSC(generic(R11, R12, false, R(R13)))))
+        );
+        // tst r0, #3
+        assert_eq!(
+            op(0xe310_0003),
+            Some(DataProssessing(TST(compare(R0, immediate(3)))))
+        );
+        // teq r1, r2
+        assert_eq!(
+            op(0xe131_0002),
+            Some(DataProssessing(TEQ(compare(R1, R(R2)))))
+        );
+        // cmp r3, #4
+        assert_eq!(
+            op(0xe353_0004),
+            Some(DataProssessing(CMP(compare(R3, immediate(4)))))
+        );
+        // cmn r4, r5
+        assert_eq!(
+            op(0xe174_0005),
+            Some(DataProssessing(CMN(compare(R4, R(R5)))))
+        );
+        // orr r0, r0, #0x100
+        assert_eq!(
+            op(0xe380_0c01),
+            Some(DataProssessing(ORR(generic(
+                R0,
+                R0,
+                false,
+                ShifterOperand::Immediate(RotatedImmediate {
+                    immediate: 1,
+                    rotate: 12
+                })
+            ))))
+        );
+        // movs r0, #1
+        assert_eq!(
+            op(0xe3b0_0001),
+            Some(DataProssessing(MOV(MOVLikeDataInstruction {
+                destination: R0,
+                s: true,
+                shifter: immediate(1)
+            })))
+        );
+        // bic r0, r1, #15
+        assert_eq!(
+            op(0xe3c1_000f),
+            Some(DataProssessing(BIC(generic(R0, R1, false, immediate(15)))))
+        );
+        // mvn r2, r3
+        assert_eq!(
+            op(0xe1e0_2003),
+            Some(DataProssessing(MVN(MOVLikeDataInstruction {
+                destination: R2,
+                s: false,
+                shifter: R(R3)
+            })))
+        );
+    }
+
+    #[test]
+    fn comparisons_without_s_are_miscellaneous() {
+        let op = |word| ArmInstruction::try_from(word).map(|instruction| instruction.op);
+        // TEQ without S is bx r1
+        assert!(matches!(
+            op(0xe12f_ff11),
+            Ok(PartialArmInstruction::Branch(_))
+        ));
+        // CMN without S is clz r0, r1
+        assert!(matches!(
+            op(0xe16f_0f11),
+            Ok(PartialArmInstruction::Aritmetic(_))
+        ));
+        // TEQ of an immediate without S is msr cpsr_f, #0xf0000000
+        assert!(matches!(
+            op(0xe328_f20f),
+            Ok(PartialArmInstruction::RegisterAccess(_))
+        ));
+        // TST and CMP of an immediate without S are undefined
+        assert!(op(0xe300_0003).is_err());
+        assert!(op(0xe340_0004).is_err());
+    }
+
+    #[test]
+    fn should_be_zero_registers() {
+        // tst r0, #3 with Rd set to r1
+        let decoded = ArmInstruction::decode(0xe310_1003, Architecture::ARMv5TE);
+        assert_eq!(
+            decoded.instruction.op,
+            PartialArmInstruction::DataProssessing(TST(compare(R0, immediate(3))))
+        );
+        assert_eq!(decoded.violations, Violations::SHOULD_BE_ZERO);
+        // mov r0, #1 with Rn set to r2
+        let decoded = ArmInstruction::decode(0xe3a2_0001, Architecture::ARMv5TE);
+        assert_eq!(
+            decoded.instruction.op,
+            PartialArmInstruction::DataProssessing(MOV(MOVLikeDataInstruction {
+                destination: R0,
+                s: false,
+                shifter: immediate(1)
+            }))
+        );
+        assert_eq!(decoded.violations, Violations::SHOULD_BE_ZERO);
+    }
+
+    #[test]
+    fn register_shifts_using_the_pc() {
+        let violations = |word| ArmInstruction::decode(word, Architecture::ARMv5TE).violations;
+        // add r0, r1, r2, lsl r3
+        assert_eq!(violations(0xe081_0312), Violations::empty());
+        // add pc, r1, r2 only shifts by an immediate
+        assert_eq!(violations(0xe081_f002), Violations::empty());
+        // add r0, r1, r2, lsl pc
+        assert_eq!(violations(0xe081_0f12), Violations::UNPREDICTABLE);
+        // add pc, r1, r2, lsl r3
+        assert_eq!(violations(0xe081_f312), Violations::UNPREDICTABLE);
+        // add r0, pc, r2, lsl r3
+        assert_eq!(violations(0xe08f_0312), Violations::UNPREDICTABLE);
+        // mov r0, pc, lsl r1
+        assert_eq!(violations(0xe1a0_011f), Violations::UNPREDICTABLE);
+        // cmp pc, r2, lsl r3
+        assert_eq!(violations(0xe15f_0312), Violations::UNPREDICTABLE);
+    }
+}
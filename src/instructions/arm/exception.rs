@@ -1,4 +1,9 @@
-use crate::errors::ParseError;
+use std::fmt::{self, Display};
+
+use crate::errors::EncodeError;
+use crate::instructions::{
+    encode_field, fmt_assembly, split_with_range, Architecture, Assembly, Condition, Syntax,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExceptiongeneratingInstruction {
@@ -8,7 +13,107 @@ pub enum ExceptiongeneratingInstruction {
     SWI(u32),
 }
 impl ExceptiongeneratingInstruction {
-    pub fn new(rest: u32) -> Result<Self, ParseError> {
-        todo!("Exception generating instructions")
+    ///Decodes SWI, the 24 bit comment field is ignored by the processor.
+    pub fn new(rest: u32) -> Self {
+        let (comment, _) = split_with_range(rest, 0..=23);
+        Self::SWI(comment)
+    }
+    ///Decodes BKPT, whose immediate is split over bits 8 to 19 and 0 to 3.
+    pub fn new_breakpoint(value: u32) -> Self {
+        let (high, value) = split_with_range(value, 8..=19);
+        let (low, _) = split_with_range(value, 0..=3);
+        Self::BKPT((high << 4 | low) as u16)
+    }
+    ///Encodes the instruction without the condition.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        Ok(match *self {
+            Self::BKPT(immediate) => {
+                let immediate = immediate as u32;
+                0x120_0070 | (immediate >> 4) << 8 | immediate & 0b1111
+            }
+            Self::SWI(comment) => 0b1111 << 24 | encode_field(comment, 24, "comment")?,
+        })
+    }
+    ///The BIOS function an ARM state SWI calls. The DS BIOS reads the function number from bits
+    ///16 to 23 of the comment, so `swi 0x60000` calls function 6.
+    pub fn bios_function(&self) -> Option<u8> {
+        match self {
+            Self::SWI(comment) => Some((comment >> 16) as u8),
+            Self::BKPT(_) => None,
+        }
+    }
+    ///BKPT is ARMv5.
+    pub fn architecture(&self) -> Architecture {
+        match self {
+            Self::BKPT(_) => Architecture::ARMv5TE,
+            Self::SWI(_) => Architecture::ARMv4T,
+        }
+    }
+}
+impl Assembly for ExceptiongeneratingInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        match self {
+            Self::BKPT(_) => syntax.conditional("bkpt", cond, ""),
+            // UAL renamed SWI to SVC
+            Self::SWI(_) if syntax == Syntax::Unified => syntax.conditional("svc", cond, ""),
+            Self::SWI(_) => syntax.conditional("swi", cond, ""),
+        }
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        match self {
+            Self::BKPT(immediate) => syntax.comment_field(*immediate as u32),
+            Self::SWI(comment) => syntax.comment_field(*comment),
+        }
+    }
+}
+impl Display for ExceptiongeneratingInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::arm::tests::op;
+    use crate::instructions::arm::PartialArmInstruction::Exceptiongenerating;
+    use crate::instructions::arm::{ArmInstruction, PartialArmInstruction};
+
+    #[test]
+    fn software_interrupt() {
+        // swi 0x0b0000
+        let Some(Exceptiongenerating(swi)) = op(0xef0b_0000) else {
+            panic!("swi 0x0b0000 isn't exception generating");
+        };
+        assert_eq!(swi, ExceptiongeneratingInstruction::SWI(0x0b_0000));
+        assert_eq!(swi.bios_function(), Some(0x0b));
+        assert_eq!(swi.to_string(), "swi 0xb0000");
+        // swine 0xffffff keeps the whole comment
+        let instruction = ArmInstruction::try_from(0x1fff_ffff).unwrap();
+        assert_eq!(instruction.cond, Condition::NotEqual);
+        assert_eq!(
+            instruction.op,
+            PartialArmInstruction::Exceptiongenerating(ExceptiongeneratingInstruction::SWI(
+                0xff_ffff
+            ))
+        );
+    }
+
+    #[test]
+    fn breakpoint() {
+        // bkpt 0x1234 splits the immediate over bits 8 to 19 and 0 to 3
+        let Some(Exceptiongenerating(bkpt)) = op(0xe121_2374) else {
+            panic!("bkpt 0x1234 isn't exception generating");
+        };
+        assert_eq!(bkpt, ExceptiongeneratingInstruction::BKPT(0x1234));
+        assert_eq!(bkpt.bios_function(), None);
+        assert_eq!(bkpt.encode().unwrap(), 0x0121_2374);
+        assert_eq!(bkpt.to_string(), "bkpt 0x1234");
+        // bkpt 0xffff
+        assert_eq!(
+            op(0xe12f_ff7f),
+            Some(Exceptiongenerating(ExceptiongeneratingInstruction::BKPT(
+                0xffff
+            )))
+        );
     }
 }
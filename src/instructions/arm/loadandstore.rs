@@ -1,7 +1,15 @@
-use crate::errors::ParseError;
-use crate::instructions::{Register, RegisterList};
+use std::fmt::{self, Display};
 
-use super::{AddressingMode, PostIndexedAddressingMode};
+use crate::errors::{EncodeError, ParseError};
+use crate::instructions::{
+    check_bit, fmt_assembly, should_be_one, should_be_zero, split_with_range, Architecture,
+    Assembly, Condition, Register, RegisterList, Syntax,
+};
+
+use super::adresssing::{
+    Indexing, LoadAndStoreAddressingMode, LoadAndStoreMultipleAddressingMode,
+    MiscLoadAndStoreAddressingMode, MiscLoadAndStoreOffset, PostIndexedAddressingMode,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LoadAndStoreInstruction {
@@ -12,17 +20,17 @@ pub enum LoadAndStoreInstruction {
     ///Load Byte with User Mode Privilege. See LDRBT on page A4-48.
     LDRBT(LoadAndStorePostIndexInstruction),
     ///Load Doubleword. See LDRD on page A4-50.
-    LDRD(LoadAndStoreGenericInsturction),
+    LDRD(LoadAndStoreMiscInstruction),
     ///Load Exclusive. See LDREX on page A4-52.
     LDREX(LDREX),
     ///Load Unsigned Halfword. See LDRH on page A4-54.
-    LDRH(LoadAndStoreGenericInsturction),
+    LDRH(LoadAndStoreMiscInstruction),
     ///Load Signed Byte. See LDRSB on page A4-56.
-    LDRSB(LoadAndStoreGenericInsturction),
+    LDRSB(LoadAndStoreMiscInstruction),
     ///Load Signed Halfword. See LDRSH on page A4-58.
-    LDRSH(LoadAndStoreGenericInsturction),
+    LDRSH(LoadAndStoreMiscInstruction),
     ///Load Word with User Mode Privilege. See LDRT on page A4-60.
-    LDRT(LoadAndStoreGenericInsturction),
+    LDRT(LoadAndStorePostIndexInstruction),
     ///Store Word. See STR on page A4-193.
     STR(LoadAndStoreGenericInsturction),
     ///Store Byte. See STRB on page A4-195.
@@ -30,48 +38,358 @@ pub enum LoadAndStoreInstruction {
     ///Store Byte with User Mode Privilege. See STRBT on page A4-197.
     STRBT(LoadAndStorePostIndexInstruction),
     ///Store Doubleword. See STRD on page A4-199.
-    STRD(LoadAndStoreGenericInsturction),
+    STRD(LoadAndStoreMiscInstruction),
+    ///Store Exclusive. See STREX on page A4-202.
+    STREX(STREX),
     ///Store Halfword. See STRH on page A4-204.
-    STRH(LoadAndStoreGenericInsturction),
+    STRH(LoadAndStoreMiscInstruction),
     ///Store Word with User Mode Privilege. See STRT on page A4-206.
-    STRT(LoadAndStoreGenericInsturction),
+    STRT(LoadAndStorePostIndexInstruction),
     Multiple(LoadAndStoreMultiple),
 }
 impl LoadAndStoreInstruction {
-    pub fn new(value: u32) -> Result<Self, ParseError> {
-        todo!("LoadAndStore")
+    ///Decodes the word and unsigned byte transfers, `register_offset` is the I bit.
+    pub fn new_word_or_byte(value: u32, register_offset: bool) -> Result<Self, ParseError> {
+        let load = check_bit(value, 20);
+        let byte = check_bit(value, 22);
+        // P == 0 and W == 1 selects the user mode privilege variants
+        let user_mode = !check_bit(value, 24) && check_bit(value, 21);
+        use LoadAndStoreInstruction::*;
+        Ok(match (load, byte, user_mode) {
+            (true, false, false) => {
+                LDR(LoadAndStoreGenericInsturction::new(value, register_offset)?)
+            }
+            (true, true, false) => {
+                LDRB(LoadAndStoreGenericInsturction::new(value, register_offset)?)
+            }
+            (true, false, true) => LDRT(LoadAndStorePostIndexInstruction::new(
+                value,
+                register_offset,
+            )?),
+            (true, true, true) => LDRBT(LoadAndStorePostIndexInstruction::new(
+                value,
+                register_offset,
+            )?),
+            (false, false, false) => {
+                STR(LoadAndStoreGenericInsturction::new(value, register_offset)?)
+            }
+            (false, true, false) => {
+                STRB(LoadAndStoreGenericInsturction::new(value, register_offset)?)
+            }
+            (false, false, true) => STRT(LoadAndStorePostIndexInstruction::new(
+                value,
+                register_offset,
+            )?),
+            (false, true, true) => STRBT(LoadAndStorePostIndexInstruction::new(
+                value,
+                register_offset,
+            )?),
+        })
+    }
+    ///Decodes the halfword, signed byte and doubleword transfers, which have bits 4 and 7 set
+    ///and bits 5 and 6 not both clear.
+    pub fn new_misc(value: u32) -> Result<Self, ParseError> {
+        let (op, _) = split_with_range(value, 5..=6);
+        let inst = LoadAndStoreMiscInstruction::new(value)?;
+        use LoadAndStoreInstruction::*;
+        Ok(match (check_bit(value, 20), op) {
+            (true, 0b01) => LDRH(inst),
+            (true, 0b10) => LDRSB(inst),
+            (true, _) => LDRSH(inst),
+            (false, 0b01) => STRH(inst),
+            (false, 0b10) => LDRD(inst),
+            (false, _) => STRD(inst),
+        })
+    }
+    ///Decodes LDREX and STREX, which sit in the multiply space with bits 23 and 24 set.
+    pub fn new_exclusive(value: u32) -> Result<Self, ParseError> {
+        let (fixed, _) = split_with_range(value, 21..=22);
+        if fixed != 0 {
+            return Err(ParseError::Undefined(value));
+        }
+        Ok(match check_bit(value, 20) {
+            true => Self::LDREX(LDREX::new(value)?),
+            false => Self::STREX(STREX::new(value)?),
+        })
+    }
+    ///Encodes the instruction without the condition.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        use LoadAndStoreInstruction::*;
+        let load = matches!(
+            self,
+            LDR(_) | LDRB(_) | LDRBT(_) | LDRH(_) | LDRSB(_) | LDRSH(_) | LDRT(_)
+        ) as u32;
+        Ok(match self {
+            LDR(inst) | LDRB(inst) | STR(inst) | STRB(inst) => {
+                let byte = matches!(self, LDRB(_) | STRB(_)) as u32;
+                0b01 << 26
+                    | byte << 22
+                    | load << 20
+                    | (inst.destination as u32) << 12
+                    | inst.adressing_mode.encode()?
+            }
+            // post-indexing with W set selects the user mode privilege
+            LDRBT(inst) | LDRT(inst) | STRBT(inst) | STRT(inst) => {
+                let byte = matches!(self, LDRBT(_) | STRBT(_)) as u32;
+                0b01 << 26
+                    | byte << 22
+                    | 1 << 21
+                    | load << 20
+                    | (inst.destination as u32) << 12
+                    | inst.adressing_mode.encode()?
+            }
+            LDRD(inst) | LDRH(inst) | LDRSB(inst) | LDRSH(inst) | STRD(inst) | STRH(inst) => {
+                let op: u32 = match self {
+                    LDRH(_) | STRH(_) => 0b01,
+                    LDRSB(_) | LDRD(_) => 0b10,
+                    _ => 0b11,
+                };
+                load << 20
+                    | (inst.destination as u32) << 12
+                    | 1 << 7
+                    | op << 5
+                    | 1 << 4
+                    | inst.adressing_mode.encode()
+            }
+            LDREX(inst) => {
+                0b0001_1001 << 20
+                    | (inst.adress as u32) << 16
+                    | (inst.destination as u32) << 12
+                    | 0b1111_1001_1111
+            }
+            STREX(inst) => {
+                0b0001_1000 << 20
+                    | (inst.adress as u32) << 16
+                    | (inst.destination as u32) << 12
+                    | 0b1111_1001 << 4
+                    | inst.source as u32
+            }
+            Multiple(multiple) => multiple.encode()?,
+        })
+    }
+    ///The byte and halfword transfers can't use the PC, writeback can't overlap the transferred
+    ///registers and register offsets can't be the PC. The doubleword transfers need an even
+    ///register that isn't R14, and LDRD can't use the loaded registers as offset.
+    pub fn is_unpredictable(&self) -> bool {
+        use LoadAndStoreInstruction::*;
+        match self {
+            LDR(inst) | LDRB(inst) | STR(inst) | STRB(inst) => {
+                let mode = inst.adressing_mode;
+                (matches!(self, LDRB(_) | STRB(_)) && inst.destination == Register::R15)
+                    || is_unpredictable_transfer(
+                        &[inst.destination],
+                        mode.base,
+                        mode.offset.register(),
+                        mode.indexing != Indexing::Offset,
+                    )
+            }
+            LDRBT(inst) | LDRT(inst) | STRBT(inst) | STRT(inst) => {
+                let mode = inst.adressing_mode;
+                (!matches!(self, STRT(_)) && inst.destination == Register::R15)
+                    || is_unpredictable_transfer(
+                        &[inst.destination],
+                        mode.base,
+                        mode.offset.register(),
+                        true,
+                    )
+            }
+            LDRH(inst) | LDRSB(inst) | LDRSH(inst) | STRH(inst) => {
+                let mode = inst.adressing_mode;
+                inst.destination == Register::R15
+                    || is_unpredictable_transfer(
+                        &[inst.destination],
+                        mode.base,
+                        mode.offset.register(),
+                        mode.indexing != Indexing::Offset,
+                    )
+            }
+            LDRD(inst) | STRD(inst) => {
+                let rd = inst.destination as u8;
+                if rd % 2 == 1 || inst.destination == Register::R14 {
+                    return true;
+                }
+                let mode = inst.adressing_mode;
+                let second = Register::try_from(rd + 1).unwrap_or(Register::R15);
+                if is_unpredictable_transfer(
+                    &[inst.destination, second],
+                    mode.base,
+                    mode.offset.register(),
+                    mode.indexing != Indexing::Offset,
+                ) {
+                    return true;
+                }
+                match (self, mode.offset) {
+                    (LDRD(_), MiscLoadAndStoreOffset::Register(rm)) => {
+                        rm as u8 == rd || rm as u8 == rd + 1
+                    }
+                    _ => false,
+                }
+            }
+            LDREX(inst) => inst.destination == Register::R15 || inst.adress == Register::R15,
+            STREX(inst) => {
+                let registers = [inst.destination, inst.source, inst.adress];
+                registers.contains(&Register::R15)
+                    || inst.destination == inst.source
+                    || inst.destination == inst.adress
+            }
+            Multiple(multiple) => multiple.is_unpredictable(),
+            _ => false,
+        }
+    }
+    ///The doubleword transfers are part of the DSP extension and the exclusives are ARMv6.
+    pub fn architecture(&self) -> Architecture {
+        use LoadAndStoreInstruction::*;
+        match self {
+            LDRD(_) | STRD(_) => Architecture::ARMv5TE,
+            LDREX(_) | STREX(_) => Architecture::ARMv6,
+            _ => Architecture::ARMv4T,
+        }
+    }
+}
+///Writing the base back when it's the PC or one of the `transferred` registers is unpredictable,
+///and so are the PC as `offset` and writing back with the base as `offset`.
+fn is_unpredictable_transfer(
+    transferred: &[Register],
+    base: Register,
+    offset: Option<Register>,
+    writeback: bool,
+) -> bool {
+    offset == Some(Register::R15)
+        || writeback
+            && (base == Register::R15 || transferred.contains(&base) || offset == Some(base))
+}
+impl Assembly for LoadAndStoreInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        use LoadAndStoreInstruction::*;
+        let (base, suffix) = match self {
+            LDR(_) => ("ldr", ""),
+            LDRB(_) => ("ldr", "b"),
+            LDRBT(_) => ("ldr", "bt"),
+            LDRD(_) => ("ldr", "d"),
+            LDREX(_) => ("ldrex", ""),
+            LDRH(_) => ("ldr", "h"),
+            LDRSB(_) => ("ldr", "sb"),
+            LDRSH(_) => ("ldr", "sh"),
+            LDRT(_) => ("ldr", "t"),
+            STR(_) => ("str", ""),
+            STRB(_) => ("str", "b"),
+            STRBT(_) => ("str", "bt"),
+            STRD(_) => ("str", "d"),
+            STREX(_) => ("strex", ""),
+            STRH(_) => ("str", "h"),
+            STRT(_) => ("str", "t"),
+            Multiple(multiple) => return multiple.mnemonic(cond, syntax),
+        };
+        syntax.conditional(base, cond, suffix)
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        use LoadAndStoreInstruction::*;
+        match self {
+            LDR(inst) | LDRB(inst) | STR(inst) | STRB(inst) => {
+                format!("{}, {}", inst.destination, inst.adressing_mode)
+            }
+            LDRBT(inst) | LDRT(inst) | STRBT(inst) | STRT(inst) => {
+                format!("{}, {}", inst.destination, inst.adressing_mode)
+            }
+            // UAL names the second register of the doubleword transfers too
+            LDRD(inst) | STRD(inst) if syntax == Syntax::Unified => {
+                let second = Register::try_from(inst.destination as u8 + 1)
+                    .map_or_else(|_| "pc".to_string(), |register| register.to_string());
+                format!("{}, {second}, {}", inst.destination, inst.adressing_mode)
+            }
+            LDRD(inst) | LDRH(inst) | LDRSB(inst) | LDRSH(inst) | STRD(inst) | STRH(inst) => {
+                format!("{}, {}", inst.destination, inst.adressing_mode)
+            }
+            LDREX(inst) => format!("{}, [{}]", inst.destination, inst.adress),
+            STREX(inst) => format!("{}, {}, [{}]", inst.destination, inst.source, inst.adress),
+            Multiple(multiple) => multiple.operands(syntax),
+        }
+    }
+}
+impl Display for LoadAndStoreInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
     }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LoadAndStoreGenericInsturction {
-    destination: Register,
-    adressing_mode: AddressingMode,
+    pub destination: Register,
+    pub adressing_mode: LoadAndStoreAddressingMode,
 }
 
 impl LoadAndStoreGenericInsturction {
-    fn new(value: u32) -> Result<Self, ParseError> {
-        todo!("LoadAndStoreGeneric")
+    fn new(value: u32, register_offset: bool) -> Result<Self, ParseError> {
+        let (rd, _) = split_with_range(value, 12..=15);
+        Ok(Self {
+            destination: Register::try_from(rd as u8)?,
+            adressing_mode: LoadAndStoreAddressingMode::new(value, register_offset)?,
+        })
     }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LoadAndStorePostIndexInstruction {
-    destination: Register,
-    adressing_mode: PostIndexedAddressingMode,
+    pub destination: Register,
+    pub adressing_mode: PostIndexedAddressingMode,
 }
 impl LoadAndStorePostIndexInstruction {
+    fn new(value: u32, register_offset: bool) -> Result<Self, ParseError> {
+        let (rd, _) = split_with_range(value, 12..=15);
+        Ok(Self {
+            destination: Register::try_from(rd as u8)?,
+            adressing_mode: PostIndexedAddressingMode::new(value, register_offset)?,
+        })
+    }
+}
+///Halfword, signed byte and doubleword loads and stores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadAndStoreMiscInstruction {
+    pub destination: Register,
+    pub adressing_mode: MiscLoadAndStoreAddressingMode,
+}
+impl LoadAndStoreMiscInstruction {
     fn new(value: u32) -> Result<Self, ParseError> {
-        todo!("LoadAndStorePostIndexInstruction")
+        let (rd, _) = split_with_range(value, 12..=15);
+        Ok(Self {
+            destination: Register::try_from(rd as u8)?,
+            adressing_mode: MiscLoadAndStoreAddressingMode::new(value)?,
+        })
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LDREX {
-    destination: Register,
-    adress: Register,
+    pub destination: Register,
+    pub adress: Register,
 }
 impl LDREX {
     fn new(value: u32) -> Result<Self, ParseError> {
-        todo!("LDREX")
+        should_be_one(value, 0b1111 << 8 | 0b1111)?;
+        let (rn, value) = split_with_range(value, 16..=19);
+        let (rd, _) = split_with_range(value, 12..=15);
+        Ok(Self {
+            destination: Register::try_from(rd as u8)?,
+            adress: Register::try_from(rn as u8)?,
+        })
+    }
+}
+///`destination` gets the status, 0 if the store succeeded and 1 if it didn't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct STREX {
+    pub destination: Register,
+    pub source: Register,
+    pub adress: Register,
+}
+impl STREX {
+    fn new(value: u32) -> Result<Self, ParseError> {
+        should_be_one(value, 0b1111 << 8)?;
+        let (rn, value) = split_with_range(value, 16..=19);
+        let (rd, value) = split_with_range(value, 12..=15);
+        let (rm, _) = split_with_range(value, 0..=3);
+        Ok(Self {
+            destination: Register::try_from(rd as u8)?,
+            source: Register::try_from(rm as u8)?,
+            adress: Register::try_from(rn as u8)?,
+        })
     }
 }
 impl From<LoadAndStoreMultiple> for LoadAndStoreInstruction {
@@ -86,39 +404,574 @@ pub enum LoadAndStoreMultiple {
     ///User Registers Load Multiple. See LDM (2) on page A4-38.
     LDMR(LoadAndStoreMultipleGeneric),
     ///Load Multiple with Restore CPSR. See LDM (3) on page A4-40.
-    LDMC(LoadAndStoreMultipleGeneric),
+    LDMC(LoadAndStoreMultipleWriteGeneric),
     ///Store Multiple. See STM (1) on page A4-189.
     STM(LoadAndStoreMultipleWriteGeneric),
-    ///Store Multiple. See STM (1) on page A4-189.
-    STM2(LoadAndStoreMultipleWriteGeneric),
+    ///User Registers Store Multiple. See STM (2) on page A4-191.
+    STM2(LoadAndStoreMultipleGeneric),
 }
 impl LoadAndStoreMultiple {
-    fn new(value: u32) -> Result<Self, ParseError> {
-        todo!("LDREX")
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        let load = check_bit(value, 20);
+        // the S bit selects the user mode registers, or restoring the CPSR when loading the PC
+        let s = check_bit(value, 22);
+        use LoadAndStoreMultiple::*;
+        Ok(match (load, s) {
+            (true, false) => LDM(LoadAndStoreMultipleWriteGeneric::new(value)?),
+            (true, true) if check_bit(value, 15) => {
+                LDMC(LoadAndStoreMultipleWriteGeneric::new(value)?)
+            }
+            (true, true) => LDMR(LoadAndStoreMultipleGeneric::new(value)?),
+            (false, false) => STM(LoadAndStoreMultipleWriteGeneric::new(value)?),
+            (false, true) => STM2(LoadAndStoreMultipleGeneric::new(value)?),
+        })
+    }
+    ///Encodes the instruction without the condition.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        use LoadAndStoreMultiple::*;
+        let (load, s, write, adressing_mode, base, registers) = match *self {
+            LDM(inst) | LDMC(inst) | STM(inst) => (
+                matches!(self, LDM(_) | LDMC(_)),
+                matches!(self, LDMC(_)),
+                inst.write,
+                inst.adressing_mode,
+                inst.base,
+                inst.registers,
+            ),
+            LDMR(inst) | STM2(inst) => (
+                matches!(self, LDMR(_)),
+                true,
+                false,
+                inst.adressing_mode,
+                inst.base,
+                inst.registers,
+            ),
+        };
+        // the PC in the list selects between LDM (2) and LDM (3)
+        let pc = registers.contains(RegisterList::REGISTER15);
+        match self {
+            LDMC(_) if !pc => return Err(EncodeError::Unencodable("LDM (3) without the PC")),
+            LDMR(_) if pc => return Err(EncodeError::Unencodable("LDM (2) with the PC")),
+            _ => {}
+        }
+        Ok(0b100 << 25
+            | adressing_mode.encode()
+            | (s as u32) << 22
+            | (write as u32) << 21
+            | (load as u32) << 20
+            | (base as u32) << 16
+            | registers.bits() as u32)
+    }
+    ///An empty register list or the PC as base is unpredictable, and so is writing back a base
+    ///that is in the list, unless STM stores it as the lowest register.
+    pub fn is_unpredictable(&self) -> bool {
+        use LoadAndStoreMultiple::*;
+        let (base, registers) = match self {
+            LDM(inst) | LDMC(inst) | STM(inst) => (inst.base, inst.registers),
+            LDMR(inst) | STM2(inst) => (inst.base, inst.registers),
+        };
+        let written_back = match self {
+            LDM(inst) | LDMC(inst) => inst.write && registers.registers().any(|r| r == base),
+            STM(inst) => {
+                inst.write
+                    && registers.registers().any(|r| r == base)
+                    && registers.registers().next() != Some(base)
+            }
+            LDMR(_) | STM2(_) => false,
+        };
+        base == Register::R15 || registers.is_empty() || written_back
+    }
+}
+impl Assembly for LoadAndStoreMultiple {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        use LoadAndStoreMultiple::*;
+        match self {
+            LDM(inst) | LDMC(inst) => {
+                syntax.conditional("ldm", cond, inst.adressing_mode.suffix(syntax))
+            }
+            LDMR(inst) => syntax.conditional("ldm", cond, inst.adressing_mode.suffix(syntax)),
+            STM(inst) => syntax.conditional("stm", cond, inst.adressing_mode.suffix(syntax)),
+            STM2(inst) => syntax.conditional("stm", cond, inst.adressing_mode.suffix(syntax)),
+        }
+    }
+    ///The `^` marks the user mode registers, or restoring the CPSR for LDM (3).
+    fn operands(&self, syntax: Syntax) -> String {
+        use LoadAndStoreMultiple::*;
+        match self {
+            LDM(inst) | STM(inst) => inst.to_string(),
+            LDMC(inst) => format!("{inst}^"),
+            LDMR(inst) | STM2(inst) => format!("{}, {}^", inst.base, inst.registers),
+        }
+    }
+}
+impl Display for LoadAndStoreMultiple {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LoadAndStoreMultipleWriteGeneric {
-    adressing_mode: AddressingMode,
-    base: Register,
-    write: bool,
-    registers: RegisterList,
+    pub adressing_mode: LoadAndStoreMultipleAddressingMode,
+    pub base: Register,
+    pub write: bool,
+    pub registers: RegisterList,
 }
 
 impl LoadAndStoreMultipleWriteGeneric {
     fn new(value: u32) -> Result<Self, ParseError> {
-        todo!("LDREX")
+        let (rn, value) = split_with_range(value, 16..=19);
+        let (registers, _) = split_with_range(value, 0..=15);
+        Ok(Self {
+            adressing_mode: LoadAndStoreMultipleAddressingMode::new(value),
+            base: Register::try_from(rn as u8)?,
+            write: check_bit(value, 21),
+            registers: RegisterList::new(registers as u16),
+        })
+    }
+}
+///The base, with `!` for writeback, and the register list.
+impl Display for LoadAndStoreMultipleWriteGeneric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write = if self.write { "!" } else { "" };
+        write!(f, "{}{write}, {}", self.base, self.registers)
     }
 }
+///The user mode register forms, which can't write back.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LoadAndStoreMultipleGeneric {
-    adressing_mode: AddressingMode,
-    base: Register,
-    registers: RegisterList,
+    pub adressing_mode: LoadAndStoreMultipleAddressingMode,
+    pub base: Register,
+    pub registers: RegisterList,
 }
 impl LoadAndStoreMultipleGeneric {
     fn new(value: u32) -> Result<Self, ParseError> {
-        todo!("LDREX")
+        should_be_zero(value, 1 << 21)?;
+        let (rn, value) = split_with_range(value, 16..=19);
+        let (registers, _) = split_with_range(value, 0..=15);
+        Ok(Self {
+            adressing_mode: LoadAndStoreMultipleAddressingMode::new(value),
+            base: Register::try_from(rn as u8)?,
+            registers: RegisterList::new(registers as u16),
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::arm::adresssing::{LoadAndStoreOffset, MiscLoadAndStoreOffset, Shift};
+    use crate::instructions::arm::tests::{op, violations};
+    use crate::instructions::arm::PartialArmInstruction::LoadAndStore;
+    use crate::instructions::arm::{ArmInstruction, PartialArmInstruction};
+    use crate::instructions::Violations;
+    use Register::*;
+
+    fn generic(
+        destination: Register,
+        base: Register,
+        offset: LoadAndStoreOffset,
+        add: bool,
+        indexing: Indexing,
+    ) -> LoadAndStoreGenericInsturction {
+        LoadAndStoreGenericInsturction {
+            destination,
+            adressing_mode: LoadAndStoreAddressingMode {
+                base,
+                offset,
+                add,
+                indexing,
+            },
+        }
+    }
+    fn post_indexed(
+        destination: Register,
+        base: Register,
+        offset: LoadAndStoreOffset,
+        add: bool,
+    ) -> LoadAndStorePostIndexInstruction {
+        LoadAndStorePostIndexInstruction {
+            destination,
+            adressing_mode: PostIndexedAddressingMode { base, offset, add },
+        }
+    }
+
+    #[test]
+    fn word_and_byte_transfers() {
+        use LoadAndStoreInstruction::*;
+        use LoadAndStoreOffset::*;
+        // ldr r1, [r2, #4]
+        assert_eq!(
+            op(0xe592_1004),
+            Some(LoadAndStore(LDR(generic(
+                R1,
+                R2,
+                Immediate(4),
+                true,
+                Indexing::Offset
+            ))))
+        );
+        // ldr r1, [r2, #-4]!
+        assert_eq!(
+            op(0xe532_1004),
+            Some(LoadAndStore(LDR(generic(
+                R1,
+                R2,
+                Immediate(4),
+                false,
+                Indexing::PreIndexed
+            ))))
+        );
+        // str r0, [r1], #-4095
+        assert_eq!(
+            op(0xe401_0fff),
+            Some(LoadAndStore(STR(generic(
+                R0,
+                R1,
+                Immediate(4095),
+                false,
+                Indexing::PostIndexed
+            ))))
+        );
+        // str r0, [r1, r2]!
+        assert_eq!(
+            op(0xe7a1_0002),
+            Some(LoadAndStore(STR(generic(
+                R0,
+                R1,
+                Register(R2),
+                true,
+                Indexing::PreIndexed
+            ))))
+        );
+        // ldrb r4, [r5, -r6]
+        assert_eq!(
+            op(0xe755_4006),
+            Some(LoadAndStore(LDRB(generic(
+                R4,
+                R5,
+                Register(R6),
+                false,
+                Indexing::Offset
+            ))))
+        );
+    }
+
+    #[test]
+    fn scaled_register_offsets() {
+        use LoadAndStoreInstruction::*;
+        let scaled = |register, shift| LoadAndStoreOffset::ScaledRegister { register, shift };
+        // ldr r1, [r2], r3, lsl #2
+        assert_eq!(
+            op(0xe692_1103),
+            Some(LoadAndStore(LDR(generic(
+                R1,
+                R2,
+                scaled(R3, Shift::LSL(2)),
+                true,
+                Indexing::PostIndexed
+            ))))
+        );
+        // strb r0, [r1, -r2, asr #32]
+        assert_eq!(
+            op(0xe741_0042),
+            Some(LoadAndStore(STRB(generic(
+                R0,
+                R1,
+                scaled(R2, Shift::ASR(32)),
+                false,
+                Indexing::Offset
+            ))))
+        );
+        // ldr r0, [r1, r2, rrx]
+        assert_eq!(
+            op(0xe791_0062),
+            Some(LoadAndStore(LDR(generic(
+                R0,
+                R1,
+                scaled(R2, Shift::RRX),
+                true,
+                Indexing::Offset
+            ))))
+        );
+    }
+
+    #[test]
+    fn user_mode_privilege_transfers() {
+        use LoadAndStoreInstruction::*;
+        use LoadAndStoreOffset::*;
+        // ldrt r0, [r1], #8
+        assert_eq!(
+            op(0xe4b1_0008),
+            Some(LoadAndStore(LDRT(post_indexed(R0, R1, Immediate(8), true))))
+        );
+        // ldrbt r0, [r1], #-1
+        assert_eq!(
+            op(0xe471_0001),
+            Some(LoadAndStore(LDRBT(post_indexed(
+                R0,
+                R1,
+                Immediate(1),
+                false
+            ))))
+        );
+        // strt r7, [r8], -r9
+        assert_eq!(
+            op(0xe628_7009),
+            Some(LoadAndStore(STRT(post_indexed(
+                R7,
+                R8,
+                Register(R9),
+                false
+            ))))
+        );
+        // strbt r3, [r4], -r5, ror #3
+        assert_eq!(
+            op(0xe664_31e5),
+            Some(LoadAndStore(STRBT(post_indexed(
+                R3,
+                R4,
+                ScaledRegister {
+                    register: R5,
+                    shift: Shift::ROR(3)
+                },
+                false
+            ))))
+        );
+    }
+
+    #[test]
+    fn register_offset_with_bit_4_set_is_undefined() {
+        assert!(ArmInstruction::try_from(0xe791_0012).is_err());
+    }
+
+    #[test]
+    fn load_and_store_multiple() {
+        use LoadAndStoreMultiple::*;
+        use LoadAndStoreMultipleAddressingMode::*;
+        let multiple = |word| match op(word) {
+            Some(LoadAndStore(LoadAndStoreInstruction::Multiple(multiple))) => multiple,
+            op => panic!("{word:#010x} decoded as {op:?}"),
+        };
+        // ldmia r0!, {r1, pc}
+        assert_eq!(
+            multiple(0xe8b0_8002),
+            LDM(LoadAndStoreMultipleWriteGeneric {
+                adressing_mode: IncrementAfter,
+                base: R0,
+                write: true,
+                registers: RegisterList::REGISTER1 | RegisterList::REGISTER15
+            })
+        );
+        // stmib r4, {r5}
+        assert_eq!(
+            multiple(0xe984_0020),
+            STM(LoadAndStoreMultipleWriteGeneric {
+                adressing_mode: IncrementBefore,
+                base: R4,
+                write: false,
+                registers: RegisterList::REGISTER5
+            })
+        );
+        // ldmia r0, {r1, r2}^ loads the user mode registers
+        assert_eq!(
+            multiple(0xe8d0_0006),
+            LDMR(LoadAndStoreMultipleGeneric {
+                adressing_mode: IncrementAfter,
+                base: R0,
+                registers: RegisterList::new(0b110)
+            })
+        );
+        // stmdb sp, {r0, lr}^ stores them
+        assert_eq!(
+            multiple(0xe94d_4001),
+            STM2(LoadAndStoreMultipleGeneric {
+                adressing_mode: DecrementBefore,
+                base: R13,
+                registers: RegisterList::REGISTER0 | RegisterList::REGISTER14
+            })
+        );
+        // ldmia sp!, {r0-r3, pc}^ restores the CPSR
+        assert_eq!(
+            multiple(0xe8fd_800f),
+            LDMC(LoadAndStoreMultipleWriteGeneric {
+                adressing_mode: IncrementAfter,
+                base: R13,
+                write: true,
+                registers: RegisterList::new(0x800f)
+            })
+        );
+    }
+
+    #[test]
+    fn load_and_store_multiple_restrictions() {
+        assert_eq!(violations(0xe8b0_8002), Violations::empty());
+        // ldmia r0, {r1, r2}^ with writeback
+        assert_eq!(violations(0xe8f0_0006), Violations::SHOULD_BE_ZERO);
+        // ldmia pc, {r0}
+        assert_eq!(violations(0xe89f_0001), Violations::UNPREDICTABLE);
+        // ldmia r0!, {}
+        assert_eq!(violations(0xe8b0_0000), Violations::UNPREDICTABLE);
+        // ldmia r1!, {r1, r2} and ldmdb r1!, {r1-r3, r6, r7, sl, ip} load the written back base
+        assert_eq!(violations(0xe8b1_0006), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0xe931_14ce), Violations::UNPREDICTABLE);
+        // ldmia r1, {r1, r2} doesn't write back
+        assert_eq!(violations(0xe891_0006), Violations::empty());
+        // stmdb r5!, {r1, r2, r5, r7-sl, lr} stores the base after another register
+        assert_eq!(violations(0xe925_47a6), Violations::UNPREDICTABLE);
+        // stmia r1!, {r1, r2} stores it first
+        assert_eq!(violations(0xe8a1_0006), Violations::empty());
+    }
+
+    #[test]
+    fn register_lists() {
+        let empty = RegisterList::new(0);
+        assert_eq!(empty.registers().count(), 0);
+        assert_eq!(empty.to_string(), "{}");
+        let list = RegisterList::new(0x800f);
+        assert_eq!(list.registers().collect::<Vec<_>>(), [R0, R1, R2, R3, R15]);
+        assert_eq!(
+            RegisterList::new(0xffff).registers().collect::<Vec<_>>(),
+            (0..16)
+                .map(|i| Register::try_from(i).unwrap())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    fn misc(
+        destination: Register,
+        base: Register,
+        offset: MiscLoadAndStoreOffset,
+        add: bool,
+        indexing: Indexing,
+    ) -> LoadAndStoreMiscInstruction {
+        LoadAndStoreMiscInstruction {
+            destination,
+            adressing_mode: MiscLoadAndStoreAddressingMode {
+                base,
+                offset,
+                add,
+                indexing,
+            },
+        }
+    }
+
+    #[test]
+    fn halfword_and_doubleword_transfers() {
+        use LoadAndStoreInstruction::*;
+        use MiscLoadAndStoreOffset::*;
+        // ldrh r1, [r2, #0x34] splits the immediate over bits 8 to 11 and 0 to 3
+        assert_eq!(
+            op(0xe1d2_13b4),
+            Some(LoadAndStore(LDRH(misc(
+                R1,
+                R2,
+                Immediate(0x34),
+                true,
+                Indexing::Offset
+            ))))
+        );
+        // strh r1, [r2], -r3
+        assert_eq!(
+            op(0xe002_10b3),
+            Some(LoadAndStore(STRH(misc(
+                R1,
+                R2,
+                Register(R3),
+                false,
+                Indexing::PostIndexed
+            ))))
+        );
+        // ldrsb r4, [r5, #-1]!
+        assert_eq!(
+            op(0xe175_40d1),
+            Some(LoadAndStore(LDRSB(misc(
+                R4,
+                R5,
+                Immediate(1),
+                false,
+                Indexing::PreIndexed
+            ))))
+        );
+        // ldrsh r6, [r7, r8]
+        assert_eq!(
+            op(0xe197_60f8),
+            Some(LoadAndStore(LDRSH(misc(
+                R6,
+                R7,
+                Register(R8),
+                true,
+                Indexing::Offset
+            ))))
+        );
+        // ldrd r2, r3, [r4, #8]
+        assert_eq!(
+            op(0xe1c4_20d8),
+            Some(LoadAndStore(LDRD(misc(
+                R2,
+                R4,
+                Immediate(8),
+                true,
+                Indexing::Offset
+            ))))
+        );
+        // strd r4, r5, [r6], #-16
+        assert_eq!(
+            op(0xe046_41f0),
+            Some(LoadAndStore(STRD(misc(
+                R4,
+                R6,
+                Immediate(16),
+                false,
+                Indexing::PostIndexed
+            ))))
+        );
+        // ldrh r1, [r2, r3] with immedH set and strh r1, [r2], #0 with writeback
+        assert_eq!(violations(0xe192_13b3), Violations::SHOULD_BE_ZERO);
+        assert_eq!(violations(0xe0e2_10b0), Violations::SHOULD_BE_ZERO);
+    }
+
+    #[test]
+    fn halfword_and_doubleword_restrictions() {
+        assert_eq!(violations(0xe1d2_13b4), Violations::empty());
+        assert_eq!(violations(0xe1c4_20d8), Violations::empty());
+        // ldrh pc, [r2, #0x34]
+        assert_eq!(violations(0xe1d2_f3b4), Violations::UNPREDICTABLE);
+        // ldrh r1, [r1, #4]! writes back to the loaded register
+        assert_eq!(violations(0xe1f1_10b4), Violations::UNPREDICTABLE);
+        // ldrh r1, [pc, #4]! writes back to the PC
+        assert_eq!(violations(0xe1ff_10b4), Violations::UNPREDICTABLE);
+        // ldrsh r6, [r7, pc]
+        assert_eq!(violations(0xe197_60ff), Violations::UNPREDICTABLE);
+        // strh r1, [r2], r2 writes back with the base as offset
+        assert_eq!(violations(0xe082_10b2), Violations::UNPREDICTABLE);
+        // ldrd r3, r4, [r4, #8] has an odd first register
+        assert_eq!(violations(0xe1c4_30d8), Violations::UNPREDICTABLE);
+        // ldrd lr, pc, [r4, #8]
+        assert_eq!(violations(0xe1c4_e0d8), Violations::UNPREDICTABLE);
+        // ldrd r2, r3, [r2, #8]! writes back to a loaded register
+        assert_eq!(violations(0xe1e2_20d8), Violations::UNPREDICTABLE);
+        // ldrd r2, r3, [r4, r3] loads its offset, which strd may store
+        assert_eq!(violations(0xe184_20d3), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0xe184_20f3), Violations::empty());
+    }
+
+    #[test]
+    fn word_and_byte_restrictions() {
+        // ldr r1, [r1, #4] and ldr r0, [pc, #4] don't write back
+        assert_eq!(violations(0xe591_1004), Violations::empty());
+        assert_eq!(violations(0xe59f_0004), Violations::empty());
+        // ldr pc, [r0] is a branch
+        assert_eq!(violations(0xe590_f000), Violations::empty());
+        // ldr r1, [r1, #4]! and str r1, [r1], #4 write back to the transferred register
+        assert_eq!(violations(0xe5b1_1004), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0xe481_1004), Violations::UNPREDICTABLE);
+        // ldr r1, [pc, #4]! writes back to the PC
+        assert_eq!(violations(0xe5bf_1004), Violations::UNPREDICTABLE);
+        // ldr r0, [r1, pc] and ldr r0, [r1], r1
+        assert_eq!(violations(0xe791_000f), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0xe691_0001), Violations::UNPREDICTABLE);
+        // ldrb pc, [r0] and ldrt pc, [r0]
+        assert_eq!(violations(0xe5d0_f000), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0xe4b0_f000), Violations::UNPREDICTABLE);
     }
 }
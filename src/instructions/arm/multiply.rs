@@ -1,4 +1,10 @@
-use crate::instructions::Register;
+use std::fmt::{self, Display};
+
+use crate::errors::ParseError;
+use crate::instructions::{
+    check_bit, fmt_assembly, should_be_zero, split_with_range, Architecture, Assembly, Condition,
+    Register, Syntax,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MultiplyInstruction {
@@ -25,6 +31,7 @@ pub enum MultiplyInstruction {
         destination: Register,
         first_operand: Register,
         second_operand: Register,
+        add_operand: Register,
     },
     ///SMLAL <x><y>,Signed Multiply Accumulate Long. See SMLAL on page A4-146.
     SMLAL {
@@ -34,6 +41,8 @@ pub enum MultiplyInstruction {
         first_operand: Register,
         second_operand: Register,
     },
+    ///<x><y>
+    ///Signed halfword Multiply Accumulate Long. See SMLAL<x><y> on page A4-148.
     SMLAL2 {
         x: bool,
         y: bool,
@@ -46,9 +55,9 @@ pub enum MultiplyInstruction {
     SMLAW {
         y: bool,
         destination: Register,
-        source: Register,
         first_operand: Register,
         second_operand: Register,
+        add_operand: Register,
     },
     ///<x><y>
     ///Signed halfword Multiply. See SMUL<x><y> on page A4-166.
@@ -56,8 +65,8 @@ pub enum MultiplyInstruction {
         x: bool,
         y: bool,
         destination: Register,
-        high: Register,
-        low: Register,
+        first_operand: Register,
+        second_operand: Register,
     },
     ///Signed Multiply Long. See SMULL on page A4-168.
     SMULL {
@@ -71,8 +80,8 @@ pub enum MultiplyInstruction {
     SMULW {
         y: bool,
         destination: Register,
-        source: Register,
         first_operand: Register,
+        second_operand: Register,
     },
 
     ///Unsigned Multiply Accumulate Long. See UMLAL on page A4-249.
@@ -92,3 +101,678 @@ pub enum MultiplyInstruction {
         second_operand: Register,
     },
 }
+///The four register fields shared by all multiplies, in encoding order.
+fn registers(value: u32) -> Result<(Register, Register, Register, Register), ParseError> {
+    let (rd, value) = split_with_range(value, 16..=19);
+    let (rn, value) = split_with_range(value, 12..=15);
+    let (rs, value) = split_with_range(value, 8..=11);
+    let (rm, _) = split_with_range(value, 0..=3);
+    Ok((
+        Register::try_from(rd as u8)?,
+        Register::try_from(rn as u8)?,
+        Register::try_from(rs as u8)?,
+        Register::try_from(rm as u8)?,
+    ))
+}
+impl MultiplyInstruction {
+    ///Decodes the multiplies and long multiplies with bits 4 to 7 being `1001`.
+    ///
+    ///The first operand is Rm and the second Rs, the accumulator is Rn.
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        let (op, _) = split_with_range(value, 21..=23);
+        let s = check_bit(value, 20);
+        let (rd, rn, rs, rm) = registers(value)?;
+        use MultiplyInstruction::*;
+        Ok(match op {
+            0b000 => {
+                should_be_zero(value, 0b1111 << 12)?;
+                MUL {
+                    destination: rd,
+                    s,
+                    first_operand: rm,
+                    second_operand: rs,
+                }
+            }
+            0b001 => MLA {
+                destination: rd,
+                s,
+                first_operand: rm,
+                second_operand: rs,
+                add_operand: rn,
+            },
+            0b100 => UMULL {
+                s,
+                high: rd,
+                low: rn,
+                first_operand: rm,
+                second_operand: rs,
+            },
+            0b101 => UMLAL {
+                s,
+                high: rd,
+                low: rn,
+                first_operand: rm,
+                second_operand: rs,
+            },
+            0b110 => SMULL {
+                s,
+                high: rd,
+                low: rn,
+                first_operand: rm,
+                second_operand: rs,
+            },
+            0b111 => SMLAL {
+                s,
+                high: rd,
+                low: rn,
+                first_operand: rm,
+                second_operand: rs,
+            },
+            // UMAAL is ARMv6
+            _ => return Err(ParseError::Undefined(value)),
+        })
+    }
+    ///Decodes the signed halfword multiplies from the miscellaneous instruction space, with bit
+    ///7 set and bit 4 clear.
+    pub fn new_halfword(value: u32) -> Result<Self, ParseError> {
+        let (op, _) = split_with_range(value, 21..=22);
+        let x = check_bit(value, 5);
+        let y = check_bit(value, 6);
+        let (rd, rn, rs, rm) = registers(value)?;
+        use MultiplyInstruction::*;
+        Ok(match (op, x) {
+            (0b00, _) => SMLA {
+                x,
+                y,
+                destination: rd,
+                first_operand: rm,
+                second_operand: rs,
+                add_operand: rn,
+            },
+            (0b01, false) => SMLAW {
+                y,
+                destination: rd,
+                first_operand: rm,
+                second_operand: rs,
+                add_operand: rn,
+            },
+            (0b01, true) => {
+                should_be_zero(value, 0b1111 << 12)?;
+                SMULW {
+                    y,
+                    destination: rd,
+                    first_operand: rm,
+                    second_operand: rs,
+                }
+            }
+            (0b10, _) => SMLAL2 {
+                x,
+                y,
+                high: rd,
+                low: rn,
+                first_operand: rm,
+                second_operand: rs,
+            },
+            _ => {
+                should_be_zero(value, 0b1111 << 12)?;
+                SMUL {
+                    x,
+                    y,
+                    destination: rd,
+                    first_operand: rm,
+                    second_operand: rs,
+                }
+            }
+        })
+    }
+    ///Encodes the instruction without the condition.
+    pub fn encode(&self) -> u32 {
+        use MultiplyInstruction::*;
+        // the multiplies have bits 4 to 7 set to `1001`, the halfword ones sit in the
+        // miscellaneous space with bit 7 set and the halves in bits 5 and 6
+        let (fixed, rd, rn, rs, rm) = match *self {
+            MUL {
+                destination,
+                s,
+                first_operand,
+                second_operand,
+            } => (
+                (s as u32) << 20 | 0b1001 << 4,
+                destination,
+                Register::R0,
+                second_operand,
+                first_operand,
+            ),
+            MLA {
+                destination,
+                s,
+                first_operand,
+                second_operand,
+                add_operand,
+            } => (
+                0b001 << 21 | (s as u32) << 20 | 0b1001 << 4,
+                destination,
+                add_operand,
+                second_operand,
+                first_operand,
+            ),
+            UMULL {
+                s,
+                high,
+                low,
+                first_operand,
+                second_operand,
+            }
+            | UMLAL {
+                s,
+                high,
+                low,
+                first_operand,
+                second_operand,
+            }
+            | SMULL {
+                s,
+                high,
+                low,
+                first_operand,
+                second_operand,
+            }
+            | SMLAL {
+                s,
+                high,
+                low,
+                first_operand,
+                second_operand,
+            } => {
+                let op: u32 = match self {
+                    UMULL { .. } => 0b100,
+                    UMLAL { .. } => 0b101,
+                    SMULL { .. } => 0b110,
+                    _ => 0b111,
+                };
+                (
+                    op << 21 | (s as u32) << 20 | 0b1001 << 4,
+                    high,
+                    low,
+                    second_operand,
+                    first_operand,
+                )
+            }
+            SMLA {
+                x,
+                y,
+                destination,
+                first_operand,
+                second_operand,
+                add_operand,
+            } => (
+                0b0001_0000 << 20 | halves(x, y),
+                destination,
+                add_operand,
+                second_operand,
+                first_operand,
+            ),
+            SMLAW {
+                y,
+                destination,
+                first_operand,
+                second_operand,
+                add_operand,
+            } => (
+                0b0001_0010 << 20 | halves(false, y),
+                destination,
+                add_operand,
+                second_operand,
+                first_operand,
+            ),
+            SMULW {
+                y,
+                destination,
+                first_operand,
+                second_operand,
+            } => (
+                0b0001_0010 << 20 | halves(true, y),
+                destination,
+                Register::R0,
+                second_operand,
+                first_operand,
+            ),
+            SMLAL2 {
+                x,
+                y,
+                high,
+                low,
+                first_operand,
+                second_operand,
+            } => (
+                0b0001_0100 << 20 | halves(x, y),
+                high,
+                low,
+                second_operand,
+                first_operand,
+            ),
+            SMUL {
+                x,
+                y,
+                destination,
+                first_operand,
+                second_operand,
+            } => (
+                0b0001_0110 << 20 | halves(x, y),
+                destination,
+                Register::R0,
+                second_operand,
+                first_operand,
+            ),
+        };
+        fixed | (rd as u32) << 16 | (rn as u32) << 12 | (rs as u32) << 8 | rm as u32
+    }
+    ///Checks the operand restrictions: R15 can't be used and the long multiplies need RdHi and
+    ///RdLo to be distinct. Before ARMv6 Rd must also differ from Rm, and so must RdHi and RdLo.
+    pub fn is_unpredictable(&self, architecture: Architecture) -> bool {
+        use MultiplyInstruction::*;
+        let overlapping = architecture < Architecture::ARMv6;
+        let (destinations, operands): (&[Register], &[Register]) = match self {
+            MUL {
+                destination,
+                first_operand,
+                second_operand,
+                ..
+            } => {
+                if overlapping && destination == first_operand {
+                    return true;
+                }
+                (&[*destination], &[*first_operand, *second_operand])
+            }
+            MLA {
+                destination,
+                first_operand,
+                second_operand,
+                add_operand,
+                ..
+            } => {
+                if overlapping && destination == first_operand {
+                    return true;
+                }
+                (
+                    &[*destination],
+                    &[*first_operand, *second_operand, *add_operand],
+                )
+            }
+            SMLAL {
+                high,
+                low,
+                first_operand,
+                second_operand,
+                ..
+            }
+            | UMLAL {
+                high,
+                low,
+                first_operand,
+                second_operand,
+                ..
+            }
+            | SMULL {
+                high,
+                low,
+                first_operand,
+                second_operand,
+                ..
+            }
+            | UMULL {
+                high,
+                low,
+                first_operand,
+                second_operand,
+                ..
+            } => {
+                if high == low || overlapping && (high == first_operand || low == first_operand) {
+                    return true;
+                }
+                (&[*high, *low], &[*first_operand, *second_operand])
+            }
+            SMLAL2 {
+                high,
+                low,
+                first_operand,
+                second_operand,
+                ..
+            } => {
+                if high == low {
+                    return true;
+                }
+                (&[*high, *low], &[*first_operand, *second_operand])
+            }
+            SMLA {
+                destination,
+                first_operand,
+                second_operand,
+                add_operand,
+                ..
+            }
+            | SMLAW {
+                destination,
+                first_operand,
+                second_operand,
+                add_operand,
+                ..
+            } => (
+                &[*destination],
+                &[*first_operand, *second_operand, *add_operand],
+            ),
+            SMUL {
+                destination,
+                first_operand,
+                second_operand,
+                ..
+            }
+            | SMULW {
+                destination,
+                first_operand,
+                second_operand,
+                ..
+            } => (&[*destination], &[*first_operand, *second_operand]),
+        };
+        destinations
+            .iter()
+            .chain(operands)
+            .any(|r| *r == Register::R15)
+    }
+    ///The halfword multiplies are part of the DSP extension.
+    pub fn architecture(&self) -> Architecture {
+        use MultiplyInstruction::*;
+        match self {
+            SMLA { .. } | SMLAL2 { .. } | SMLAW { .. } | SMUL { .. } | SMULW { .. } => {
+                Architecture::ARMv5TE
+            }
+            _ => Architecture::ARMv4T,
+        }
+    }
+}
+///Bit 7 and the `<x>` and `<y>` bits of the halfword multiplies.
+fn halves(x: bool, y: bool) -> u32 {
+    1 << 7 | (y as u32) << 6 | (x as u32) << 5
+}
+///The `<x>` and `<y>` of the halfword multiplies, which select the top or bottom half.
+fn half(top: bool) -> char {
+    match top {
+        true => 't',
+        false => 'b',
+    }
+}
+impl Assembly for MultiplyInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        use MultiplyInstruction::*;
+        let s = |s: bool| if s { "s" } else { "" };
+        match *self {
+            MLA { s: set, .. } => syntax.conditional("mla", cond, s(set)),
+            MUL { s: set, .. } => syntax.conditional("mul", cond, s(set)),
+            SMLAL { s: set, .. } => syntax.conditional("smlal", cond, s(set)),
+            SMULL { s: set, .. } => syntax.conditional("smull", cond, s(set)),
+            UMLAL { s: set, .. } => syntax.conditional("umlal", cond, s(set)),
+            UMULL { s: set, .. } => syntax.conditional("umull", cond, s(set)),
+            SMLA { x, y, .. } => {
+                syntax.conditional(&format!("smla{}{}", half(x), half(y)), cond, "")
+            }
+            SMLAL2 { x, y, .. } => {
+                syntax.conditional(&format!("smlal{}{}", half(x), half(y)), cond, "")
+            }
+            SMUL { x, y, .. } => {
+                syntax.conditional(&format!("smul{}{}", half(x), half(y)), cond, "")
+            }
+            SMLAW { y, .. } => syntax.conditional(&format!("smlaw{}", half(y)), cond, ""),
+            SMULW { y, .. } => syntax.conditional(&format!("smulw{}", half(y)), cond, ""),
+        }
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        use MultiplyInstruction::*;
+        match self {
+            MUL {
+                destination,
+                first_operand,
+                second_operand,
+                ..
+            }
+            | SMUL {
+                destination,
+                first_operand,
+                second_operand,
+                ..
+            }
+            | SMULW {
+                destination,
+                first_operand,
+                second_operand,
+                ..
+            } => format!("{destination}, {first_operand}, {second_operand}"),
+            MLA {
+                destination,
+                first_operand,
+                second_operand,
+                add_operand,
+                ..
+            }
+            | SMLA {
+                destination,
+                first_operand,
+                second_operand,
+                add_operand,
+                ..
+            }
+            | SMLAW {
+                destination,
+                first_operand,
+                second_operand,
+                add_operand,
+                ..
+            } => format!("{destination}, {first_operand}, {second_operand}, {add_operand}"),
+            SMLAL {
+                high,
+                low,
+                first_operand,
+                second_operand,
+                ..
+            }
+            | SMLAL2 {
+                high,
+                low,
+                first_operand,
+                second_operand,
+                ..
+            }
+            | SMULL {
+                high,
+                low,
+                first_operand,
+                second_operand,
+                ..
+            }
+            | UMLAL {
+                high,
+                low,
+                first_operand,
+                second_operand,
+                ..
+            }
+            | UMULL {
+                high,
+                low,
+                first_operand,
+                second_operand,
+                ..
+            } => format!("{low}, {high}, {first_operand}, {second_operand}"),
+        }
+    }
+}
+impl Display for MultiplyInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::arm::tests::{op, violations};
+    use crate::instructions::arm::PartialArmInstruction::Multiply;
+    use crate::instructions::arm::{ArmInstruction, PartialArmInstruction};
+    use crate::instructions::Violations;
+    use MultiplyInstruction::*;
+    use Register::*;
+
+    #[test]
+    fn multiplies() {
+        // mul r0, r1, r2
+        assert_eq!(
+            op(0xe000_0291),
+            Some(Multiply(MUL {
+                destination: R0,
+                s: false,
+                first_operand: R1,
+                second_operand: R2
+            }))
+        );
+        // muls r3, r4, r5
+        assert_eq!(
+            op(0xe013_0594),
+            Some(Multiply(MUL {
+                destination: R3,
+                s: true,
+                first_operand: R4,
+                second_operand: R5
+            }))
+        );
+        // mla r0, r1, r2, r3
+        assert_eq!(
+            op(0xe020_3291),
+            Some(Multiply(MLA {
+                destination: R0,
+                s: false,
+                first_operand: R1,
+                second_operand: R2,
+                add_operand: R3
+            }))
+        );
+        // umull r0, r1, r2, r3
+        assert_eq!(
+            op(0xe081_0392),
+            Some(Multiply(UMULL {
+                s: false,
+                high: R1,
+                low: R0,
+                first_operand: R2,
+                second_operand: R3
+            }))
+        );
+        // umlal r0, r1, r2, r3
+        assert!(matches!(
+            op(0xe0a1_0392),
+            Some(Multiply(UMLAL { s: false, .. }))
+        ));
+        // smull r0, r1, r2, r3
+        assert!(matches!(
+            op(0xe0c1_0392),
+            Some(Multiply(SMULL { s: false, .. }))
+        ));
+        // smlals r4, r5, r6, r7
+        assert_eq!(
+            op(0xe0f5_4796),
+            Some(Multiply(SMLAL {
+                s: true,
+                high: R5,
+                low: R4,
+                first_operand: R6,
+                second_operand: R7
+            }))
+        );
+        // umaal is ARMv6
+        assert!(ArmInstruction::try_from(0xe041_0392).is_err());
+    }
+
+    #[test]
+    fn halfword_multiplies() {
+        // smlatb r0, r1, r2, r3
+        assert_eq!(
+            op(0xe100_32a1),
+            Some(Multiply(SMLA {
+                x: true,
+                y: false,
+                destination: R0,
+                first_operand: R1,
+                second_operand: R2,
+                add_operand: R3
+            }))
+        );
+        // smlawb r0, r1, r2, r3
+        assert_eq!(
+            op(0xe120_3281),
+            Some(Multiply(SMLAW {
+                y: false,
+                destination: R0,
+                first_operand: R1,
+                second_operand: R2,
+                add_operand: R3
+            }))
+        );
+        // smulwt r0, r1, r2
+        assert_eq!(
+            op(0xe120_02e1),
+            Some(Multiply(SMULW {
+                y: true,
+                destination: R0,
+                first_operand: R1,
+                second_operand: R2
+            }))
+        );
+        // smlalbt r0, r1, r2, r3
+        assert_eq!(
+            op(0xe141_03c2),
+            Some(Multiply(SMLAL2 {
+                x: false,
+                y: true,
+                high: R1,
+                low: R0,
+                first_operand: R2,
+                second_operand: R3
+            }))
+        );
+        // smultt r0, r1, r2
+        assert_eq!(
+            op(0xe160_02e1),
+            Some(Multiply(SMUL {
+                x: true,
+                y: true,
+                destination: R0,
+                first_operand: R1,
+                second_operand: R2
+            }))
+        );
+    }
+
+    #[test]
+    fn register_restrictions() {
+        assert_eq!(violations(0xe000_0291), Violations::empty());
+        // mul r0, r0, r1 has Rd equal to Rm
+        assert_eq!(violations(0xe000_0190), Violations::UNPREDICTABLE);
+        // mul pc, r1, r2
+        assert_eq!(violations(0xe00f_0291), Violations::UNPREDICTABLE);
+        // mla r0, r1, r2, pc
+        assert_eq!(violations(0xe020_f291), Violations::UNPREDICTABLE);
+        // umull r0, r0, r2, r3 has RdHi equal to RdLo
+        assert_eq!(violations(0xe080_0392), Violations::UNPREDICTABLE);
+        // umull r0, r1, r1, r3 has RdHi equal to Rm
+        assert_eq!(violations(0xe081_0391), Violations::UNPREDICTABLE);
+        // smlalbt r0, r0, r2, r3 has RdHi equal to RdLo
+        assert_eq!(violations(0xe140_03c2), Violations::UNPREDICTABLE);
+        // smultt r0, pc, r2
+        assert_eq!(violations(0xe160_02ef), Violations::UNPREDICTABLE);
+        // mul r0, r1, r2 with Rn set is a should be zero violation
+        assert_eq!(violations(0xe000_1291), Violations::SHOULD_BE_ZERO);
+        // ARMv6 allows Rd equal to Rm, but still not RdHi equal to RdLo
+        let v6 = |word| ArmInstruction::decode(word, Architecture::ARMv6).violations;
+        // muls r0, r0, r0
+        assert_eq!(v6(0xe010_0090), Violations::empty());
+        assert_eq!(v6(0xe081_0391), Violations::empty());
+        assert_eq!(v6(0xe080_0392), Violations::UNPREDICTABLE);
+    }
+}
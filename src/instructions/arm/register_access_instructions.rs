@@ -1,6 +1,13 @@
-use ux::u4;
+use std::fmt::{self, Display};
 
-use crate::instructions::{PSRFlags, Register, ShifterOperand};
+use ux::u5;
+
+use super::adresssing::ShifterOperand;
+use crate::errors::{EncodeError, ParseError};
+use crate::instructions::{
+    check_bit, fmt_assembly, should_be_one, should_be_zero, split_with_range, Architecture,
+    Assembly, Condition, PSRFlags, Register, Syntax,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RegisterAccessInstruction {
@@ -18,12 +25,222 @@ pub enum RegisterAccessInstruction {
     ///Change Processor State. Changes one or more of the processor mode and interrupt enable bits of the CPSR, without changing the other CPSR bits. See CPS on page A4-29.
     CPS {
         flags: Option<IFlags>,
-        mode: Option<u4>,
+        mode: Option<u5>,
     },
 }
+impl RegisterAccessInstruction {
+    ///Decodes MRS and MSR, `immediate` is the I bit which only MSR can have set.
+    pub fn new(value: u32, immediate: bool) -> Result<Self, ParseError> {
+        let is_spsr = check_bit(value, 22);
+        if !check_bit(value, 21) {
+            should_be_one(value, 0b1111 << 16)?;
+            should_be_zero(value, 0b1111_1111_1111)?;
+            let (rd, _) = split_with_range(value, 12..=15);
+            return Ok(Self::MRS {
+                destination: Register::try_from(rd as u8)?,
+                is_spsr,
+            });
+        }
+        should_be_one(value, 0b1111 << 12)?;
+        if !immediate {
+            should_be_zero(value, 0b1111 << 8)?;
+        }
+        Ok(Self::MSR {
+            flags: PSRFlags::new(value),
+            shifter_operand: ShifterOperand::new(value, immediate)?,
+            is_spsr,
+        })
+    }
+    ///Decodes CPS, `imod` selects whether the interrupt flags are enabled or disabled and `mmod`
+    ///whether the mode changes.
+    pub fn new_cps(value: u32) -> Result<Self, ParseError> {
+        should_be_zero(value, 0b111_1111 << 9)?;
+        let (imod, _) = split_with_range(value, 18..=19);
+        let flags = match imod {
+            0b00 => {
+                should_be_zero(value, 0b111 << 6)?;
+                None
+            }
+            0b01 => return Err(ParseError::Undefined(value)),
+            _ => Some(IFlags::new(value)),
+        };
+        let mode = match check_bit(value, 17) {
+            true => Some(u5::new(split_with_range(value, 0..=4).0 as u8)),
+            false => {
+                should_be_zero(value, 0b11111)?;
+                None
+            }
+        };
+        Ok(Self::CPS { flags, mode })
+    }
+    ///Encodes the instruction without the condition, CPS is only valid without a condition.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        Ok(match *self {
+            Self::MRS {
+                destination,
+                is_spsr,
+            } => {
+                0b0001_0000 << 20
+                    | (is_spsr as u32) << 22
+                    | 0b1111 << 16
+                    | (destination as u32) << 12
+            }
+            Self::MSR {
+                flags,
+                shifter_operand,
+                is_spsr,
+            } => {
+                let immediate = match shifter_operand {
+                    ShifterOperand::Immediate(_) => true,
+                    ShifterOperand::Register(_) => false,
+                    _ => return Err(EncodeError::Unencodable("MSR of a shifted register")),
+                };
+                (immediate as u32) << 25
+                    | 0b0001_0010 << 20
+                    | (is_spsr as u32) << 22
+                    | flags.encode()
+                    | 0b1111 << 12
+                    | shifter_operand.encode()?
+            }
+            Self::CPS { flags, mode } => {
+                let imod = match flags {
+                    Some(flags) => (0b10 | !flags.enable as u32) << 18 | flags.encode(),
+                    None => 0,
+                };
+                let mode = match mode {
+                    Some(mode) => 1 << 17 | u8::from(mode) as u32,
+                    None => 0,
+                };
+                0b0001_0000 << 20 | imod | mode
+            }
+        })
+    }
+    ///MRS into the PC and a CPS that changes nothing are unpredictable.
+    pub fn is_unpredictable(&self) -> bool {
+        matches!(
+            self,
+            Self::MRS {
+                destination: Register::R15,
+                ..
+            } | Self::CPS {
+                flags: None,
+                mode: None
+            }
+        )
+    }
+    ///CPS is ARMv6.
+    pub fn architecture(&self) -> Architecture {
+        match self {
+            Self::CPS { .. } => Architecture::ARMv6,
+            _ => Architecture::ARMv4T,
+        }
+    }
+}
+///The name of the status register.
+fn psr(is_spsr: bool) -> &'static str {
+    match is_spsr {
+        true => "spsr",
+        false => "cpsr",
+    }
+}
+impl Assembly for RegisterAccessInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        match self {
+            Self::MRS { .. } => syntax.conditional("mrs", cond, ""),
+            Self::MSR { .. } => syntax.conditional("msr", cond, ""),
+            // CPS is unconditional
+            Self::CPS {
+                flags: Some(flags), ..
+            } => match flags.enable {
+                true => "cpsie".to_string(),
+                false => "cpsid".to_string(),
+            },
+            Self::CPS { flags: None, .. } => "cps".to_string(),
+        }
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        match self {
+            Self::MRS {
+                destination,
+                is_spsr,
+            } => format!("{destination}, {}", psr(*is_spsr)),
+            Self::MSR {
+                flags,
+                shifter_operand,
+                is_spsr,
+            } => format!(
+                "{}_{flags}, {}",
+                psr(*is_spsr),
+                shifter_operand.operand(syntax)
+            ),
+            Self::CPS { flags, mode } => {
+                let flags = flags.map(|flags| flags.to_string());
+                let mode = mode.map(|mode| format!("#{mode}"));
+                flags
+                    .into_iter()
+                    .chain(mode)
+                    .filter(|operand| !operand.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        }
+    }
+}
+impl Display for RegisterAccessInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
+///The interrupt masks CPS changes, `enable` clears the selected masks and otherwise they are set.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct IFlags {
-    a: bool,
-    i: bool,
-    f: bool,
+    pub enable: bool,
+    pub a: bool,
+    pub i: bool,
+    pub f: bool,
+}
+impl IFlags {
+    ///Encodes the A, I and F bits.
+    pub fn encode(&self) -> u32 {
+        (self.a as u32) << 8 | (self.i as u32) << 7 | (self.f as u32) << 6
+    }
+    ///Decodes the low bit of `imod` and the A, I and F bits.
+    pub fn new(value: u32) -> Self {
+        Self {
+            enable: !check_bit(value, 18),
+            a: check_bit(value, 8),
+            i: check_bit(value, 7),
+            f: check_bit(value, 6),
+        }
+    }
+}
+///The `<iflags>` of CPS, like `if`.
+impl Display for IFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (set, flag) in [(self.a, 'a'), (self.i, 'i'), (self.f, 'f')] {
+            if set {
+                write!(f, "{flag}")?;
+            }
+        }
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::instructions::arm::ArmInstruction;
+
+    fn text(word: u32) -> String {
+        ArmInstruction::try_from(word).unwrap().to_string()
+    }
+
+    #[test]
+    fn rendering() {
+        assert_eq!(text(0xe14f_0000), "mrs r0, spsr");
+        assert_eq!(text(0xe129_f001), "msr cpsr_fc, r1");
+        assert_eq!(text(0xe328_f20f), "msr cpsr_f, #0xf0000000");
+        assert_eq!(text(0xf10a_018c), "cpsie ai, #12");
+        assert_eq!(text(0xf10c_0040), "cpsid f");
+        // msr cpsr, #0x1fc without fields is written as data
+        assert_eq!(text(0xe320_ff7f), ".word 0xe320ff7f");
+    }
 }
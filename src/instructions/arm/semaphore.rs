@@ -1,4 +1,10 @@
-use crate::instructions::Register;
+use std::fmt::{self, Display};
+
+use crate::errors::ParseError;
+use crate::instructions::{
+    check_bit, fmt_assembly, should_be_zero, split_with_range, Assembly, Condition, Register,
+    Syntax,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SemaphoreInstruction {
@@ -15,3 +21,142 @@ pub enum SemaphoreInstruction {
         mem: Register,
     },
 }
+impl SemaphoreInstruction {
+    ///Decodes SWP and SWPB, which sit in the multiply space with bit 24 set.
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        let (fixed, _) = split_with_range(value, 20..=23);
+        // bit 22 is B, the others are fixed
+        if fixed & 0b1011 != 0 {
+            return Err(ParseError::Undefined(value));
+        }
+        should_be_zero(value, 0b1111 << 8)?;
+        let (rn, value) = split_with_range(value, 16..=19);
+        let (rd, value) = split_with_range(value, 12..=15);
+        let (rm, _) = split_with_range(value, 0..=3);
+        let destination = Register::try_from(rd as u8)?;
+        let value = Register::try_from(rm as u8)?;
+        let mem = Register::try_from(rn as u8)?;
+        Ok(match fixed & 0b0100 != 0 {
+            true => Self::SWPB {
+                destination,
+                value,
+                mem,
+            },
+            false => Self::SWP {
+                destination,
+                value,
+                mem,
+            },
+        })
+    }
+    ///Encodes the instruction without the condition.
+    pub fn encode(&self) -> u32 {
+        let (Self::SWP {
+            destination,
+            value,
+            mem,
+        }
+        | Self::SWPB {
+            destination,
+            value,
+            mem,
+        }) = *self;
+        let byte = matches!(self, Self::SWPB { .. });
+        0b0001_0000 << 20
+            | (byte as u32) << 22
+            | (mem as u32) << 16
+            | (destination as u32) << 12
+            | 0b1001 << 4
+            | value as u32
+    }
+    ///Using R15, or the address register as value or destination, is unpredictable.
+    pub fn is_unpredictable(&self) -> bool {
+        let (Self::SWP {
+            destination,
+            value,
+            mem,
+        }
+        | Self::SWPB {
+            destination,
+            value,
+            mem,
+        }) = self;
+        [destination, value, mem].contains(&&Register::R15) || mem == value || mem == destination
+    }
+}
+impl Assembly for SemaphoreInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        match self {
+            Self::SWP { .. } => syntax.conditional("swp", cond, ""),
+            Self::SWPB { .. } => syntax.conditional("swp", cond, "b"),
+        }
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        let (Self::SWP {
+            destination,
+            value,
+            mem,
+        }
+        | Self::SWPB {
+            destination,
+            value,
+            mem,
+        }) = self;
+        format!("{destination}, {value}, [{mem}]")
+    }
+}
+impl Display for SemaphoreInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::arm::tests::{op, violations};
+    use crate::instructions::arm::{ArmInstruction, PartialArmInstruction};
+    use crate::instructions::{Architecture, Violations};
+    use Register::*;
+
+    #[test]
+    fn swaps() {
+        let op = |word| ArmInstruction::try_from(word).unwrap().op;
+        // swp r0, r1, [r2]
+        assert_eq!(
+            op(0xe102_0091),
+            PartialArmInstruction::Semaphore(SemaphoreInstruction::SWP {
+                destination: R0,
+                value: R1,
+                mem: R2
+            })
+        );
+        // swpb r3, r4, [r5]
+        assert_eq!(
+            op(0xe145_3094),
+            PartialArmInstruction::Semaphore(SemaphoreInstruction::SWPB {
+                destination: R3,
+                value: R4,
+                mem: R5
+            })
+        );
+        // bits 20, 21 and 23 are fixed
+        assert!(ArmInstruction::try_from(0xe112_0091).is_err());
+        assert!(ArmInstruction::try_from(0xe122_0091).is_err());
+    }
+
+    #[test]
+    fn swap_restrictions() {
+        assert_eq!(violations(0xe102_0091), Violations::empty());
+        // swp r0, r1, [r2] with bits 8 to 11 set
+        assert_eq!(violations(0xe102_0f91), Violations::SHOULD_BE_ZERO);
+        // swp pc, r1, [r2], swp r0, pc, [r2] and swp r0, r1, [pc]
+        assert_eq!(violations(0xe102_f091), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0xe102_009f), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0xe10f_0091), Violations::UNPREDICTABLE);
+        // swp r0, r2, [r2] and swpb r2, r1, [r2]
+        assert_eq!(violations(0xe102_0092), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0xe142_2091), Violations::UNPREDICTABLE);
+        // swp r0, r0, [r2] is the usual way of swapping a register
+        assert_eq!(violations(0xe102_0090), Violations::empty());
+    }
+}
@@ -1,50 +1,249 @@
-use crate::errors::ParseError;
-use crate::instructions::{CRegister, Coprocessor, Register};
+use std::fmt::{self, Display};
 
-use super::AddressingMode;
+use crate::errors::{EncodeError, ParseError};
+use crate::instructions::{
+    check_bit, fmt_assembly, should_be_one, split_with_range, Adress, Architecture, Assembly,
+    Condition, RelativeAdress, Syntax,
+};
 
+use super::adresssing::{Indexing, LoadAndStoreAddressingMode};
+use super::coprocessor::CoprocessorInstruction;
+use super::register_access_instructions::RegisterAccessInstruction;
+
+///The instructions with the condition field set to `1111`, see A3.16.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UnconditionalInstruction {
-    BLX(i32),
-    LDC {
-        coprocessor: Coprocessor,
-        long_load: bool,
-        destination: CRegister,
-        addressing_mode: AddressingMode,
-    },
-    MCR {
-        coprocessor: Coprocessor,
-        value: Register,
-        destination: CRegister,
-        additional_destination: CRegister,
-        opcode_1: u8,
-        opcode_2: u8,
-    },
-    MRC {
-        coprocessor: Coprocessor,
-        value: Register,
-        destination: CRegister,
-        additional_destination: CRegister,
-        opcode_1: u8,
-        opcode_2: u8,
-    },
-    MRRC {
-        coprocessor: Coprocessor,
-        opcode: u8,
-        first_register: Register,
-        second_register: Register,
-        destination: CRegister,
-    },
+    ///Branch with Link and Exchange to Thumb. See BLX (1) on page A4-16.
+    ///
+    ///The H bit is folded into the offset, which is therefore halfword aligned.
+    BLX(RelativeAdress),
+    ///CDP2, LDC2, STC2, MCR2 and MRC2, which are the coprocessor instructions without a
+    ///condition.
+    Coprocessor(CoprocessorInstruction),
     ///Pre load data. See PLD on page A4-90.
     PLD {
-        addressing_mode: AddressingMode,
+        addressing_mode: LoadAndStoreAddressingMode,
     },
+    ///CPS, which can only be unconditional.
+    RegisterAccess(RegisterAccessInstruction),
+}
+impl UnconditionalInstruction {
+    ///Resolves the Thumb target of BLX for the instruction at `adress`.
+    pub fn target(&self, adress: Adress) -> Option<Adress> {
+        match self {
+            Self::BLX(offset) => Some(offset.resolve(adress.arm_pc())),
+            _ => None,
+        }
+    }
+    ///Encodes the instruction without the condition.
+    pub fn encode(&self) -> Result<u32, EncodeError> {
+        Ok(match self {
+            // the H bit is the second bit of the offset
+            Self::BLX(offset) => {
+                let h = (offset.offset() >> 1 & 1) as u32;
+                let words = RelativeAdress::new(offset.offset() & !0b10);
+                0b101 << 25 | h << 24 | words.to_word_offset(24)?
+            }
+            Self::Coprocessor(coprocessor) => coprocessor.encode()?,
+            Self::PLD { addressing_mode } if addressing_mode.indexing != Indexing::Offset => {
+                return Err(EncodeError::Unencodable("PLD with writeback"))
+            }
+            Self::PLD { addressing_mode } => {
+                0b01 << 26 | 0b101 << 20 | 0b1111 << 12 | addressing_mode.encode()?
+            }
+            Self::RegisterAccess(register_access @ RegisterAccessInstruction::CPS { .. }) => {
+                register_access.encode()?
+            }
+            Self::RegisterAccess(_) => return Err(EncodeError::Condition(Condition::Never)),
+        })
+    }
+    ///Whether the operands make the instruction unpredictable.
+    pub fn is_unpredictable(&self) -> bool {
+        match self {
+            Self::Coprocessor(inst) => inst.is_unpredictable(),
+            Self::RegisterAccess(inst) => inst.is_unpredictable(),
+            _ => false,
+        }
+    }
+    ///The unconditional space is ARMv5, MCRR2, MRRC2 and CPS were added in ARMv6.
+    pub fn architecture(&self) -> Architecture {
+        match self {
+            Self::Coprocessor(
+                CoprocessorInstruction::MCRR(_) | CoprocessorInstruction::MRRC(_),
+            )
+            | Self::RegisterAccess(_) => Architecture::ARMv6,
+            _ => Architecture::ARMv5TE,
+        }
+    }
+}
+///The condition is part of the encoding, so the one passed in is ignored.
+impl Assembly for UnconditionalInstruction {
+    fn mnemonic(&self, _cond: Condition, syntax: Syntax) -> String {
+        match self {
+            Self::BLX(_) => "blx".to_string(),
+            Self::Coprocessor(coprocessor) => {
+                coprocessor.versioned_mnemonic(Condition::Allways, syntax, "2")
+            }
+            Self::PLD { .. } => "pld".to_string(),
+            Self::RegisterAccess(register_access) => {
+                register_access.mnemonic(Condition::Allways, syntax)
+            }
+        }
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        match self {
+            Self::BLX(offset) => offset.to_string(),
+            Self::Coprocessor(coprocessor) => coprocessor.operands(syntax),
+            Self::PLD { addressing_mode } => addressing_mode.to_string(),
+            Self::RegisterAccess(register_access) => register_access.operands(syntax),
+        }
+    }
+}
+impl Display for UnconditionalInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Never, f)
+    }
 }
 
 impl TryFrom<u32> for UnconditionalInstruction {
     type Error = ParseError;
 
     fn try_from(value: u32) -> Result<Self, Self::Error> {
-        todo!("unconditional")
+        let (switch, _) = split_with_range(value, 25..=27);
+        match switch {
+            // bit 16 set is SETEND, which isn't supported
+            0b000 if split_with_range(value, 20..=27).0 == 0b0001_0000 && !check_bit(value, 16) => {
+                if check_bit(value, 5) {
+                    return Err(ParseError::Undefined(value));
+                }
+                RegisterAccessInstruction::new_cps(value).map(Self::RegisterAccess)
+            }
+            0b010 | 0b011 => parse_pld(value),
+            0b101 => {
+                let (offset, _) = split_with_range(value, 0..=23);
+                let offset = RelativeAdress::from_word_offset(offset, 24).offset();
+                let h = check_bit(value, 24) as i32;
+                Ok(Self::BLX(RelativeAdress::new(offset | h << 1)))
+            }
+            0b110 => CoprocessorInstruction::new_load_store(value).map(Self::Coprocessor),
+            0b111 if !check_bit(value, 24) => {
+                CoprocessorInstruction::new(value, check_bit(value, 4)).map(Self::Coprocessor)
+            }
+            _ => Err(ParseError::Undefined(value)),
+        }
+    }
+}
+///PLD is encoded as an offset addressed LDRB with Rd set to R15.
+fn parse_pld(value: u32) -> Result<UnconditionalInstruction, ParseError> {
+    let register_offset = check_bit(value, 25);
+    // P, B and L set, W clear
+    let (fixed, _) = split_with_range(value & !(1 << 23), 20..=24);
+    if fixed != 0b10101 || (register_offset && check_bit(value, 4)) {
+        return Err(ParseError::Undefined(value));
+    }
+    should_be_one(value, 0b1111 << 12)?;
+    Ok(UnconditionalInstruction::PLD {
+        addressing_mode: LoadAndStoreAddressingMode::new(value, register_offset)?,
+    })
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::arm::adresssing::{LoadAndStoreOffset, Shift};
+    use crate::instructions::arm::tests::op;
+    use crate::instructions::arm::PartialArmInstruction::Unconditional;
+    use crate::instructions::arm::{ArmInstruction, PartialArmInstruction};
+    use crate::instructions::{Register, Violations};
+
+    #[test]
+    fn branch_with_link_and_exchange() {
+        // blx 0x100 at 0
+        assert_eq!(
+            op(0xfa00_003e),
+            Some(Unconditional(UnconditionalInstruction::BLX(
+                RelativeAdress::new(0xf8)
+            )))
+        );
+        assert_eq!(
+            ArmInstruction::try_from(0xfa00_003e)
+                .unwrap()
+                .target(Adress::new(0)),
+            Some(Adress::new(0x100))
+        );
+        // blx 0x102 at 0 sets the H bit
+        assert_eq!(
+            op(0xfb00_003e),
+            Some(Unconditional(UnconditionalInstruction::BLX(
+                RelativeAdress::new(0xfa)
+            )))
+        );
+        assert_eq!(
+            ArmInstruction::try_from(0xfb00_003e)
+                .unwrap()
+                .target(Adress::new(0)),
+            Some(Adress::new(0x102))
+        );
+        // blx 0x2 at 0x10, backwards
+        assert_eq!(
+            ArmInstruction::try_from(0xfbff_fffa)
+                .unwrap()
+                .target(Adress::new(0x10)),
+            Some(Adress::new(0x2))
+        );
+    }
+
+    #[test]
+    fn preload() {
+        // pld [r0, #4]
+        assert_eq!(
+            op(0xf5d0_f004),
+            Some(Unconditional(UnconditionalInstruction::PLD {
+                addressing_mode: LoadAndStoreAddressingMode {
+                    base: Register::R0,
+                    offset: LoadAndStoreOffset::Immediate(4),
+                    add: true,
+                    indexing: Indexing::Offset
+                }
+            }))
+        );
+        // pld [r1, -r2, lsl #2]
+        assert_eq!(
+            op(0xf751_f102),
+            Some(Unconditional(UnconditionalInstruction::PLD {
+                addressing_mode: LoadAndStoreAddressingMode {
+                    base: Register::R1,
+                    offset: LoadAndStoreOffset::ScaledRegister {
+                        register: Register::R2,
+                        shift: Shift::LSL(2)
+                    },
+                    add: false,
+                    indexing: Indexing::Offset
+                }
+            }))
+        );
+        // writeback, a load that isn't a byte and a register offset with bit 4 set are undefined
+        for word in [0xf5f0_f004, 0xf590_f004, 0xf751_f112] {
+            assert!(ArmInstruction::try_from(word).is_err(), "{word:#010x}");
+        }
+        // pld [r0, #4] with Rd not being the PC
+        let decoded = ArmInstruction::decode(0xf5d0_e004, Architecture::ARMv5TE);
+        assert_eq!(
+            decoded.instruction,
+            ArmInstruction::try_from(0xf5d0_f004).unwrap()
+        );
+        assert_eq!(decoded.violations, Violations::SHOULD_BE_ONE);
+    }
+
+    #[test]
+    fn undefined() {
+        // the data processing, multiple, SWI and SETEND spaces
+        for word in [0xf000_0000, 0xf800_0000, 0xff00_0000, 0xf101_0000] {
+            assert!(ArmInstruction::try_from(word).is_err(), "{word:#010x}");
+            let decoded = ArmInstruction::decode(word, Architecture::ARMv6);
+            assert!(matches!(
+                decoded.instruction.op,
+                PartialArmInstruction::Undefined(_)
+            ));
+        }
     }
 }
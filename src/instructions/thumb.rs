@@ -0,0 +1,961 @@
+//! The Thumb instruction set of chapter A7, the numbered forms like `ADD (3)` are the ones used
+//! there. The formats are numbered as in the ARM7TDMI data sheet.
+use std::fmt::{self, Display};
+use std::ops::RangeInclusive;
+
+use crate::errors::ParseError;
+use crate::instructions::{
+    check_bit, fmt_assembly, split_with_range, Adress, Architecture, Assembly, Condition, Decoded,
+    Register, RegisterList, Syntax, Violations,
+};
+use branch::BranchInstruction;
+use dataprosessing::{DataProssessingInstruction, Operand, Operands};
+use exception::ExceptiongeneratingInstruction;
+use loadandstore::{AdressingMode, LoadAndStoreInstruction, TransferInstruction};
+pub mod branch;
+pub mod dataprosessing;
+mod equivalent;
+pub mod exception;
+pub mod loadandstore;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThumbInstruction {
+    Branch(BranchInstruction),
+    DataProssessing(DataProssessingInstruction),
+    LoadAndStore(LoadAndStoreInstruction),
+    Exceptiongenerating(ExceptiongeneratingInstruction),
+    ///A halfword that doesn't encode an instruction.
+    Undefined(u16),
+}
+impl ThumbInstruction {
+    ///Whether the operands make the instruction unpredictable on `architecture`.
+    pub fn is_unpredictable(&self, architecture: Architecture) -> bool {
+        match self {
+            Self::Branch(branch) => branch.is_unpredictable(),
+            Self::DataProssessing(data) => data.is_unpredictable(architecture),
+            Self::LoadAndStore(load_and_store) => load_and_store.is_unpredictable(),
+            Self::Exceptiongenerating(_) | Self::Undefined(_) => false,
+        }
+    }
+    ///The first architecture version that has the instruction.
+    pub fn architecture(&self) -> Architecture {
+        match self {
+            Self::Branch(branch) => branch.architecture(),
+            Self::Exceptiongenerating(exception) => exception.architecture(),
+            Self::DataProssessing(_) | Self::LoadAndStore(_) | Self::Undefined(_) => {
+                Architecture::ARMv4T
+            }
+        }
+    }
+    ///Decodes any halfword without failing, the same way as
+    ///[`ArmInstruction::decode`](super::arm::ArmInstruction::decode).
+    pub fn decode(value: u16, architecture: Architecture) -> Decoded<ThumbInstruction> {
+        let mut violations = Violations::empty();
+        let mut half = value as u32;
+        loop {
+            match ThumbInstruction::try_from(half as u16) {
+                Ok(instruction) if instruction.architecture() > architecture => {
+                    return Decoded {
+                        instruction: ThumbInstruction::Undefined(value),
+                        violations: Violations::empty(),
+                    }
+                }
+                Ok(instruction) => {
+                    if instruction.is_unpredictable(architecture) {
+                        violations |= Violations::UNPREDICTABLE;
+                    }
+                    return Decoded {
+                        instruction,
+                        violations,
+                    };
+                }
+                Err(ParseError::ShouldBeZero(bits)) if half & bits != 0 => {
+                    half &= !bits;
+                    violations |= Violations::SHOULD_BE_ZERO;
+                }
+                Err(ParseError::ShouldBeOne(bits)) if !half & bits != 0 => {
+                    half |= bits;
+                    violations |= Violations::SHOULD_BE_ONE;
+                }
+                Err(_) => {
+                    return Decoded {
+                        instruction: ThumbInstruction::Undefined(value),
+                        violations: Violations::empty(),
+                    }
+                }
+            }
+        }
+    }
+}
+impl ThumbInstruction {
+    ///The size in bytes, the joined BL and BLX (1) are the only 4 byte instructions.
+    pub fn size(&self) -> u32 {
+        match self {
+            Self::Branch(BranchInstruction::BL(_) | BranchInstruction::BLXImmediate(_)) => 4,
+            _ => 2,
+        }
+    }
+    ///Decodes the instruction starting with `first`, which is joined with `second` if they are
+    ///the halves of BL or BLX (1).
+    pub fn decode_pair(
+        first: u16,
+        second: Option<u16>,
+        architecture: Architecture,
+    ) -> Decoded<ThumbInstruction> {
+        let decoded = ThumbInstruction::decode(first, architecture);
+        let suffix = second.map(|half| ThumbInstruction::decode(half, architecture));
+        match (decoded.instruction, suffix) {
+            (
+                ThumbInstruction::Branch(prefix),
+                Some(Decoded {
+                    instruction: ThumbInstruction::Branch(suffix),
+                    violations,
+                }),
+            ) => BranchInstruction::fuse(prefix, suffix).map(|branch| Decoded {
+                instruction: ThumbInstruction::Branch(branch),
+                violations: decoded.violations | violations,
+            }),
+            _ => None,
+        }
+        .unwrap_or(decoded)
+    }
+    ///Whether the instruction is a half of BL or BLX (1) that wasn't joined with the other one.
+    pub fn is_half(&self) -> bool {
+        matches!(self, Self::Branch(branch) if branch.is_half())
+    }
+    ///Resolves the target of a branch at `adress`.
+    pub fn target(&self, adress: Adress) -> Option<Adress> {
+        match self {
+            Self::Branch(branch) => branch.target(adress),
+            _ => None,
+        }
+    }
+    ///The address of the word loaded by LDR (3) at `adress`, which is relative to the word
+    ///aligned PC.
+    pub fn literal(&self, adress: Adress) -> Option<Adress> {
+        match self {
+            Self::LoadAndStore(LoadAndStoreInstruction::LDR(TransferInstruction {
+                adressing_mode:
+                    AdressingMode::Immediate {
+                        base: Register::R15,
+                        offset,
+                    },
+                ..
+            })) => Some(Adress::new(
+                adress
+                    .thumb_pc()
+                    .word_aligned()
+                    .value()
+                    .wrapping_add(*offset as u32),
+            )),
+            _ => None,
+        }
+    }
+}
+///Halfwords that aren't an instruction are written as data.
+impl Assembly for ThumbInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        match self {
+            Self::Branch(branch) => branch.mnemonic(cond, syntax),
+            Self::DataProssessing(data) => data.mnemonic(cond, syntax),
+            Self::LoadAndStore(load_and_store) => load_and_store.mnemonic(cond, syntax),
+            Self::Exceptiongenerating(exception) => exception.mnemonic(cond, syntax),
+            Self::Undefined(_) => syntax.halfword().to_string(),
+        }
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        match self {
+            Self::Branch(branch) => branch.operands(syntax),
+            Self::DataProssessing(data) => data.operands(syntax),
+            Self::LoadAndStore(load_and_store) => load_and_store.operands(syntax),
+            Self::Exceptiongenerating(exception) => exception.operands(syntax),
+            Self::Undefined(value) => format!("{value:#06x}"),
+        }
+    }
+}
+impl Display for ThumbInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
+///A decoded Thumb instruction at its address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamInstruction {
+    pub adress: Adress,
+    pub decoded: Decoded<ThumbInstruction>,
+    ///The absolute target of a branch or call.
+    pub target: Option<Adress>,
+}
+///The result of [`decode_stream`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThumbStream {
+    pub instructions: Vec<StreamInstruction>,
+    ///The addresses of BL and BLX (1) halves that aren't next to their other half. They are
+    ///kept in `instructions` as they are.
+    pub orphans: Vec<Adress>,
+}
+///Decodes consecutive halfwords starting at `base`, joining the halves of BL and BLX (1) into
+///one instruction.
+pub fn decode_stream(halfwords: &[u16], base: Adress, architecture: Architecture) -> ThumbStream {
+    let mut instructions = Vec::with_capacity(halfwords.len());
+    let mut orphans = Vec::new();
+    let mut index = 0;
+    while index < halfwords.len() {
+        let adress = Adress::new(base.value().wrapping_add(index as u32 * 2));
+        let decoded = ThumbInstruction::decode_pair(
+            halfwords[index],
+            halfwords.get(index + 1).copied(),
+            architecture,
+        );
+        if decoded.instruction.is_half() {
+            orphans.push(adress);
+        }
+        index += decoded.instruction.size() as usize / 2;
+        instructions.push(StreamInstruction {
+            adress,
+            decoded,
+            target: decoded.instruction.target(adress),
+        });
+    }
+    ThumbStream {
+        instructions,
+        orphans,
+    }
+}
+impl TryFrom<u16> for ThumbInstruction {
+    type Error = ParseError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        let value = value as u32;
+        let (switch, _) = split_with_range(value, 13..=15);
+        match switch {
+            0b000 => parse_0b000(value),
+            0b001 => parse_0b001(value),
+            0b010 => parse_0b010(value),
+            0b011 => parse_0b011(value),
+            0b100 => parse_0b100(value),
+            0b101 => parse_0b101(value),
+            0b110 => parse_0b110(value),
+            _ => parse_0b111(value),
+        }
+    }
+}
+///The register in the bits of `range`.
+fn register_at(value: u32, range: RangeInclusive<u32>) -> Result<Register, ParseError> {
+    let (register, _) = split_with_range(value, range);
+    Register::try_from(register as u8)
+}
+///Format 1, move shifted register, and format 2, add/subtract.
+fn parse_0b000(value: u32) -> Result<ThumbInstruction, ParseError> {
+    let (op, _) = split_with_range(value, 11..=12);
+    let destination = register_at(value, 0..=2)?;
+    let first_operand = register_at(value, 3..=5)?;
+    use DataProssessingInstruction::*;
+    let instruction = match op {
+        0b11 => {
+            let operands = match check_bit(value, 10) {
+                true => Operands::RegisterImmediate {
+                    destination,
+                    first_operand,
+                    immediate: split_with_range(value, 6..=8).0 as u16,
+                },
+                false => Operands::Registers {
+                    destination,
+                    first_operand,
+                    second_operand: register_at(value, 6..=8)?,
+                },
+            };
+            match check_bit(value, 9) {
+                true => SUB(operands),
+                false => ADD(operands),
+            }
+        }
+        op => {
+            let (amount, _) = split_with_range(value, 6..=10);
+            // LSR and ASR encode a shift by 32 as 0
+            let amount = match (op, amount) {
+                (0b00, amount) => amount,
+                (_, 0) => 32,
+                (_, amount) => amount,
+            };
+            let operands = Operands::RegisterImmediate {
+                destination,
+                first_operand,
+                immediate: amount as u16,
+            };
+            match op {
+                0b00 => LSL(operands),
+                0b01 => LSR(operands),
+                _ => ASR(operands),
+            }
+        }
+    };
+    Ok(ThumbInstruction::DataProssessing(instruction))
+}
+///Format 3, move/compare/add/subtract immediate.
+fn parse_0b001(value: u32) -> Result<ThumbInstruction, ParseError> {
+    let (op, _) = split_with_range(value, 11..=12);
+    let register = register_at(value, 8..=10)?;
+    let (immediate, _) = split_with_range(value, 0..=7);
+    let immediate = immediate as u8;
+    let operands = Operands::Immediate {
+        destination: register,
+        immediate,
+    };
+    use DataProssessingInstruction::*;
+    Ok(ThumbInstruction::DataProssessing(match op {
+        0b00 => MOV(operands),
+        0b01 => CMP {
+            first_operand: register,
+            second_operand: Operand::Immediate(immediate),
+        },
+        0b10 => ADD(operands),
+        _ => SUB(operands),
+    }))
+}
+///Format 4, ALU operations, format 5, hi register operations and branch exchange, format 6,
+///PC-relative load, and formats 7 and 8, load/store with register offset.
+fn parse_0b010(value: u32) -> Result<ThumbInstruction, ParseError> {
+    if check_bit(value, 12) {
+        let operation = match split_with_range(value, 9..=11).0 {
+            0b000 => LoadAndStoreInstruction::STR,
+            0b001 => LoadAndStoreInstruction::STRH,
+            0b010 => LoadAndStoreInstruction::STRB,
+            0b011 => LoadAndStoreInstruction::LDRSB,
+            0b100 => LoadAndStoreInstruction::LDR,
+            0b101 => LoadAndStoreInstruction::LDRH,
+            0b110 => LoadAndStoreInstruction::LDRB,
+            _ => LoadAndStoreInstruction::LDRSH,
+        };
+        return Ok(ThumbInstruction::LoadAndStore(operation(
+            TransferInstruction {
+                destination: register_at(value, 0..=2)?,
+                adressing_mode: AdressingMode::Register {
+                    base: register_at(value, 3..=5)?,
+                    offset: register_at(value, 6..=8)?,
+                },
+            },
+        )));
+    }
+    if check_bit(value, 11) {
+        let (offset, _) = split_with_range(value, 0..=7);
+        return Ok(ThumbInstruction::LoadAndStore(
+            LoadAndStoreInstruction::LDR(TransferInstruction {
+                destination: register_at(value, 8..=10)?,
+                adressing_mode: AdressingMode::Immediate {
+                    base: Register::R15,
+                    offset: (offset << 2) as u16,
+                },
+            }),
+        ));
+    }
+    if check_bit(value, 10) {
+        return parse_high_register(value);
+    }
+    DataProssessingInstruction::new_alu(value).map(ThumbInstruction::DataProssessing)
+}
+///Format 5, the operations that can use R8 to R15.
+fn parse_high_register(value: u32) -> Result<ThumbInstruction, ParseError> {
+    let (op, _) = split_with_range(value, 8..=9);
+    if op == 0b11 {
+        return BranchInstruction::new_exchange(value).map(ThumbInstruction::Branch);
+    }
+    let (low, _) = split_with_range(value, 0..=2);
+    let destination = Register::try_from((check_bit(value, 7) as u8) << 3 | low as u8)?;
+    let source = register_at(value, 3..=6)?;
+    let operands = Operands::Register {
+        destination,
+        source,
+    };
+    use DataProssessingInstruction::*;
+    Ok(ThumbInstruction::DataProssessing(match op {
+        0b00 => ADD(operands),
+        0b01 => CMP {
+            first_operand: destination,
+            second_operand: Operand::HighRegister(source),
+        },
+        _ => MOV(operands),
+    }))
+}
+///Format 9, load/store with immediate offset.
+fn parse_0b011(value: u32) -> Result<ThumbInstruction, ParseError> {
+    let byte = check_bit(value, 12);
+    let (offset, _) = split_with_range(value, 6..=10);
+    let transfer = TransferInstruction {
+        destination: register_at(value, 0..=2)?,
+        adressing_mode: AdressingMode::Immediate {
+            base: register_at(value, 3..=5)?,
+            offset: match byte {
+                true => offset,
+                false => offset << 2,
+            } as u16,
+        },
+    };
+    use LoadAndStoreInstruction::*;
+    Ok(ThumbInstruction::LoadAndStore(
+        match (check_bit(value, 11), byte) {
+            (true, true) => LDRB(transfer),
+            (true, false) => LDR(transfer),
+            (false, true) => STRB(transfer),
+            (false, false) => STR(transfer),
+        },
+    ))
+}
+///Format 10, load/store halfword, and format 11, SP-relative load/store.
+fn parse_0b100(value: u32) -> Result<ThumbInstruction, ParseError> {
+    let transfer = match check_bit(value, 12) {
+        true => TransferInstruction {
+            destination: register_at(value, 8..=10)?,
+            adressing_mode: AdressingMode::Immediate {
+                base: Register::R13,
+                offset: (split_with_range(value, 0..=7).0 << 2) as u16,
+            },
+        },
+        false => TransferInstruction {
+            destination: register_at(value, 0..=2)?,
+            adressing_mode: AdressingMode::Immediate {
+                base: register_at(value, 3..=5)?,
+                offset: (split_with_range(value, 6..=10).0 << 1) as u16,
+            },
+        },
+    };
+    use LoadAndStoreInstruction::*;
+    Ok(ThumbInstruction::LoadAndStore(
+        match (check_bit(value, 12), check_bit(value, 11)) {
+            (true, true) => LDR(transfer),
+            (true, false) => STR(transfer),
+            (false, true) => LDRH(transfer),
+            (false, false) => STRH(transfer),
+        },
+    ))
+}
+///Format 12, load address, format 13, add offset to SP, format 14, push/pop registers and BKPT.
+fn parse_0b101(value: u32) -> Result<ThumbInstruction, ParseError> {
+    use DataProssessingInstruction::*;
+    if !check_bit(value, 12) {
+        let (immediate, _) = split_with_range(value, 0..=7);
+        return Ok(ThumbInstruction::DataProssessing(ADD(
+            Operands::RegisterImmediate {
+                destination: register_at(value, 8..=10)?,
+                first_operand: match check_bit(value, 11) {
+                    true => Register::R13,
+                    false => Register::R15,
+                },
+                immediate: (immediate << 2) as u16,
+            },
+        )));
+    }
+    let (switch, _) = split_with_range(value, 8..=11);
+    match switch {
+        0b0000 => {
+            let (immediate, _) = split_with_range(value, 0..=6);
+            let operands = Operands::RegisterImmediate {
+                destination: Register::R13,
+                first_operand: Register::R13,
+                immediate: (immediate << 2) as u16,
+            };
+            Ok(ThumbInstruction::DataProssessing(
+                match check_bit(value, 7) {
+                    true => SUB(operands),
+                    false => ADD(operands),
+                },
+            ))
+        }
+        0b0100 | 0b0101 | 0b1100 | 0b1101 => {
+            let (registers, _) = split_with_range(value, 0..=7);
+            let load = check_bit(value, 11);
+            // R adds LR to a push and PC to a pop
+            let extra = match (check_bit(value, 8), load) {
+                (false, _) => 0,
+                (true, true) => 1 << 15,
+                (true, false) => 1 << 14,
+            };
+            let registers = RegisterList::new(registers as u16 | extra);
+            Ok(ThumbInstruction::LoadAndStore(match load {
+                true => LoadAndStoreInstruction::POP(registers),
+                false => LoadAndStoreInstruction::PUSH(registers),
+            }))
+        }
+        0b1110 => Ok(ThumbInstruction::Exceptiongenerating(
+            ExceptiongeneratingInstruction::BKPT(split_with_range(value, 0..=7).0 as u8),
+        )),
+        // the ARMv6 extensions
+        _ => Err(ParseError::Undefined(value)),
+    }
+}
+///Format 15, multiple load/store, format 16, conditional branch, and format 17, software
+///interrupt.
+fn parse_0b110(value: u32) -> Result<ThumbInstruction, ParseError> {
+    if !check_bit(value, 12) {
+        let (registers, _) = split_with_range(value, 0..=7);
+        let base = register_at(value, 8..=10)?;
+        let registers = RegisterList::new(registers as u16);
+        return Ok(ThumbInstruction::LoadAndStore(match check_bit(value, 11) {
+            true => LoadAndStoreInstruction::LDMIA { base, registers },
+            false => LoadAndStoreInstruction::STMIA { base, registers },
+        }));
+    }
+    let (cond, _) = split_with_range(value, 8..=11);
+    match Condition::new(cond) {
+        Condition::Never => Ok(ThumbInstruction::Exceptiongenerating(
+            ExceptiongeneratingInstruction::SWI(split_with_range(value, 0..=7).0 as u8),
+        )),
+        Condition::Allways => Err(ParseError::Undefined(value)),
+        cond => Ok(ThumbInstruction::Branch(
+            BranchInstruction::new_conditional(value, cond),
+        )),
+    }
+}
+///Format 18, unconditional branch, and format 19, long branch with link.
+fn parse_0b111(value: u32) -> Result<ThumbInstruction, ParseError> {
+    BranchInstruction::new(value).map(ThumbInstruction::Branch)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::RelativeAdress;
+    use DataProssessingInstruction::*;
+    use LoadAndStoreInstruction::*;
+    use Register::*;
+
+    fn decode(half: u16) -> ThumbInstruction {
+        ThumbInstruction::try_from(half).unwrap()
+    }
+    fn data(instruction: DataProssessingInstruction) -> ThumbInstruction {
+        ThumbInstruction::DataProssessing(instruction)
+    }
+    fn transfer(
+        operation: fn(TransferInstruction) -> LoadAndStoreInstruction,
+        destination: Register,
+        adressing_mode: AdressingMode,
+    ) -> ThumbInstruction {
+        ThumbInstruction::LoadAndStore(operation(TransferInstruction {
+            destination,
+            adressing_mode,
+        }))
+    }
+    fn violations(half: u16) -> Violations {
+        ThumbInstruction::decode(half, Architecture::ARMv5TE).violations
+    }
+
+    #[test]
+    fn register_formats() {
+        // format 1, lsl r1, r2, #3 and asr r0, r1, #32
+        assert_eq!(
+            decode(0x00d1),
+            data(LSL(Operands::RegisterImmediate {
+                destination: R1,
+                first_operand: R2,
+                immediate: 3
+            }))
+        );
+        assert_eq!(
+            decode(0x1008),
+            data(ASR(Operands::RegisterImmediate {
+                destination: R0,
+                first_operand: R1,
+                immediate: 32
+            }))
+        );
+        // format 2, add r0, r1, r2 and sub r3, r4, #5
+        assert_eq!(
+            decode(0x1888),
+            data(ADD(Operands::Registers {
+                destination: R0,
+                first_operand: R1,
+                second_operand: R2
+            }))
+        );
+        assert_eq!(
+            decode(0x1f63),
+            data(SUB(Operands::RegisterImmediate {
+                destination: R3,
+                first_operand: R4,
+                immediate: 5
+            }))
+        );
+        // format 3, mov r5, #200 and cmp r6, #7
+        assert_eq!(
+            decode(0x25c8),
+            data(MOV(Operands::Immediate {
+                destination: R5,
+                immediate: 200
+            }))
+        );
+        assert_eq!(
+            decode(0x2e07),
+            data(CMP {
+                first_operand: R6,
+                second_operand: Operand::Immediate(7)
+            })
+        );
+        // format 4, eor r0, r1 and mul r2, r3
+        assert_eq!(
+            decode(0x4048),
+            data(EOR(Operands::Register {
+                destination: R0,
+                source: R1
+            }))
+        );
+        assert_eq!(
+            decode(0x435a),
+            data(MUL(Operands::Register {
+                destination: R2,
+                source: R3
+            }))
+        );
+        // format 5, add r8, r1, cmp r0, r9 and mov pc, lr
+        assert_eq!(
+            decode(0x4488),
+            data(ADD(Operands::Register {
+                destination: R8,
+                source: R1
+            }))
+        );
+        assert_eq!(
+            decode(0x4548),
+            data(CMP {
+                first_operand: R0,
+                second_operand: Operand::HighRegister(R9)
+            })
+        );
+        assert_eq!(
+            decode(0x46f7),
+            data(MOV(Operands::Register {
+                destination: R15,
+                source: R14
+            }))
+        );
+        // format 5, bx lr and blx r3
+        assert_eq!(
+            decode(0x4770),
+            ThumbInstruction::Branch(BranchInstruction::BX(R14))
+        );
+        assert_eq!(
+            decode(0x4798),
+            ThumbInstruction::Branch(BranchInstruction::BLX(R3))
+        );
+    }
+
+    #[test]
+    fn load_and_store_formats() {
+        // format 6, ldr r2, [pc, #16]
+        assert_eq!(
+            decode(0x4a04),
+            transfer(
+                LDR,
+                R2,
+                AdressingMode::Immediate {
+                    base: R15,
+                    offset: 16
+                }
+            )
+        );
+        // format 7, str r0, [r1, r2]
+        assert_eq!(
+            decode(0x5088),
+            transfer(
+                STR,
+                R0,
+                AdressingMode::Register {
+                    base: R1,
+                    offset: R2
+                }
+            )
+        );
+        // format 8, ldrsh r3, [r4, r5]
+        assert_eq!(
+            decode(0x5f63),
+            transfer(
+                LDRSH,
+                R3,
+                AdressingMode::Register {
+                    base: R4,
+                    offset: R5
+                }
+            )
+        );
+        // format 9, ldrb r0, [r1, #31] and str r2, [r3, #124]
+        assert_eq!(
+            decode(0x7fc8),
+            transfer(
+                LDRB,
+                R0,
+                AdressingMode::Immediate {
+                    base: R1,
+                    offset: 31
+                }
+            )
+        );
+        assert_eq!(
+            decode(0x67da),
+            transfer(
+                STR,
+                R2,
+                AdressingMode::Immediate {
+                    base: R3,
+                    offset: 124
+                }
+            )
+        );
+        // format 10, strh r1, [r2, #62]
+        assert_eq!(
+            decode(0x87d1),
+            transfer(
+                STRH,
+                R1,
+                AdressingMode::Immediate {
+                    base: R2,
+                    offset: 62
+                }
+            )
+        );
+        // format 11, ldr r0, [sp, #1020]
+        assert_eq!(
+            decode(0x98ff),
+            transfer(
+                LDR,
+                R0,
+                AdressingMode::Immediate {
+                    base: R13,
+                    offset: 1020
+                }
+            )
+        );
+        // format 14, push {r0, r1, lr} and pop {r4, pc}
+        assert_eq!(
+            decode(0xb503),
+            ThumbInstruction::LoadAndStore(PUSH(RegisterList::new(0x4003)))
+        );
+        assert_eq!(
+            decode(0xbd10),
+            ThumbInstruction::LoadAndStore(POP(RegisterList::new(0x8010)))
+        );
+        // format 15, stmia r0!, {r1, r2} and ldmia r3!, {r4}
+        assert_eq!(
+            decode(0xc006),
+            ThumbInstruction::LoadAndStore(STMIA {
+                base: R0,
+                registers: RegisterList::new(0b110)
+            })
+        );
+        assert_eq!(
+            decode(0xcb10),
+            ThumbInstruction::LoadAndStore(LDMIA {
+                base: R3,
+                registers: RegisterList::REGISTER4
+            })
+        );
+    }
+
+    #[test]
+    fn address_and_stack_formats() {
+        // format 12, add r1, pc, #8 and add r2, sp, #1020
+        assert_eq!(
+            decode(0xa102),
+            data(ADD(Operands::RegisterImmediate {
+                destination: R1,
+                first_operand: R15,
+                immediate: 8
+            }))
+        );
+        assert_eq!(
+            decode(0xaaff),
+            data(ADD(Operands::RegisterImmediate {
+                destination: R2,
+                first_operand: R13,
+                immediate: 1020
+            }))
+        );
+        // format 13, sub sp, #508
+        assert_eq!(
+            decode(0xb0ff),
+            data(SUB(Operands::RegisterImmediate {
+                destination: R13,
+                first_operand: R13,
+                immediate: 508
+            }))
+        );
+        // bkpt 0xab
+        assert_eq!(
+            decode(0xbeab),
+            ThumbInstruction::Exceptiongenerating(ExceptiongeneratingInstruction::BKPT(0xab))
+        );
+    }
+
+    #[test]
+    fn branch_formats() {
+        // format 16, beq .
+        assert_eq!(
+            decode(0xd0fe),
+            ThumbInstruction::Branch(BranchInstruction::BCond {
+                cond: Condition::Equal,
+                offset: RelativeAdress::new(-4)
+            })
+        );
+        // format 17, swi 0xff
+        assert_eq!(
+            decode(0xdfff),
+            ThumbInstruction::Exceptiongenerating(ExceptiongeneratingInstruction::SWI(0xff))
+        );
+        // format 18, b . + 2050
+        assert_eq!(
+            decode(0xe3ff),
+            ThumbInstruction::Branch(BranchInstruction::B(RelativeAdress::new(2046)))
+        );
+        // format 19, bl . + 0x1004 and blx . + 0x1004
+        assert_eq!(
+            decode(0xf001),
+            ThumbInstruction::Branch(BranchInstruction::BLPrefix(RelativeAdress::new(0x1000)))
+        );
+        assert_eq!(
+            decode(0xf800),
+            ThumbInstruction::Branch(BranchInstruction::BLSuffix(0))
+        );
+        assert_eq!(
+            decode(0xe800),
+            ThumbInstruction::Branch(BranchInstruction::BLXSuffix(0))
+        );
+        let pair = |second| {
+            ThumbInstruction::decode_pair(0xf001, Some(second), Architecture::ARMv5TE).instruction
+        };
+        assert_eq!(
+            pair(0xf800),
+            ThumbInstruction::Branch(BranchInstruction::BL(RelativeAdress::new(0x1000)))
+        );
+        assert_eq!(
+            pair(0xe800),
+            ThumbInstruction::Branch(BranchInstruction::BLXImmediate(RelativeAdress::new(0x1000)))
+        );
+    }
+
+    #[test]
+    fn violations_and_architectures() {
+        // bx lr with an SBZ bit set
+        let decoded = ThumbInstruction::decode(0x4771, Architecture::ARMv5TE);
+        assert_eq!(
+            decoded.instruction,
+            ThumbInstruction::Branch(BranchInstruction::BX(R14))
+        );
+        assert_eq!(decoded.violations, Violations::SHOULD_BE_ZERO);
+        // blx r3 and bkpt 0xab are ARMv5
+        for half in [0x4798, 0xbeab] {
+            let decoded = ThumbInstruction::decode(half, Architecture::ARMv4T);
+            assert_eq!(decoded.instruction, ThumbInstruction::Undefined(half));
+            assert_ne!(
+                ThumbInstruction::decode(half, Architecture::ARMv5TE).instruction,
+                ThumbInstruction::Undefined(half)
+            );
+        }
+        // blx pc and mul r2, r2
+        assert_eq!(violations(0x47f8), Violations::UNPREDICTABLE);
+        assert_eq!(violations(0x4352), Violations::UNPREDICTABLE);
+        // add r0, r1, cmp r0, r1 and mov r0, r1 as hi register operations on low registers
+        for half in [0x4408, 0x4508, 0x4608] {
+            assert_eq!(violations(half), Violations::UNPREDICTABLE, "{half:#06x}");
+        }
+        // cmp r0, r1 as format 4 and add r8, r1
+        assert_eq!(violations(0x4288), Violations::empty());
+        assert_eq!(violations(0x4488), Violations::empty());
+        // ARMv6 allows all of them
+        for half in [0x4352, 0x4408, 0x4508, 0x4608] {
+            let decoded = ThumbInstruction::decode(half, Architecture::ARMv6);
+            assert_eq!(decoded.violations, Violations::empty(), "{half:#06x}");
+        }
+    }
+
+    #[test]
+    fn undefined_halfwords() {
+        // b (1) with the AL condition, blx (1) with an odd offset and the ARMv6 extensions
+        for half in [0xde00, 0xe801, 0xb200, 0xb650] {
+            assert!(ThumbInstruction::try_from(half).is_err(), "{half:#06x}");
+            assert_eq!(
+                ThumbInstruction::decode(half, Architecture::ARMv6).instruction,
+                ThumbInstruction::Undefined(half)
+            );
+        }
+    }
+
+    #[test]
+    fn fusing_halves() {
+        use BranchInstruction::*;
+        let offset = RelativeAdress::new;
+        assert_eq!(
+            BranchInstruction::fuse(BLPrefix(offset(0x1000)), BLSuffix(0x10)),
+            Some(BL(offset(0x1010)))
+        );
+        assert_eq!(
+            BranchInstruction::fuse(BLPrefix(offset(-0x1000)), BLXSuffix(0xffc)),
+            Some(BLXImmediate(offset(-4)))
+        );
+        assert_eq!(BranchInstruction::fuse(BLPrefix(offset(0)), BX(R14)), None);
+        assert_eq!(BranchInstruction::fuse(BLSuffix(0), BLSuffix(0)), None);
+        assert_eq!(
+            BranchInstruction::fuse(BLPrefix(offset(0)), BLPrefix(offset(0))),
+            None
+        );
+    }
+
+    #[test]
+    fn stream_targets() {
+        // movs r0, #0, bl 0x2004, blx 0x2004 and bl .
+        let halfwords = [0x2000, 0xf001, 0xf800, 0xf001, 0xe800, 0xf7ff, 0xfffe];
+        let stream = decode_stream(&halfwords, Adress::new(0x1000), Architecture::ARMv5TE);
+        assert!(stream.orphans.is_empty());
+        let adresses: Vec<_> = stream
+            .instructions
+            .iter()
+            .map(|line| line.adress.value())
+            .collect();
+        assert_eq!(adresses, [0x1000, 0x1002, 0x1006, 0x100a]);
+        let targets: Vec<_> = stream
+            .instructions
+            .iter()
+            .map(|line| line.target.map(Adress::value))
+            .collect();
+        // BLX (1) at 0x1006 clears bit 1 of 0x200a
+        assert_eq!(targets, [None, Some(0x2006), Some(0x2008), Some(0x100a)]);
+        assert_eq!(stream.instructions[1].decoded.instruction.size(), 4);
+    }
+
+    #[test]
+    fn stream_orphans() {
+        // a suffix on its own, a prefix followed by a prefix, bl and a prefix at the end
+        let halfwords = [0xf800, 0xf001, 0xf001, 0xf800, 0xf001];
+        let stream = decode_stream(&halfwords, Adress::new(0x1000), Architecture::ARMv5TE);
+        let orphans: Vec<_> = stream.orphans.iter().map(|adress| adress.value()).collect();
+        assert_eq!(orphans, [0x1000, 0x1002, 0x1008]);
+        let instructions: Vec<_> = stream
+            .instructions
+            .iter()
+            .map(|line| (line.adress.value(), line.decoded.instruction))
+            .collect();
+        use BranchInstruction::*;
+        assert_eq!(
+            instructions,
+            [
+                (0x1000, ThumbInstruction::Branch(BLSuffix(0))),
+                (
+                    0x1002,
+                    ThumbInstruction::Branch(BLPrefix(RelativeAdress::new(0x1000)))
+                ),
+                (
+                    0x1004,
+                    ThumbInstruction::Branch(BL(RelativeAdress::new(0x1000)))
+                ),
+                (
+                    0x1008,
+                    ThumbInstruction::Branch(BLPrefix(RelativeAdress::new(0x1000)))
+                ),
+            ]
+        );
+        // the halves have no target and BLX (1) halves are ARMv5
+        assert_eq!(stream.instructions[0].target, None);
+        let stream = decode_stream(&[0xf001, 0xe800], Adress::new(0), Architecture::ARMv4T);
+        assert_eq!(stream.orphans, [Adress::new(0)]);
+        assert_eq!(
+            stream.instructions[1].decoded.instruction,
+            ThumbInstruction::Undefined(0xe800)
+        );
+    }
+}
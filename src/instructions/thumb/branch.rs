@@ -0,0 +1,148 @@
+use std::fmt::{self, Display};
+
+use crate::errors::ParseError;
+use crate::instructions::{
+    check_bit, fmt_assembly, should_be_zero, split_with_range, Adress, Architecture, Assembly,
+    Condition, Register, RelativeAdress, Syntax,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchInstruction {
+    ///B (1), the only conditional Thumb instruction.
+    BCond {
+        cond: Condition,
+        offset: RelativeAdress,
+    },
+    ///B (2).
+    B(RelativeAdress),
+    ///The first half of BL and BLX (1), which adds the high part of the offset to the PC and
+    ///stores it in LR.
+    BLPrefix(RelativeAdress),
+    ///The second half of BL, the low part of the offset in bytes.
+    BLSuffix(u16),
+    ///The second half of BLX (1), the low part of the offset in bytes.
+    BLXSuffix(u16),
+    ///BL with both halves, the offset is relative to the PC of the first half.
+    BL(RelativeAdress),
+    ///BLX (1) with both halves, the offset is relative to the PC of the first half.
+    BLXImmediate(RelativeAdress),
+    ///BLX (2).
+    BLX(Register),
+    ///BX.
+    BX(Register),
+}
+impl BranchInstruction {
+    ///Decodes B (1), the 8 bit offset is sign extended and converted to bytes.
+    pub fn new_conditional(value: u32, cond: Condition) -> Self {
+        let (offset, _) = split_with_range(value, 0..=7);
+        Self::BCond {
+            cond,
+            offset: RelativeAdress::from_halfword_offset(offset, 8),
+        }
+    }
+    ///Decodes B (2) and the halves of BL and BLX (1), which are selected by bits 11 and 12.
+    pub fn new(value: u32) -> Result<Self, ParseError> {
+        let (offset, _) = split_with_range(value, 0..=10);
+        let (switch, _) = split_with_range(value, 11..=12);
+        Ok(match switch {
+            0b00 => Self::B(RelativeAdress::from_halfword_offset(offset, 11)),
+            0b01 => {
+                // the target of BLX has to be word aligned
+                if check_bit(offset, 0) {
+                    return Err(ParseError::Undefined(value));
+                }
+                Self::BLXSuffix((offset << 1) as u16)
+            }
+            0b10 => Self::BLPrefix(RelativeAdress::new(
+                RelativeAdress::from_halfword_offset(offset, 11).offset() << 11,
+            )),
+            _ => Self::BLSuffix((offset << 1) as u16),
+        })
+    }
+    ///Joins the halves of BL and BLX (1), `None` if they don't belong together.
+    pub fn fuse(prefix: Self, suffix: Self) -> Option<Self> {
+        let offset = match prefix {
+            Self::BLPrefix(offset) => offset.offset(),
+            _ => return None,
+        };
+        match suffix {
+            Self::BLSuffix(low) => Some(Self::BL(RelativeAdress::new(offset + low as i32))),
+            Self::BLXSuffix(low) => {
+                Some(Self::BLXImmediate(RelativeAdress::new(offset + low as i32)))
+            }
+            _ => None,
+        }
+    }
+    ///Decodes BX and BLX (2), bit 7 selects the link.
+    pub fn new_exchange(value: u32) -> Result<Self, ParseError> {
+        should_be_zero(value, 0b111)?;
+        let (rm, _) = split_with_range(value, 3..=6);
+        let rm = Register::try_from(rm as u8)?;
+        Ok(match check_bit(value, 7) {
+            true => Self::BLX(rm),
+            false => Self::BX(rm),
+        })
+    }
+    ///Resolves the target of B, BL and BLX (1) for the instruction at `adress`, taking the PC
+    ///being 4 bytes ahead into account. BLX switches to ARM state, so its target is word
+    ///aligned. The halves of BL and BLX have no target on their own.
+    pub fn target(&self, adress: Adress) -> Option<Adress> {
+        match self {
+            Self::BCond { offset, .. } | Self::B(offset) | Self::BL(offset) => {
+                Some(offset.resolve(adress.thumb_pc()))
+            }
+            Self::BLXImmediate(offset) => Some(offset.resolve(adress.thumb_pc()).word_aligned()),
+            _ => None,
+        }
+    }
+    ///Whether the instruction is one of the halves of BL and BLX (1).
+    pub fn is_half(&self) -> bool {
+        matches!(
+            self,
+            Self::BLPrefix(_) | Self::BLSuffix(_) | Self::BLXSuffix(_)
+        )
+    }
+    ///BLX (2) with the PC is unpredictable.
+    pub fn is_unpredictable(&self) -> bool {
+        matches!(self, Self::BLX(Register::R15))
+    }
+    ///BX is ARMv4T, both BLX forms ARMv5.
+    pub fn architecture(&self) -> Architecture {
+        match self {
+            Self::BLXSuffix(_) | Self::BLXImmediate(_) | Self::BLX(_) => Architecture::ARMv5TE,
+            _ => Architecture::ARMv4T,
+        }
+    }
+}
+///The halves of BL and BLX (1) on their own are written as halfwords of data.
+impl Assembly for BranchInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        match self {
+            Self::BCond { cond, .. } => syntax.conditional("b", *cond, ""),
+            Self::B(_) => syntax.conditional("b", cond, ""),
+            Self::BL(_) => syntax.conditional("bl", cond, ""),
+            Self::BLXImmediate(_) | Self::BLX(_) => syntax.conditional("blx", cond, ""),
+            Self::BX(_) => syntax.conditional("bx", cond, ""),
+            Self::BLPrefix(_) | Self::BLSuffix(_) | Self::BLXSuffix(_) => {
+                syntax.halfword().to_string()
+            }
+        }
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        match self {
+            Self::BCond { offset, .. }
+            | Self::B(offset)
+            | Self::BL(offset)
+            | Self::BLXImmediate(offset) => offset.to_string(),
+            Self::BLX(register) | Self::BX(register) => register.to_string(),
+            Self::BLPrefix(offset) => format!("{:#06x}", 0xf000 | (offset.offset() >> 12) & 0x7ff),
+            Self::BLSuffix(low) => format!("{:#06x}", 0xf800 | low >> 1),
+            Self::BLXSuffix(low) => format!("{:#06x}", 0xe800 | low >> 1),
+        }
+    }
+}
+impl Display for BranchInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
@@ -0,0 +1,305 @@
+use std::fmt::{self, Display};
+
+use crate::errors::ParseError;
+use crate::instructions::{
+    fmt_assembly, split_with_range, Architecture, Assembly, Condition, Register, Syntax,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataProssessingInstruction {
+    ///Add with Carry.
+    ADC(Operands),
+    ///Add, ADD (1) to ADD (7).
+    ADD(Operands),
+    ///Logical AND.
+    AND(Operands),
+    ///Arithmetic Shift Right, ASR (1) and ASR (2).
+    ASR(Operands),
+    ///Logical Bit Clear.
+    BIC(Operands),
+    ///Compare Negative.
+    CMN {
+        first_operand: Register,
+        second_operand: Register,
+    },
+    ///Compare, CMP (1) to CMP (3).
+    CMP {
+        first_operand: Register,
+        second_operand: Operand,
+    },
+    ///Logical EOR.
+    EOR(Operands),
+    ///Logical Shift Left, LSL (1) and LSL (2).
+    LSL(Operands),
+    ///Logical Shift Right, LSR (1) and LSR (2).
+    LSR(Operands),
+    ///Move, MOV (1) and MOV (3). MOV (2) is encoded as an ADD (1) of zero.
+    MOV(Operands),
+    ///Multiply.
+    MUL(Operands),
+    ///Move Not.
+    MVN(Operands),
+    ///Negate.
+    NEG(Operands),
+    ///Logical OR.
+    ORR(Operands),
+    ///Rotate Right.
+    ROR(Operands),
+    ///Subtract with Carry.
+    SBC(Operands),
+    ///Subtract, SUB (1) to SUB (4).
+    SUB(Operands),
+    ///Test.
+    TST {
+        first_operand: Register,
+        second_operand: Register,
+    },
+}
+///The operand forms of the Thumb data-processing instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operands {
+    ///`Rd, Rm`, Rd is also the first operand unless the instruction is a move or negate.
+    Register {
+        destination: Register,
+        source: Register,
+    },
+    ///`Rd, Rn, Rm`
+    Registers {
+        destination: Register,
+        first_operand: Register,
+        second_operand: Register,
+    },
+    ///`Rd, Rn, #immediate`, which is a shift amount for the shifts and in bytes for the PC and
+    ///SP relative forms.
+    RegisterImmediate {
+        destination: Register,
+        first_operand: Register,
+        immediate: u16,
+    },
+    ///`Rd, #immediate`, Rd is also the first operand unless the instruction is a move.
+    Immediate {
+        destination: Register,
+        immediate: u8,
+    },
+}
+impl Display for Operands {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Register {
+                destination,
+                source,
+            } => write!(f, "{destination}, {source}"),
+            Self::Registers {
+                destination,
+                first_operand,
+                second_operand,
+            } => write!(f, "{destination}, {first_operand}, {second_operand}"),
+            // ADD (7) and SUB (4) only name the SP once
+            Self::RegisterImmediate {
+                destination: Register::R13,
+                first_operand: Register::R13,
+                immediate,
+            } => write!(f, "sp, #{immediate}"),
+            Self::RegisterImmediate {
+                destination,
+                first_operand,
+                immediate,
+            } => write!(f, "{destination}, {first_operand}, #{immediate}"),
+            Self::Immediate {
+                destination,
+                immediate,
+            } => write!(f, "{destination}, #{immediate}"),
+        }
+    }
+}
+///Second operand of a compare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Register(Register),
+    ///The register of CMP (3), which can be any of R0 to R15.
+    HighRegister(Register),
+    Immediate(u8),
+}
+impl DataProssessingInstruction {
+    ///Decodes format 4, the ALU operations on two low registers.
+    pub fn new_alu(value: u32) -> Result<Self, ParseError> {
+        let (op, value) = split_with_range(value, 6..=9);
+        let (rm, value) = split_with_range(value, 3..=5);
+        let (rd, _) = split_with_range(value, 0..=2);
+        let destination = Register::try_from(rd as u8)?;
+        let source = Register::try_from(rm as u8)?;
+        let operands = Operands::Register {
+            destination,
+            source,
+        };
+        use DataProssessingInstruction::*;
+        Ok(match op {
+            0b0000 => AND(operands),
+            0b0001 => EOR(operands),
+            0b0010 => LSL(operands),
+            0b0011 => LSR(operands),
+            0b0100 => ASR(operands),
+            0b0101 => ADC(operands),
+            0b0110 => SBC(operands),
+            0b0111 => ROR(operands),
+            0b1000 => TST {
+                first_operand: destination,
+                second_operand: source,
+            },
+            0b1001 => NEG(operands),
+            0b1010 => CMP {
+                first_operand: destination,
+                second_operand: Operand::Register(source),
+            },
+            0b1011 => CMN {
+                first_operand: destination,
+                second_operand: source,
+            },
+            0b1100 => ORR(operands),
+            0b1101 => MUL(operands),
+            0b1110 => BIC(operands),
+            _ => MVN(operands),
+        })
+    }
+    ///Before ARMv6 MUL can't use the same register for both operands, and ADD (4), CMP (3) and
+    ///MOV (3) can't use two low registers.
+    pub fn is_unpredictable(&self, architecture: Architecture) -> bool {
+        let same_registers = matches!(
+            self,
+            Self::MUL(Operands::Register {
+                destination,
+                source,
+            }) if destination == source
+        );
+        architecture < Architecture::ARMv6 && (same_registers || self.is_low_register_operation())
+    }
+    ///Whether ADD (4), CMP (3) or MOV (3) only use low registers, which assemblers write as the
+    ///forms for low registers instead.
+    pub fn is_low_register_operation(&self) -> bool {
+        use DataProssessingInstruction::*;
+        let is_low = |register: &Register| (*register as u8) < 8;
+        match self {
+            ADD(Operands::Register {
+                destination,
+                source,
+            })
+            | MOV(Operands::Register {
+                destination,
+                source,
+            })
+            | CMP {
+                first_operand: destination,
+                second_operand: Operand::HighRegister(source),
+            } => is_low(destination) && is_low(source),
+            _ => false,
+        }
+    }
+}
+impl Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Register(register) | Self::HighRegister(register) => write!(f, "{register}"),
+            Self::Immediate(immediate) => write!(f, "#{immediate}"),
+        }
+    }
+}
+impl DataProssessingInstruction {
+    ///Whether the instruction updates the flags, which is all of them except the compares, the
+    ///ones on high registers and the SP and PC relative ones that don't update them.
+    pub fn sets_flags(&self) -> bool {
+        use DataProssessingInstruction::*;
+        !matches!(
+            self,
+            CMN { .. }
+                | CMP { .. }
+                | TST { .. }
+                | ADD(Operands::Register { .. })
+                | MOV(Operands::Register { .. })
+                | ADD(Operands::RegisterImmediate {
+                    first_operand: Register::R13 | Register::R15,
+                    ..
+                })
+                | SUB(Operands::RegisterImmediate {
+                    first_operand: Register::R13,
+                    ..
+                })
+        )
+    }
+}
+///UAL writes the S of the instructions that update the flags, spells NEG and MUL as the
+///general forms they are and ADD (5) as ADR.
+impl Assembly for DataProssessingInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        use DataProssessingInstruction::*;
+        let base = match self {
+            NEG(_) if syntax == Syntax::Unified => "rsb",
+            ADD(Operands::RegisterImmediate {
+                first_operand: Register::R15,
+                ..
+            }) if syntax == Syntax::Unified => "adr",
+            ADC(_) => "adc",
+            ADD(_) => "add",
+            AND(_) => "and",
+            ASR(_) => "asr",
+            BIC(_) => "bic",
+            CMN { .. } => "cmn",
+            CMP { .. } => "cmp",
+            EOR(_) => "eor",
+            LSL(_) => "lsl",
+            LSR(_) => "lsr",
+            MOV(_) => "mov",
+            MUL(_) => "mul",
+            MVN(_) => "mvn",
+            NEG(_) => "neg",
+            ORR(_) => "orr",
+            ROR(_) => "ror",
+            SBC(_) => "sbc",
+            SUB(_) => "sub",
+            TST { .. } => "tst",
+        };
+        let s = match syntax == Syntax::Unified && self.sets_flags() {
+            true => "s",
+            false => "",
+        };
+        syntax.conditional(base, cond, s)
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        use DataProssessingInstruction::*;
+        match self {
+            NEG(Operands::Register {
+                destination,
+                source,
+            }) if syntax == Syntax::Unified => format!("{destination}, {source}, #0"),
+            MUL(Operands::Register {
+                destination,
+                source,
+            }) if syntax == Syntax::Unified => format!("{destination}, {source}, {destination}"),
+            ADD(Operands::RegisterImmediate {
+                destination,
+                first_operand: Register::R15,
+                immediate,
+            }) if syntax == Syntax::Unified => format!("{destination}, #{immediate}"),
+            ADC(operands) | ADD(operands) | AND(operands) | ASR(operands) | BIC(operands)
+            | EOR(operands) | LSL(operands) | LSR(operands) | MOV(operands) | MUL(operands)
+            | MVN(operands) | NEG(operands) | ORR(operands) | ROR(operands) | SBC(operands)
+            | SUB(operands) => operands.to_string(),
+            CMN {
+                first_operand,
+                second_operand,
+            }
+            | TST {
+                first_operand,
+                second_operand,
+            } => format!("{first_operand}, {second_operand}"),
+            CMP {
+                first_operand,
+                second_operand,
+            } => format!("{first_operand}, {second_operand}"),
+        }
+    }
+}
+impl Display for DataProssessingInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
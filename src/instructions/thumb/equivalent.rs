@@ -0,0 +1,589 @@
+//! The ARM equivalents of the Thumb instructions, as given for each of them in chapter A7.
+use super::branch::BranchInstruction;
+use super::dataprosessing::{DataProssessingInstruction, Operand, Operands};
+use super::exception::ExceptiongeneratingInstruction;
+use super::loadandstore::{AdressingMode, LoadAndStoreInstruction, TransferInstruction};
+use super::ThumbInstruction;
+use crate::instructions::arm::adresssing::{
+    Indexing, LoadAndStoreAddressingMode, LoadAndStoreMultipleAddressingMode, LoadAndStoreOffset,
+    MiscLoadAndStoreAddressingMode, MiscLoadAndStoreOffset, RotatedImmediate, Shift, ShiftType,
+    ShifterOperand,
+};
+use crate::instructions::arm::branch::BranchInstruction as ArmBranch;
+use crate::instructions::arm::dataprosessing::{
+    DataProssessingInstruction as ArmData, GenericDataInstruction, MOVLikeDataInstruction,
+    NoDestinationDataInstruction,
+};
+use crate::instructions::arm::exception::ExceptiongeneratingInstruction as ArmException;
+use crate::instructions::arm::loadandstore::{
+    LoadAndStoreGenericInsturction, LoadAndStoreInstruction as ArmLoadAndStore,
+    LoadAndStoreMiscInstruction, LoadAndStoreMultiple, LoadAndStoreMultipleWriteGeneric,
+};
+use crate::instructions::arm::multiply::MultiplyInstruction;
+use crate::instructions::arm::unconditional::UnconditionalInstruction;
+use crate::instructions::arm::{ArmInstruction, PartialArmInstruction};
+use crate::instructions::{consts, Adress, Condition, Register, RegisterList, RelativeAdress};
+
+impl ThumbInstruction {
+    ///The ARM instruction that does the same as this one at `adress`, with condition AL unless
+    ///it's B (1), and with the operands the Thumb encoding leaves implicit spelled out.
+    ///
+    ///Branch targets and the addresses of PC-relative loads are kept, so the offsets are
+    ///adjusted for the PC being 8 bytes ahead instead of 4. The PC as a register operand can't
+    ///be adjusted and still reads as in Thumb state. `None` for the halves of BL and BLX (1) on
+    ///their own, and for an ADD (5) whose adjusted offset isn't an ARM immediate.
+    pub fn to_arm(self, adress: Adress) -> Option<ArmInstruction> {
+        let op = match self {
+            Self::Branch(BranchInstruction::BCond { cond, offset }) => {
+                return Some(ArmInstruction {
+                    cond,
+                    op: PartialArmInstruction::Branch(ArmBranch::B(arm_offset(offset))),
+                })
+            }
+            Self::Branch(branch) => match branch {
+                BranchInstruction::B(offset) => {
+                    PartialArmInstruction::Branch(ArmBranch::B(arm_offset(offset)))
+                }
+                BranchInstruction::BL(offset) => {
+                    PartialArmInstruction::Branch(ArmBranch::BL(arm_offset(offset)))
+                }
+                BranchInstruction::BLXImmediate(_) => {
+                    let target = branch.target(adress)?;
+                    return Some(ArmInstruction {
+                        cond: Condition::Never,
+                        op: PartialArmInstruction::Unconditional(UnconditionalInstruction::BLX(
+                            RelativeAdress::new(distance(adress.arm_pc(), target)),
+                        )),
+                    });
+                }
+                BranchInstruction::BLX(register) => {
+                    PartialArmInstruction::Branch(ArmBranch::BLX(register))
+                }
+                BranchInstruction::BX(register) => {
+                    PartialArmInstruction::Branch(ArmBranch::BX(register))
+                }
+                _ => return None,
+            },
+            Self::DataProssessing(data) => data_prosessing(data, adress)?,
+            Self::LoadAndStore(load_and_store) => {
+                PartialArmInstruction::LoadAndStore(load_and_store_to_arm(load_and_store, adress))
+            }
+            Self::Exceptiongenerating(ExceptiongeneratingInstruction::BKPT(immediate)) => {
+                PartialArmInstruction::Exceptiongenerating(ArmException::BKPT(immediate as u16))
+            }
+            // the DS BIOS reads the function from bits 16 to 23 in ARM state
+            Self::Exceptiongenerating(ExceptiongeneratingInstruction::SWI(comment)) => {
+                PartialArmInstruction::Exceptiongenerating(ArmException::SWI(
+                    (comment as u32) << 16,
+                ))
+            }
+            Self::Undefined(value) => PartialArmInstruction::Undefined(value as u32),
+        };
+        Some(ArmInstruction {
+            cond: Condition::Allways,
+            op,
+        })
+    }
+}
+///A Thumb branch offset as an ARM one with the same target.
+fn arm_offset(offset: RelativeAdress) -> RelativeAdress {
+    RelativeAdress::new(
+        offset.offset() + consts::THUMB_PC_OFFSET as i32 - consts::ARM_PC_OFFSET as i32,
+    )
+}
+fn distance(from: Adress, to: Adress) -> i32 {
+    to.value().wrapping_sub(from.value()) as i32
+}
+///The address the PC-relative forms are based on, the Thumb PC with the low bits cleared.
+fn literal_base(adress: Adress) -> Adress {
+    adress.thumb_pc().word_aligned()
+}
+fn data_prosessing(
+    data: DataProssessingInstruction,
+    adress: Adress,
+) -> Option<PartialArmInstruction> {
+    use DataProssessingInstruction::*;
+    Some(PartialArmInstruction::DataProssessing(match data {
+        ADC(operands) => ArmData::ADC(generic(operands, true)),
+        AND(operands) => ArmData::AND(generic(operands, true)),
+        BIC(operands) => ArmData::BIC(generic(operands, true)),
+        EOR(operands) => ArmData::EOR(generic(operands, true)),
+        ORR(operands) => ArmData::ORR(generic(operands, true)),
+        SBC(operands) => ArmData::SBC(generic(operands, true)),
+        // ADD (4) with high registers doesn't update the flags
+        ADD(operands @ Operands::Register { .. }) => ArmData::ADD(generic(operands, false)),
+        ADD(Operands::RegisterImmediate {
+            destination,
+            first_operand: Register::R15,
+            immediate,
+        }) => {
+            let offset = literal_base(adress)
+                .value()
+                .wrapping_add(immediate as u32)
+                .wrapping_sub(adress.arm_pc().value()) as i32;
+            let first_operand = Register::R15;
+            let shifter =
+                ShifterOperand::Immediate(RotatedImmediate::from_value(offset.unsigned_abs())?);
+            let instruction = GenericDataInstruction {
+                destination,
+                first_operand,
+                s: false,
+                shifter,
+            };
+            match offset < 0 {
+                true => ArmData::SUB(instruction),
+                false => ArmData::ADD(instruction),
+            }
+        }
+        // ADD (6), ADD (7) and SUB (4) are relative to the SP and don't update the flags
+        ADD(operands) => ArmData::ADD(generic(operands, !uses_sp(operands))),
+        SUB(operands) => ArmData::SUB(generic(operands, !uses_sp(operands))),
+        CMN {
+            first_operand,
+            second_operand,
+        } => ArmData::CMN(compare(
+            first_operand,
+            ShifterOperand::Register(second_operand),
+        )),
+        CMP {
+            first_operand,
+            second_operand,
+        } => ArmData::CMP(compare(
+            first_operand,
+            match second_operand {
+                Operand::Register(register) | Operand::HighRegister(register) => {
+                    ShifterOperand::Register(register)
+                }
+                Operand::Immediate(immediate) => ShifterOperand::Immediate(RotatedImmediate {
+                    immediate,
+                    rotate: 0,
+                }),
+            },
+        )),
+        TST {
+            first_operand,
+            second_operand,
+        } => ArmData::TST(compare(
+            first_operand,
+            ShifterOperand::Register(second_operand),
+        )),
+        LSL(operands) => ArmData::MOV(shift(operands, Shift::LSL, ShiftType::LSL)),
+        LSR(operands) => ArmData::MOV(shift(operands, Shift::LSR, ShiftType::LSR)),
+        ASR(operands) => ArmData::MOV(shift(operands, Shift::ASR, ShiftType::ASR)),
+        ROR(operands) => ArmData::MOV(shift(operands, Shift::ROR, ShiftType::ROR)),
+        // MOV (3) with high registers doesn't update the flags
+        MOV(Operands::Register {
+            destination,
+            source,
+        }) => ArmData::MOV(MOVLikeDataInstruction {
+            destination,
+            s: false,
+            shifter: ShifterOperand::Register(source),
+        }),
+        MOV(operands) => ArmData::MOV(move_like(operands)),
+        MVN(operands) => ArmData::MVN(move_like(operands)),
+        // NEG is a reverse subtract from zero
+        NEG(operands) => {
+            let (destination, source) = destination_and_source(operands);
+            ArmData::RSB(GenericDataInstruction {
+                destination,
+                first_operand: source,
+                s: true,
+                shifter: ShifterOperand::Immediate(RotatedImmediate {
+                    immediate: 0,
+                    rotate: 0,
+                }),
+            })
+        }
+        MUL(operands) => {
+            let (destination, source) = destination_and_source(operands);
+            return Some(PartialArmInstruction::Multiply(MultiplyInstruction::MUL {
+                destination,
+                s: true,
+                first_operand: source,
+                second_operand: destination,
+            }));
+        }
+    }))
+}
+fn uses_sp(operands: Operands) -> bool {
+    matches!(
+        operands,
+        Operands::RegisterImmediate {
+            first_operand: Register::R13,
+            ..
+        }
+    )
+}
+fn destination_and_source(operands: Operands) -> (Register, Register) {
+    match operands {
+        Operands::Register {
+            destination,
+            source,
+        }
+        | Operands::Registers {
+            destination,
+            second_operand: source,
+            ..
+        }
+        | Operands::RegisterImmediate {
+            destination,
+            first_operand: source,
+            ..
+        } => (destination, source),
+        Operands::Immediate { destination, .. } => (destination, destination),
+    }
+}
+///`Rd, Rm` becomes `Rd, Rd, Rm` and `Rd, #immediate` becomes `Rd, Rd, #immediate`.
+fn generic(operands: Operands, s: bool) -> GenericDataInstruction {
+    let (destination, first_operand, shifter) = match operands {
+        Operands::Register {
+            destination,
+            source,
+        } => (destination, destination, ShifterOperand::Register(source)),
+        Operands::Registers {
+            destination,
+            first_operand,
+            second_operand,
+        } => (
+            destination,
+            first_operand,
+            ShifterOperand::Register(second_operand),
+        ),
+        Operands::RegisterImmediate {
+            destination,
+            first_operand,
+            immediate,
+        } => (
+            destination,
+            first_operand,
+            // the SP relative forms are at most 1020, which always has an encoding
+            ShifterOperand::Immediate(
+                RotatedImmediate::from_value(immediate as u32)
+                    .expect("Thumb immediates are ARM immediates"),
+            ),
+        ),
+        Operands::Immediate {
+            destination,
+            immediate,
+        } => (
+            destination,
+            destination,
+            ShifterOperand::Immediate(RotatedImmediate {
+                immediate,
+                rotate: 0,
+            }),
+        ),
+    };
+    GenericDataInstruction {
+        destination,
+        first_operand,
+        s,
+        shifter,
+    }
+}
+fn compare(first_operand: Register, shifter: ShifterOperand) -> NoDestinationDataInstruction {
+    NoDestinationDataInstruction {
+        first_operand,
+        s: true,
+        shifter,
+    }
+}
+fn move_like(operands: Operands) -> MOVLikeDataInstruction {
+    let (destination, shifter) = match operands {
+        Operands::Immediate {
+            destination,
+            immediate,
+        } => (
+            destination,
+            ShifterOperand::Immediate(RotatedImmediate {
+                immediate,
+                rotate: 0,
+            }),
+        ),
+        operands => {
+            let (destination, source) = destination_and_source(operands);
+            (destination, ShifterOperand::Register(source))
+        }
+    };
+    MOVLikeDataInstruction {
+        destination,
+        s: true,
+        shifter,
+    }
+}
+///The shifts are moves of a shifted register, `Rd, Rm, #amount` shifts Rm and `Rd, Rs` shifts
+///Rd by Rs.
+fn shift(
+    operands: Operands,
+    immediate_shift: fn(u8) -> Shift,
+    shift: ShiftType,
+) -> MOVLikeDataInstruction {
+    let (destination, shifter) = match operands {
+        Operands::RegisterImmediate {
+            destination,
+            first_operand,
+            immediate,
+        } => (
+            destination,
+            match immediate_shift(immediate as u8) {
+                Shift::LSL(0) => ShifterOperand::Register(first_operand),
+                shift => ShifterOperand::ImmediateShift {
+                    register: first_operand,
+                    shift,
+                },
+            },
+        ),
+        operands => {
+            let (destination, source) = destination_and_source(operands);
+            (
+                destination,
+                ShifterOperand::RegisterShift {
+                    register: destination,
+                    shift,
+                    amount: source,
+                },
+            )
+        }
+    };
+    MOVLikeDataInstruction {
+        destination,
+        s: true,
+        shifter,
+    }
+}
+fn load_and_store_to_arm(
+    load_and_store: LoadAndStoreInstruction,
+    adress: Adress,
+) -> ArmLoadAndStore {
+    use LoadAndStoreInstruction::*;
+    match load_and_store {
+        LDR(transfer) => ArmLoadAndStore::LDR(generic_transfer(transfer, adress)),
+        LDRB(transfer) => ArmLoadAndStore::LDRB(generic_transfer(transfer, adress)),
+        STR(transfer) => ArmLoadAndStore::STR(generic_transfer(transfer, adress)),
+        STRB(transfer) => ArmLoadAndStore::STRB(generic_transfer(transfer, adress)),
+        LDRH(transfer) => ArmLoadAndStore::LDRH(misc_transfer(transfer)),
+        LDRSB(transfer) => ArmLoadAndStore::LDRSB(misc_transfer(transfer)),
+        LDRSH(transfer) => ArmLoadAndStore::LDRSH(misc_transfer(transfer)),
+        STRH(transfer) => ArmLoadAndStore::STRH(misc_transfer(transfer)),
+        // the base is only written back when it isn't loaded
+        LDMIA { base, registers } => ArmLoadAndStore::Multiple(LoadAndStoreMultiple::LDM(
+            LoadAndStoreMultipleWriteGeneric {
+                adressing_mode: LoadAndStoreMultipleAddressingMode::IncrementAfter,
+                base,
+                write: !registers.registers().any(|register| register == base),
+                registers,
+            },
+        )),
+        STMIA { base, registers } => {
+            ArmLoadAndStore::Multiple(LoadAndStoreMultiple::STM(multiple_with_writeback(
+                LoadAndStoreMultipleAddressingMode::IncrementAfter,
+                base,
+                registers,
+            )))
+        }
+        POP(registers) => {
+            ArmLoadAndStore::Multiple(LoadAndStoreMultiple::LDM(multiple_with_writeback(
+                LoadAndStoreMultipleAddressingMode::IncrementAfter,
+                Register::R13,
+                registers,
+            )))
+        }
+        PUSH(registers) => {
+            ArmLoadAndStore::Multiple(LoadAndStoreMultiple::STM(multiple_with_writeback(
+                LoadAndStoreMultipleAddressingMode::DecrementBefore,
+                Register::R13,
+                registers,
+            )))
+        }
+    }
+}
+fn multiple_with_writeback(
+    adressing_mode: LoadAndStoreMultipleAddressingMode,
+    base: Register,
+    registers: RegisterList,
+) -> LoadAndStoreMultipleWriteGeneric {
+    LoadAndStoreMultipleWriteGeneric {
+        adressing_mode,
+        base,
+        write: true,
+        registers,
+    }
+}
+///LDR (3) reads from the word aligned Thumb PC, so the offset is moved to the ARM PC.
+fn generic_transfer(
+    transfer: TransferInstruction,
+    adress: Adress,
+) -> LoadAndStoreGenericInsturction {
+    let (base, offset, add) = match transfer.adressing_mode {
+        AdressingMode::Immediate {
+            base: Register::R15,
+            offset,
+        } => {
+            let offset = distance(
+                adress.arm_pc(),
+                Adress::new(literal_base(adress).value().wrapping_add(offset as u32)),
+            );
+            (
+                Register::R15,
+                LoadAndStoreOffset::Immediate(offset.unsigned_abs() as u16),
+                offset >= 0,
+            )
+        }
+        AdressingMode::Immediate { base, offset } => {
+            (base, LoadAndStoreOffset::Immediate(offset), true)
+        }
+        AdressingMode::Register { base, offset } => {
+            (base, LoadAndStoreOffset::Register(offset), true)
+        }
+    };
+    LoadAndStoreGenericInsturction {
+        destination: transfer.destination,
+        adressing_mode: LoadAndStoreAddressingMode {
+            base,
+            offset,
+            add,
+            indexing: Indexing::Offset,
+        },
+    }
+}
+fn misc_transfer(transfer: TransferInstruction) -> LoadAndStoreMiscInstruction {
+    let (base, offset) = match transfer.adressing_mode {
+        AdressingMode::Immediate { base, offset } => {
+            (base, MiscLoadAndStoreOffset::Immediate(offset as u8))
+        }
+        AdressingMode::Register { base, offset } => {
+            (base, MiscLoadAndStoreOffset::Register(offset))
+        }
+    };
+    LoadAndStoreMiscInstruction {
+        destination: transfer.destination,
+        adressing_mode: MiscLoadAndStoreAddressingMode {
+            base,
+            offset,
+            add: true,
+            indexing: Indexing::Offset,
+        },
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::Architecture;
+
+    fn decode(first: u16, second: Option<u16>) -> ThumbInstruction {
+        ThumbInstruction::decode_pair(first, second, Architecture::ARMv5TE).instruction
+    }
+    ///The address ADD (5) and its ARM equivalent compute.
+    fn thumb_adress(instruction: ThumbInstruction, adress: Adress) -> Option<u32> {
+        match instruction {
+            ThumbInstruction::DataProssessing(DataProssessingInstruction::ADD(
+                Operands::RegisterImmediate {
+                    first_operand: Register::R15,
+                    immediate,
+                    ..
+                },
+            )) => Some(literal_base(adress).value() + immediate as u32),
+            _ => None,
+        }
+    }
+    fn arm_adress(instruction: ArmInstruction, adress: Adress) -> Option<u32> {
+        let pc = adress.arm_pc().value();
+        match instruction.op {
+            PartialArmInstruction::DataProssessing(ArmData::ADD(GenericDataInstruction {
+                first_operand: Register::R15,
+                shifter: ShifterOperand::Immediate(immediate),
+                ..
+            })) => Some(pc.wrapping_add(immediate.value())),
+            PartialArmInstruction::DataProssessing(ArmData::SUB(GenericDataInstruction {
+                first_operand: Register::R15,
+                shifter: ShifterOperand::Immediate(immediate),
+                ..
+            })) => Some(pc.wrapping_sub(immediate.value())),
+            _ => None,
+        }
+    }
+    fn sets_flags(instruction: ArmInstruction) -> bool {
+        match instruction.op {
+            PartialArmInstruction::DataProssessing(
+                ArmData::ADD(inst) | ArmData::SUB(inst) | ArmData::AND(inst),
+            ) => inst.s,
+            PartialArmInstruction::DataProssessing(ArmData::MOV(inst)) => inst.s,
+            op => panic!("{op:?} isn't a data-processing instruction"),
+        }
+    }
+
+    #[test]
+    fn targets_and_literals_are_kept() {
+        let instructions = [
+            // ldr r2, [pc, #16]
+            decode(0x4a04, None),
+            // add r1, pc, #8
+            decode(0xa102, None),
+            // beq . and b . + 2050
+            decode(0xd0fe, None),
+            decode(0xe3ff, None),
+            // bl . + 0x1004 and blx . + 0x1004
+            decode(0xf001, Some(0xf800)),
+            decode(0xf001, Some(0xe800)),
+            // bl . and blx . - 0x3ffffc
+            decode(0xf7ff, Some(0xfffe)),
+            decode(0xf400, Some(0xe800)),
+        ];
+        for instruction in instructions {
+            for adress in [0x0800_0000, 0x0800_0002, 0x0800_0004, 0x0800_0006] {
+                let adress = Adress::new(adress);
+                let arm = instruction.to_arm(adress).unwrap();
+                let message = format!("{instruction} at {:#x}", adress.value());
+                assert_eq!(arm.target(adress), instruction.target(adress), "{message}");
+                assert_eq!(
+                    arm.literal(adress),
+                    instruction.literal(adress),
+                    "{message}"
+                );
+                assert_eq!(
+                    arm_adress(arm, adress),
+                    thumb_adress(instruction, adress),
+                    "{message}"
+                );
+            }
+        }
+        // the BLX (1) target is word aligned
+        let target = decode(0xf001, Some(0xe800))
+            .to_arm(Adress::new(0x0800_0002))
+            .and_then(|arm| arm.target(Adress::new(0x0800_0002)));
+        assert_eq!(target, Some(Adress::new(0x0800_1004)));
+    }
+
+    #[test]
+    fn flags_are_kept() {
+        let arm = |half| decode(half, None).to_arm(Adress::new(0)).unwrap();
+        // adds r0, r1, r2 and ands r0, r1
+        assert!(sets_flags(arm(0x1888)));
+        assert!(sets_flags(arm(0x4008)));
+        // add r8, r1, add r2, sp, #1020, add sp, #508, mov r8, r1 and sub sp, #508
+        for half in [0x4488, 0xaaff, 0xb07f, 0x4688, 0xb0ff] {
+            assert!(!sets_flags(arm(half)), "{half:#06x}");
+        }
+    }
+
+    #[test]
+    fn software_interrupt() {
+        // swi 0x0b calls the same BIOS function as swi 0x0b0000 in ARM state
+        let thumb = decode(0xdf0b, None);
+        let arm = thumb.to_arm(Adress::new(0)).unwrap().op;
+        assert_eq!(
+            arm,
+            PartialArmInstruction::Exceptiongenerating(ArmException::SWI(0x0b_0000))
+        );
+        let (
+            ThumbInstruction::Exceptiongenerating(thumb),
+            PartialArmInstruction::Exceptiongenerating(arm),
+        ) = (thumb, arm)
+        else {
+            panic!("{thumb:?} and {arm:?} aren't both exception generating");
+        };
+        assert_eq!(thumb.bios_function(), Some(0x0b));
+        assert_eq!(thumb.bios_function(), arm.bios_function());
+    }
+}
@@ -0,0 +1,47 @@
+use std::fmt::{self, Display};
+
+use crate::instructions::{fmt_assembly, Architecture, Assembly, Condition, Syntax};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExceptiongeneratingInstruction {
+    ///Breakpoint.
+    BKPT(u8),
+    ///Software Interrupt.
+    SWI(u8),
+}
+impl ExceptiongeneratingInstruction {
+    ///The BIOS function a Thumb state SWI calls, which is the whole comment.
+    pub fn bios_function(&self) -> Option<u8> {
+        match self {
+            Self::SWI(comment) => Some(*comment),
+            Self::BKPT(_) => None,
+        }
+    }
+    ///BKPT is ARMv5.
+    pub fn architecture(&self) -> Architecture {
+        match self {
+            Self::BKPT(_) => Architecture::ARMv5TE,
+            Self::SWI(_) => Architecture::ARMv4T,
+        }
+    }
+}
+impl Assembly for ExceptiongeneratingInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        match self {
+            Self::BKPT(_) => syntax.conditional("bkpt", cond, ""),
+            // UAL renamed SWI to SVC
+            Self::SWI(_) if syntax == Syntax::Unified => syntax.conditional("svc", cond, ""),
+            Self::SWI(_) => syntax.conditional("swi", cond, ""),
+        }
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        match self {
+            Self::BKPT(immediate) | Self::SWI(immediate) => syntax.comment_field(*immediate as u32),
+        }
+    }
+}
+impl Display for ExceptiongeneratingInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
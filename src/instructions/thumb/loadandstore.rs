@@ -0,0 +1,126 @@
+use std::fmt::{self, Display};
+
+use crate::instructions::arm::adresssing::LoadAndStoreMultipleAddressingMode;
+use crate::instructions::{fmt_assembly, Assembly, Condition, Register, RegisterList, Syntax};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadAndStoreInstruction {
+    ///Load Multiple Increment After, the base is written back unless it's in the list.
+    LDMIA {
+        base: Register,
+        registers: RegisterList,
+    },
+    ///Load Word, LDR (1) to LDR (4).
+    LDR(TransferInstruction),
+    ///Load Byte, LDRB (1) and LDRB (2).
+    LDRB(TransferInstruction),
+    ///Load Unsigned Halfword, LDRH (1) and LDRH (2).
+    LDRH(TransferInstruction),
+    ///Load Signed Byte.
+    LDRSB(TransferInstruction),
+    ///Load Signed Halfword.
+    LDRSH(TransferInstruction),
+    ///Pop Multiple Registers, the list can include the PC.
+    POP(RegisterList),
+    ///Push Multiple Registers, the list can include LR.
+    PUSH(RegisterList),
+    ///Store Multiple Increment After, the base is always written back.
+    STMIA {
+        base: Register,
+        registers: RegisterList,
+    },
+    ///Store Word, STR (1) to STR (3).
+    STR(TransferInstruction),
+    ///Store Byte, STRB (1) and STRB (2).
+    STRB(TransferInstruction),
+    ///Store Halfword, STRH (1) and STRH (2).
+    STRH(TransferInstruction),
+}
+impl LoadAndStoreInstruction {
+    ///An empty register list is unpredictable, and so is storing the written back base unless
+    ///it's the first register stored.
+    pub fn is_unpredictable(&self) -> bool {
+        use LoadAndStoreInstruction::*;
+        match self {
+            LDMIA { registers, .. } | POP(registers) | PUSH(registers) => registers.is_empty(),
+            STMIA { base, registers } => {
+                registers.is_empty()
+                    || registers
+                        .registers()
+                        .skip(1)
+                        .any(|register| register == *base)
+            }
+            _ => false,
+        }
+    }
+}
+impl Assembly for LoadAndStoreInstruction {
+    fn mnemonic(&self, cond: Condition, syntax: Syntax) -> String {
+        use LoadAndStoreInstruction::*;
+        let ia = LoadAndStoreMultipleAddressingMode::IncrementAfter.suffix(syntax);
+        let (base, suffix) = match self {
+            LDMIA { .. } => ("ldm", ia),
+            LDR(_) => ("ldr", ""),
+            LDRB(_) => ("ldr", "b"),
+            LDRH(_) => ("ldr", "h"),
+            LDRSB(_) => ("ldr", "sb"),
+            LDRSH(_) => ("ldr", "sh"),
+            POP(_) => ("pop", ""),
+            PUSH(_) => ("push", ""),
+            STMIA { .. } => ("stm", ia),
+            STR(_) => ("str", ""),
+            STRB(_) => ("str", "b"),
+            STRH(_) => ("str", "h"),
+        };
+        syntax.conditional(base, cond, suffix)
+    }
+    fn operands(&self, syntax: Syntax) -> String {
+        use LoadAndStoreInstruction::*;
+        match self {
+            // LDMIA only writes the base back when it isn't loaded
+            LDMIA { base, registers } if registers.registers().any(|r| r == *base) => {
+                format!("{base}, {registers}")
+            }
+            LDMIA { base, registers } | STMIA { base, registers } => {
+                format!("{base}!, {registers}")
+            }
+            LDR(transfer) | LDRB(transfer) | LDRH(transfer) | LDRSB(transfer) | LDRSH(transfer)
+            | STR(transfer) | STRB(transfer) | STRH(transfer) => {
+                format!("{}, {}", transfer.destination, transfer.adressing_mode)
+            }
+            POP(registers) | PUSH(registers) => registers.to_string(),
+        }
+    }
+}
+impl Display for LoadAndStoreInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_assembly(self, Condition::Allways, f)
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferInstruction {
+    pub destination: Register,
+    pub adressing_mode: AdressingMode,
+}
+///The address of a single load or store, which is always base plus offset without writeback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdressingMode {
+    ///Offset in bytes, the base is R15 for PC-relative loads and R13 for SP-relative ones.
+    Immediate {
+        base: Register,
+        offset: u16,
+    },
+    Register {
+        base: Register,
+        offset: Register,
+    },
+}
+impl Display for AdressingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Immediate { base, offset: 0 } => write!(f, "[{base}]"),
+            Self::Immediate { base, offset } => write!(f, "[{base}, #{offset}]"),
+            Self::Register { base, offset } => write!(f, "[{base}, {offset}]"),
+        }
+    }
+}
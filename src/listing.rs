@@ -0,0 +1,207 @@
+//! Writes the disassembly as a listing in the style of `objdump -d`.
+use std::io::{self, Write};
+
+use crate::disassembler::{Image, Instruction, Line, Mode};
+use crate::instructions::arm::adresssing::ShifterOperand;
+use crate::instructions::arm::dataprosessing::{
+    DataProssessingInstruction, GenericDataInstruction,
+};
+use crate::instructions::arm::{ArmInstruction, PartialArmInstruction};
+use crate::instructions::thumb::dataprosessing::{
+    DataProssessingInstruction as ThumbDataProssessingInstruction, Operands,
+};
+use crate::instructions::thumb::ThumbInstruction;
+use crate::instructions::{assembly, Adress, Condition, Register, Syntax, Violations};
+
+///The column the comments start at, unless the instruction is longer.
+const COMMENT_COLUMN: usize = 48;
+
+///Writes one line per instruction with its address, encoding, mnemonic and operands in
+///`syntax`, with branches showing their target, followed by comments with the values of literal
+///loads and the addresses computed from the PC. Every change of state starts with a header
+///named like the `$a` and `$t` mapping symbols.
+pub fn write_listing(
+    out: &mut impl Write,
+    image: Image,
+    lines: &[Line],
+    syntax: Syntax,
+) -> io::Result<()> {
+    let mut mode = None;
+    for line in lines {
+        let current = line.instruction.mode();
+        if mode != Some(current) {
+            if mode.is_some() {
+                writeln!(out)?;
+            }
+            let symbol = match current {
+                Mode::Arm => "$a",
+                Mode::Thumb => "$t",
+            };
+            writeln!(out, "{:08x} <{symbol}>:", line.adress.value())?;
+            mode = Some(current);
+        }
+        write_line(out, image, line, syntax)?;
+    }
+    Ok(())
+}
+fn write_line(out: &mut impl Write, image: Image, line: &Line, syntax: Syntax) -> io::Result<()> {
+    let (raw, (mnemonic, mut operands), target, violations, mut comments) = match line.instruction {
+        Instruction::Arm(decoded) => {
+            let instruction = decoded.instruction;
+            (
+                format!("{:08x}", line.raw),
+                assembly(&instruction, instruction.cond, syntax),
+                instruction.target(line.adress),
+                decoded.violations,
+                arm_comments(&instruction, line.adress, image),
+            )
+        }
+        Instruction::Thumb(decoded) => {
+            let instruction = decoded.instruction;
+            // the joined BL and BLX (1) are shown as the two halfwords in memory order
+            let raw = match instruction.size() {
+                4 => format!("{:04x} {:04x}", line.raw & 0xffff, line.raw >> 16),
+                _ => format!("{:04x}", line.raw),
+            };
+            (
+                raw,
+                assembly(&instruction, Condition::Allways, syntax),
+                instruction.target(line.adress),
+                decoded.violations,
+                thumb_comments(&instruction, line.adress, image),
+            )
+        }
+    };
+    // branches show the address they go to instead of the offset, like objdump
+    if let Some(target) = target {
+        operands = format!("{:x}", target.value());
+    }
+    comments.extend(violations.iter().map(|violation| {
+        match violation {
+            Violations::UNPREDICTABLE => "unpredictable",
+            Violations::SHOULD_BE_ZERO => "should be zero bits ignored",
+            _ => "should be one bits ignored",
+        }
+        .to_string()
+    }));
+    let text = format!(
+        "{:8x}:  {raw:<9}  {mnemonic:<8} {operands}",
+        line.adress.value()
+    );
+    let text = text.trim_end();
+    match comments.is_empty() {
+        true => writeln!(out, "{text}"),
+        false => writeln!(
+            out,
+            "{text:<COMMENT_COLUMN$} {} {}",
+            syntax.comment(),
+            comments.join(", ")
+        ),
+    }
+}
+///The value at `adress` as written after a literal load, or just the address if it's outside of
+///the image.
+fn literal_comment(image: Image, adress: Adress) -> String {
+    match image.word(adress) {
+        Some(value) => format!("={value:#x}"),
+        None => format!("{:#x}", adress.value()),
+    }
+}
+fn arm_comments(instruction: &ArmInstruction, adress: Adress, image: Image) -> Vec<String> {
+    if let Some(literal) = instruction.literal(adress) {
+        return vec![literal_comment(image, literal)];
+    }
+    // ADD and SUB of an immediate to the PC are how ADR is encoded
+    let pc = adress.arm_pc().value();
+    let computed = match instruction.op {
+        PartialArmInstruction::DataProssessing(DataProssessingInstruction::ADD(
+            GenericDataInstruction {
+                first_operand: Register::R15,
+                shifter: ShifterOperand::Immediate(immediate),
+                ..
+            },
+        )) => Some(pc.wrapping_add(immediate.value())),
+        PartialArmInstruction::DataProssessing(DataProssessingInstruction::SUB(
+            GenericDataInstruction {
+                first_operand: Register::R15,
+                shifter: ShifterOperand::Immediate(immediate),
+                ..
+            },
+        )) => Some(pc.wrapping_sub(immediate.value())),
+        _ => None,
+    };
+    computed
+        .map(|value| format!("{value:#x}"))
+        .into_iter()
+        .collect()
+}
+fn thumb_comments(instruction: &ThumbInstruction, adress: Adress, image: Image) -> Vec<String> {
+    if let Some(literal) = instruction.literal(adress) {
+        return vec![literal_comment(image, literal)];
+    }
+    match instruction {
+        // ADD (5), the ADR of Thumb
+        ThumbInstruction::DataProssessing(ThumbDataProssessingInstruction::ADD(
+            Operands::RegisterImmediate {
+                first_operand: Register::R15,
+                immediate,
+                ..
+            },
+        )) => vec![format!(
+            "{:#x}",
+            adress
+                .thumb_pc()
+                .word_aligned()
+                .value()
+                .wrapping_add(*immediate as u32)
+        )],
+        _ => vec![],
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disassembler::disassemble;
+    use crate::instructions::Architecture;
+
+    #[test]
+    fn tiny_image() {
+        let mut bytes = Vec::new();
+        // add r0, pc, #5, bx r0 and bx lr with a should be one bit clear
+        for word in [0xe28f_0005u32, 0xe12f_ff10, 0xe12f_0f1e] {
+            bytes.extend(word.to_le_bytes());
+        }
+        // ldr r0, [pc, #4], b . and add r0, r1 on two low registers
+        for half in [0x4801u16, 0xe7fe, 0x4408, 0x0000] {
+            bytes.extend(half.to_le_bytes());
+        }
+        bytes.extend(0x1234_5678u32.to_le_bytes());
+        let image = Image {
+            bytes: &bytes,
+            base: Adress::new(0x0200_0000),
+            architecture: Architecture::ARMv5TE,
+        };
+        let lines = disassemble(image, image.base, Mode::Arm);
+        let mut out = Vec::new();
+        write_listing(&mut out, image, &lines, Syntax::Gnu).unwrap();
+        let listing = String::from_utf8(out).unwrap();
+        let expected = [
+            "02000000 <$a>:",
+            " 2000000:  e28f0005   add      r0, pc, #5        @ 0x200000d",
+            " 2000004:  e12fff10   bx       r0",
+            " 2000008:  e12f0f1e   bx       lr                @ should be one bits ignored",
+            "",
+            "0200000c <$t>:",
+            " 200000c:  4801       ldr      r0, [pc, #4]      @ =0x12345678",
+            " 200000e:  e7fe       b        200000e",
+            " 2000010:  4408       add      r0, r1            @ unpredictable",
+            " 2000012:  0000       lsl      r0, r0, #0",
+            " 2000014:  5678       ldrsb    r0, [r7, r1]",
+            " 2000016:  1234       asr      r4, r6, #8",
+        ];
+        assert_eq!(listing.lines().collect::<Vec<_>>(), expected);
+        for line in listing.lines().filter(|line| line.contains('@')) {
+            assert_eq!(line.find('@'), Some(COMMENT_COLUMN + 1));
+        }
+    }
+}
@@ -1,19 +1,40 @@
-#![feature(slice_as_chunks)]
+#![allow(clippy::upper_case_acronyms)]
+mod disassembler;
 mod dsi;
 pub mod errors;
 mod instructions;
+mod listing;
 mod parser;
-use std::path::PathBuf;
-use clap::Parser as ClapParser;
-use tracing::{debug, error};
+mod source;
 use self::errors::DisasemblerError;
+use self::instructions::{Architecture, Syntax};
 use self::parser::Parser;
+use clap::Parser as ClapParser;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use tracing::error;
 #[derive(ClapParser)]
 struct Options {
     #[clap( value_parser = file_exists )]
     file: PathBuf,
     #[clap(long, short, default_value = "false")]
     dsi: bool,
+    ///Disassemble the ARM7 binary of the DSi file instead of the ARM9 one.
+    #[clap(long, default_value = "false")]
+    arm7: bool,
+    ///The architecture to decode for, defaults to the one of the selected core.
+    #[clap(long, value_enum)]
+    arch: Option<Architecture>,
+    ///The assembler syntax of the listing.
+    #[clap(long, value_enum, default_value_t)]
+    syntax: Syntax,
+    ///Write a GNU as source file that reassembles to the binary instead of a listing.
+    #[clap(long, default_value = "false")]
+    source: bool,
+    ///Write the listing to this file instead of stdout.
+    #[clap(long, short)]
+    output: Option<PathBuf>,
 }
 fn file_exists(v: &str) -> Result<PathBuf, String> {
     match std::fs::exists(v) {
@@ -31,10 +52,19 @@ fn file_exists(v: &str) -> Result<PathBuf, String> {
 pub fn main() -> Result<(), DisasemblerError> {
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::DEBUG)
+        .with_writer(std::io::stderr)
         .init();
-    let a: Parser = Options::parse().try_into()?;
+    let options = Options::parse();
+    let output = options.output.clone();
+    let a: Parser = options.try_into()?;
 
-    let v = a.parse()?;
-    println!("{:?}", v);
-    Ok(())
+    match output {
+        Some(path) => {
+            let file = File::create(path).map_err(DisasemblerError::Output)?;
+            let mut out = BufWriter::new(file);
+            a.parse(&mut out)?;
+            out.flush().map_err(DisasemblerError::Output)
+        }
+        None => a.parse(&mut io::stdout().lock()),
+    }
 }
@@ -1,52 +1,70 @@
+use crate::disassembler::{disassemble, Image, Mode};
 use crate::dsi::HeaderNDS;
-use crate::errors::{DisasemblerError, ParseError};
-use crate::instructions::arm::ArmInstruction;
+use crate::errors::DisasemblerError;
+use crate::instructions::{Adress, Architecture, Syntax};
+use crate::listing::write_listing;
+use crate::source::write_source;
 use crate::Options;
+use std::io::Write;
 use std::path::Path;
 use tracing::{debug, warn};
 
 pub struct Parser {
-    pub iter_arm_9: Vec<u32>,
+    pub words: Vec<u32>,
+    pub architecture: Architecture,
+    pub syntax: Syntax,
+    ///Whether to write a source file instead of a listing.
+    pub source: bool,
+    pub arm7: bool,
+    #[allow(dead_code)]
     rest: Vec<u8>,
+    #[allow(dead_code)]
     pub using_little_endian: bool,
     pub header: Option<HeaderNDS>,
 }
 impl Parser {
-    pub fn parse(self) -> Result<Vec<ArmInstruction>, ParseError> {
-        let offset = match self.header {
-            Some(v) => v.arm9_offset,
-            None => 0,
-        } as usize;
-        self.iter_arm_9
-            .into_iter()
-            .enumerate()
-            .map(|(v, e)| {
-                println!("{:#012x}    {:#032b}", v * 4 + offset, e);
-                e
-            })
-            .map(|e| match e.try_into() {
-                Ok(arm) => {
-                    println!("{:?}", arm);
-                    Ok(arm)
-                }
-                Err(e) => Err(e),
-            })
-            .collect()
+    ///Disassembles the binary from its entry point and writes the listing or the source file to
+    ///`out`, raw binaries are loaded at 0 and start there in ARM state.
+    pub fn parse(self, out: &mut impl Write) -> Result<(), DisasemblerError> {
+        let (load, entry) = match self.header {
+            Some(v) if self.arm7 => (v.arm7_load, v.arm7_entry),
+            Some(v) => (v.arm9_load, v.arm9_entry),
+            None => (0, 0),
+        };
+        let bytes: Vec<u8> = self.words.iter().flat_map(|e| e.to_le_bytes()).collect();
+        let image = Image {
+            bytes: &bytes,
+            base: Adress::new(load),
+            architecture: self.architecture,
+        };
+        let lines = disassemble(image, Adress::new(entry), Mode::Arm);
+        match self.source {
+            true => write_source(out, image, &lines, self.syntax),
+            false => write_listing(out, image, &lines, self.syntax),
+        }
+        .map_err(DisasemblerError::Output)
     }
 }
 impl Parser {
-    fn from_dsi(path: &Path) -> Result<Self, DisasemblerError> {
+    fn from_dsi(path: &Path, arm7: bool) -> Result<Self, DisasemblerError> {
         let file = std::fs::read(path).map_err(DisasemblerError::FileError)?;
         if let Some((head, r)) = file.split_first_chunk::<0x1000>() {
             let header = HeaderNDS::from_bytes(*head);
             debug!("header is:{:#?}", header);
             debug!("arm9 offset is:{}", header.arm9_offset);
+            debug!("arm7 offset is:{}", header.arm7_offset);
             debug!("Length of rest:{}", r.len());
-            let iter = Self::from_bin(
-                &r[header.arm9_offset as usize - 0x1000..][..header.arm9_size as usize],
-            )?;
+            let (offset, size) = match arm7 {
+                true => (header.arm7_offset, header.arm7_size),
+                false => (header.arm9_offset, header.arm9_size),
+            };
+            let iter = Self::from_bin(&r[offset as usize - 0x1000..][..size as usize])?;
             Ok(Self {
-                iter_arm_9: iter,
+                words: iter,
+                architecture: Architecture::for_core(arm7),
+                syntax: Syntax::default(),
+                source: false,
+                arm7,
                 rest: r.to_vec(),
                 using_little_endian: true,
                 header: Some(header),
@@ -55,7 +73,11 @@ impl Parser {
             warn!("File was too short for the header, trying to dissasemble it as just binary");
 
             Ok(Self {
-                iter_arm_9: Self::from_bin(&file)?,
+                words: Self::from_bin(&file)?,
+                architecture: Architecture::for_core(arm7),
+                syntax: Syntax::default(),
+                source: false,
+                arm7,
                 rest: vec![],
                 using_little_endian: true,
                 header: None,
@@ -64,15 +86,19 @@ impl Parser {
     }
     fn from_bin(asm: &[u8]) -> Result<Vec<u32>, DisasemblerError> {
         let (i, r) = asm.as_chunks();
-        if r.len() > 0 {
+        if !r.is_empty() {
             return Err(DisasemblerError::UnaligedFile(r.len()));
         }
-        Ok(i.into_iter().map(|e| u32::from_le_bytes(*e)).collect())
+        Ok(i.iter().map(|e| u32::from_le_bytes(*e)).collect())
     }
-    fn from_binary_file(path: &Path) -> Result<Self, DisasemblerError> {
+    fn from_binary_file(path: &Path, arm7: bool) -> Result<Self, DisasemblerError> {
         let file = std::fs::read(path).map_err(DisasemblerError::FileError)?;
         Ok(Self {
-            iter_arm_9: Self::from_bin(&file)?,
+            words: Self::from_bin(&file)?,
+            architecture: Architecture::for_core(arm7),
+            syntax: Syntax::default(),
+            source: false,
+            arm7,
             rest: vec![],
             using_little_endian: true,
             header: None,
@@ -83,9 +109,18 @@ impl TryFrom<Options> for Parser {
     type Error = DisasemblerError;
 
     fn try_from(value: Options) -> Result<Self, Self::Error> {
-        match value.dsi {
-            true => Self::from_dsi(&value.file),
-            false => Self::from_binary_file(&value.file),
+        let parser = match value.dsi {
+            true => Self::from_dsi(&value.file, value.arm7),
+            false => Self::from_binary_file(&value.file, value.arm7),
+        }?;
+        if value.source && value.syntax == Syntax::Armasm {
+            return Err(DisasemblerError::ArmasmSource);
         }
+        Ok(Self {
+            architecture: value.arch.unwrap_or(parser.architecture),
+            syntax: value.syntax,
+            source: value.source,
+            ..parser
+        })
     }
 }
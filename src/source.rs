@@ -0,0 +1,321 @@
+//! Writes the disassembly as a GNU as source file that assembles back to the same bytes.
+//!
+//! Branches refer to generated `sub_` labels for calls and `loc_` labels for other targets.
+//! Targets that aren't the start of an instruction in the image are defined relative to the
+//! start of the image. The linker decides between BL and BLX from the state of the label, so
+//! calls and ARM branches are only written with a label if the label is in the state they
+//! switch to.
+//! Everything that might not reassemble the same is written as data.
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::disassembler::{Image, Instruction, Line, Mode};
+use crate::instructions::arm::branch::BranchInstruction;
+use crate::instructions::arm::register_access_instructions::RegisterAccessInstruction;
+use crate::instructions::arm::unconditional::UnconditionalInstruction;
+use crate::instructions::arm::PartialArmInstruction;
+use crate::instructions::thumb::branch::BranchInstruction as ThumbBranchInstruction;
+use crate::instructions::thumb::ThumbInstruction;
+use crate::instructions::{assembly, Adress, Architecture, Condition, Syntax};
+
+///What a label marks, a target of both calls and other branches is a subroutine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Label {
+    Location,
+    Subroutine,
+}
+impl Label {
+    fn name(self, adress: Adress) -> String {
+        match self {
+            Self::Location => format!("loc_{:08x}", adress.value()),
+            Self::Subroutine => format!("sub_{:08x}", adress.value()),
+        }
+    }
+}
+///A branch to an immediate target.
+struct Branch {
+    target: Adress,
+    label: Label,
+    ///The state at the target, which differs for BLX. `None` for the Thumb branches, which the
+    ///assembler resolves on its own.
+    state: Option<Mode>,
+}
+impl Branch {
+    fn of(line: &Line) -> Option<Self> {
+        let (label, state) = match line.instruction {
+            Instruction::Arm(decoded) => match decoded.instruction.op {
+                PartialArmInstruction::Branch(BranchInstruction::B(_)) => {
+                    (Label::Location, Some(Mode::Arm))
+                }
+                PartialArmInstruction::Branch(BranchInstruction::BL(_)) => {
+                    (Label::Subroutine, Some(Mode::Arm))
+                }
+                PartialArmInstruction::Unconditional(UnconditionalInstruction::BLX(_)) => {
+                    (Label::Subroutine, Some(Mode::Thumb))
+                }
+                _ => return None,
+            },
+            Instruction::Thumb(decoded) => match decoded.instruction {
+                ThumbInstruction::Branch(
+                    ThumbBranchInstruction::BCond { .. } | ThumbBranchInstruction::B(_),
+                ) => (Label::Location, None),
+                ThumbInstruction::Branch(ThumbBranchInstruction::BL(_)) => {
+                    (Label::Subroutine, Some(Mode::Thumb))
+                }
+                ThumbInstruction::Branch(ThumbBranchInstruction::BLXImmediate(_)) => {
+                    (Label::Subroutine, Some(Mode::Arm))
+                }
+                _ => return None,
+            },
+        };
+        let target = match line.instruction {
+            Instruction::Arm(decoded) => decoded.instruction.target(line.adress),
+            Instruction::Thumb(decoded) => decoded.instruction.target(line.adress),
+        }?;
+        Some(Self {
+            target,
+            label,
+            state,
+        })
+    }
+}
+///Writes `lines` decoded from `image` as a source file in `syntax`, which has to be one of the
+///GNU as ones. The text section has to be linked at the base of the image.
+pub fn write_source(
+    out: &mut impl Write,
+    image: Image,
+    lines: &[Line],
+    syntax: Syntax,
+) -> io::Result<()> {
+    let starts: BTreeMap<Adress, Mode> = lines
+        .iter()
+        .map(|line| (line.adress, line.instruction.mode()))
+        .collect();
+    // labels outside of the image or inside an instruction have no state, which is ARM
+    let state = |adress: Adress| starts.get(&adress).copied().unwrap_or(Mode::Arm);
+    let branches: BTreeMap<Adress, Branch> = lines
+        .iter()
+        .filter(|line| reassembles(line))
+        .filter_map(|line| Branch::of(line).map(|branch| (line.adress, branch)))
+        .filter(|(_, branch)| branch.state.is_none_or(|mode| mode == state(branch.target)))
+        .collect();
+    let mut labels = BTreeMap::new();
+    for branch in branches.values() {
+        let label = labels.entry(branch.target).or_insert(branch.label);
+        *label = (*label).max(branch.label);
+    }
+
+    writeln!(
+        out,
+        "@ Link the text section at {:#010x}.",
+        image.base.value()
+    )?;
+    if syntax == Syntax::Unified {
+        writeln!(out, "\t.syntax unified")?;
+    }
+    let arch = match image.architecture {
+        Architecture::ARMv4T => "armv4t",
+        Architecture::ARMv5TE => "armv5te",
+        Architecture::ARMv6 => "armv6",
+    };
+    writeln!(out, "\t.arch\t{arch}")?;
+    writeln!(out, "\t.text")?;
+    writeln!(out, "image_base:")?;
+    let mut mode = None;
+    for line in lines {
+        let current = line.instruction.mode();
+        if mode != Some(current) {
+            match current {
+                Mode::Arm => writeln!(out, "\t.arm")?,
+                Mode::Thumb => writeln!(out, "\t.thumb")?,
+            }
+            mode = Some(current);
+        }
+        if let Some(label) = labels.get(&line.adress) {
+            // gives the symbol the Thumb bit, so the linker keeps BL and BLX as they are
+            if *label == Label::Subroutine && current == Mode::Thumb {
+                writeln!(out, "\t.thumb_func")?;
+            }
+            writeln!(out, "{}:", label.name(line.adress))?;
+        }
+        let (mnemonic, operands) = match line.instruction {
+            Instruction::Arm(decoded) => {
+                assembly(&decoded.instruction, decoded.instruction.cond, syntax)
+            }
+            Instruction::Thumb(decoded) => {
+                assembly(&decoded.instruction, Condition::Allways, syntax)
+            }
+        };
+        let text = match operands.is_empty() {
+            true => mnemonic.clone(),
+            false => format!("{mnemonic}\t{operands}"),
+        };
+        match branches.get(&line.adress) {
+            Some(branch) => {
+                let label = labels[&branch.target].name(branch.target);
+                writeln!(out, "\t{mnemonic}\t{label}")?;
+            }
+            None if Branch::of(line).is_none() && reassembles(line) => writeln!(out, "\t{text}")?,
+            None => {
+                let data = match line.instruction {
+                    Instruction::Arm(_) => format!("{}\t{:#010x}", syntax.word(), line.raw),
+                    Instruction::Thumb(decoded) if decoded.instruction.size() == 4 => format!(
+                        "{}\t{:#06x}, {:#06x}",
+                        syntax.halfword(),
+                        line.raw & 0xffff,
+                        line.raw >> 16
+                    ),
+                    Instruction::Thumb(_) => format!("{}\t{:#06x}", syntax.halfword(), line.raw),
+                };
+                match is_data(line) {
+                    true => writeln!(out, "\t{data}")?,
+                    false => writeln!(out, "\t{data}\t{} {text}", syntax.comment())?,
+                }
+            }
+        }
+    }
+    for (adress, label) in labels.iter() {
+        if !starts.contains_key(adress) {
+            // the offset wraps around like the branch does
+            let offset = adress.value().wrapping_sub(image.base.value()) as i32;
+            let sign = if offset < 0 { '-' } else { '+' };
+            writeln!(
+                out,
+                "\t.equ\t{}, image_base {sign} {:#x}",
+                label.name(*adress),
+                offset.unsigned_abs()
+            )?;
+        }
+    }
+    Ok(())
+}
+///Whether the line is written as data in any case, which is words and halfwords that aren't an
+///instruction, MSR without fields and the halves of BL and BLX (1) on their own.
+fn is_data(line: &Line) -> bool {
+    match line.instruction {
+        Instruction::Arm(decoded) => match decoded.instruction.op {
+            PartialArmInstruction::Undefined(_) => true,
+            // MSR without fields has no syntax
+            PartialArmInstruction::RegisterAccess(RegisterAccessInstruction::MSR {
+                flags, ..
+            }) => flags.is_empty(),
+            _ => false,
+        },
+        Instruction::Thumb(decoded) => {
+            matches!(decoded.instruction, ThumbInstruction::Undefined(_))
+                || decoded.instruction.is_half()
+        }
+    }
+}
+///Whether an assembler gives back the encoding of the instruction on `line`, which isn't the
+///case for data, for encodings that break the rules of the architecture and for encodings that
+///can't be written.
+fn reassembles(line: &Line) -> bool {
+    let (violations, unwritable) = match line.instruction {
+        Instruction::Arm(decoded) => (decoded.violations, false),
+        // ADD, CMP and MOV on high registers can encode two low registers, which ARMv6 allows
+        // but is written the same as the forms for low registers
+        Instruction::Thumb(decoded) => (
+            decoded.violations,
+            matches!(
+                decoded.instruction,
+                ThumbInstruction::DataProssessing(data) if data.is_low_register_operation()
+            ),
+        ),
+    };
+    violations.is_empty() && !unwritable && !is_data(line)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disassembler::disassemble;
+    use crate::instructions::arm::ArmInstruction;
+
+    fn source(bytes: &[u8], syntax: Syntax) -> Vec<String> {
+        let image = Image {
+            bytes,
+            base: Adress::new(0x0200_0000),
+            architecture: Architecture::ARMv5TE,
+        };
+        let lines = disassemble(image, image.base, Mode::Arm);
+        let mut out = Vec::new();
+        write_source(&mut out, image, &lines, syntax).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn small_image() {
+        let mut bytes = Vec::new();
+        // bl 0x2000010, mov r0, #4, 4, an undefined word, b 0x2000ff8, blx 0x2000018 and bx lr
+        for word in [
+            0xeb00_0002u32,
+            0xe3a0_0204,
+            0xe7f0_00f0,
+            0xea00_03f9,
+            0xfa00_0000,
+            0xe12f_ff1e,
+        ] {
+            bytes.extend(word.to_le_bytes());
+        }
+        // add r0, r1 on two low registers, b 0x2000018, the first half of a BL and bx lr
+        for half in [0x4408u16, 0xe7fd, 0xf000, 0x4770] {
+            bytes.extend(half.to_le_bytes());
+        }
+        let expected = [
+            "@ Link the text section at 0x02000000.",
+            "\t.arch\tarmv5te",
+            "\t.text",
+            "image_base:",
+            "\t.arm",
+            "\tbl\tsub_02000010",
+            "\tmov\tr0, #4, 4",
+            "\t.word\t0xe7f000f0",
+            "\tb\tloc_02000ff8",
+            "sub_02000010:",
+            "\tblx\tsub_02000018",
+            "\tbx\tlr",
+            "\t.thumb",
+            "\t.thumb_func",
+            "sub_02000018:",
+            "\t.short\t0x4408\t@ add\tr0, r1",
+            "\tb\tsub_02000018",
+            "\t.short\t0xf000",
+            "\tbx\tlr",
+            "\t.equ\tloc_02000ff8, image_base + 0xff8",
+        ];
+        assert_eq!(source(&bytes, Syntax::Gnu), expected);
+        let unified = source(&bytes, Syntax::Unified);
+        assert_eq!(unified[1], "\t.syntax unified");
+        assert_eq!(unified[7], "\tmov\tr0, #4, #4");
+    }
+
+    #[test]
+    fn unwritable_instructions() {
+        let line = |half: u16| Line {
+            adress: Adress::new(0),
+            raw: half as u32,
+            instruction: Instruction::Thumb(ThumbInstruction::decode(half, Architecture::ARMv6)),
+            reached: true,
+        };
+        // mov r0, r1 as MOV (3) is allowed on ARMv6, but assembles as MOV (2)
+        assert!(!reassembles(&line(0x4608)));
+        // mov r8, r1
+        assert!(reassembles(&line(0x4688)));
+        // msr cpsr, #0x1fc without fields
+        let msr = Line {
+            adress: Adress::new(0),
+            raw: 0xe320_ff7f,
+            instruction: Instruction::Arm(ArmInstruction::decode(
+                0xe320_ff7f,
+                Architecture::ARMv5TE,
+            )),
+            reached: true,
+        };
+        assert!(is_data(&msr));
+        assert!(!reassembles(&msr));
+    }
+}